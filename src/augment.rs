@@ -0,0 +1,409 @@
+//! Per-node bookkeeping for augmented splay trees.
+//!
+//! The collections in this crate take an optional augmentation type parameter
+//! (`()` by default, which costs nothing).
+//! The tree keeps the augmentation of each node up to date while it restructures itself,
+//! and some operations become available depending on what the augmentation tracks.
+//!
+//! # Examples
+//! ```
+//! use splay_tree::SplaySet;
+//! use splay_tree::augment::Count;
+//!
+//! let mut set: SplaySet<_, Count> = vec![30, 10, 20].into_iter().collect();
+//! assert_eq!(set.rank(&20), Some(1));
+//! assert_eq!(set.select(2), Some(&30));
+//! ```
+//!
+//! Augmentations can be combined by pairing them, e.g., `(Count, Fold<M>)`
+//! supports both of the order statistic operations and range folding.
+//!
+//! `Lazy<T, A>` wraps another augmentation and allows to update the entries in a range
+//! with a `Tag` in `O(log n)` amortized time (e.g., `SplayMap::update_range`).
+//! Since the updated values are only brought up to date when they are visited mutably,
+//! the operations which expose the values through `&self` (e.g., `SplayMap::iter`)
+//! are only available for the augmentations which implement `Eager`.
+
+/// Bookkeeping data attached to each node of a splay tree.
+///
+/// The data of a node summarizes the entries in the subtree rooted at the node.
+pub trait Augment<K, V> {
+    /// If `true`, the tree skips all of the maintenance work.
+    const STATELESS: bool = false;
+
+    /// If `true`, the tree calls `push_down` before it visits the children of a node.
+    const LAZY: bool = false;
+
+    /// Makes the data for a node which has no children.
+    fn new(key: &K, value: &V) -> Self;
+
+    /// Recomputes the data of a node from its own entry and the data of its children.
+    fn update(&mut self, key: &K, value: &V, lft: Option<&Self>, rgt: Option<&Self>);
+
+    /// Pushes the pending update of a node down to its children.
+    ///
+    /// Returns `true` if the children of the node have to be swapped
+    /// (i.e., the subtree is reversed).
+    /// This is only meaningful for positional trees such as `SplayVec`.
+    fn push_down(
+        &mut self,
+        _lft: Option<(&K, &mut V, &mut Self)>,
+        _rgt: Option<(&K, &mut V, &mut Self)>,
+    ) -> bool {
+        false
+    }
+}
+impl<K, V> Augment<K, V> for () {
+    const STATELESS: bool = true;
+
+    fn new(_key: &K, _value: &V) -> Self {}
+    fn update(&mut self, _key: &K, _value: &V, _lft: Option<&Self>, _rgt: Option<&Self>) {}
+}
+impl<K, V> Eager<K, V> for () {}
+
+/// Augmentation which never postpones updating the values of the entries.
+///
+/// The values of a collection augmented by such a type are always up to date,
+/// so they can be read through `&self` (e.g., `SplayMap::iter` and `SplayMap::peek_get`).
+/// Every augmentation of this crate but `Lazy` implements this.
+pub trait Eager<K, V>: Augment<K, V> {}
+
+/// Augmentation which knows the number of entries in each subtree.
+///
+/// Collections augmented by such a type support order statistic operations
+/// (e.g., `SplaySet::rank` and `SplaySet::select`).
+pub trait Counted<K, V>: Augment<K, V> {
+    /// Returns the number of entries in the subtree.
+    fn count(&self) -> usize;
+}
+
+/// Augmentation which counts the entries in each subtree.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Count(usize);
+impl<K, V> Augment<K, V> for Count {
+    fn new(_key: &K, _value: &V) -> Self {
+        Count(1)
+    }
+    fn update(&mut self, _key: &K, _value: &V, lft: Option<&Self>, rgt: Option<&Self>) {
+        self.0 = 1 + lft.map_or(0, |c| c.0) + rgt.map_or(0, |c| c.0);
+    }
+}
+impl<K, V> Counted<K, V> for Count {
+    fn count(&self) -> usize {
+        self.0
+    }
+}
+impl<K, V> Eager<K, V> for Count {}
+
+/// A monoid over the entries of a map.
+///
+/// `combine` must be associative, and `identity()` must be its identity element.
+///
+/// # Examples
+/// ```
+/// use splay_tree::SplayMap;
+/// use splay_tree::augment::{Fold, Monoid};
+///
+/// #[derive(Debug, PartialEq)]
+/// struct Max(Option<u32>);
+/// impl<K> Monoid<K, u32> for Max {
+///     fn identity() -> Self {
+///         Max(None)
+///     }
+///     fn lift(_key: &K, value: &u32) -> Self {
+///         Max(Some(*value))
+///     }
+///     fn combine(&self, other: &Self) -> Self {
+///         Max(std::cmp::max(self.0, other.0))
+///     }
+/// }
+///
+/// let mut map: SplayMap<_, _, Fold<Max>> = vec![(1, 30), (2, 10), (3, 20)].into_iter().collect();
+/// assert_eq!(map.fold_range(2..), Max(Some(20)));
+/// assert_eq!(map.fold_range(4..), Max(None));
+/// ```
+pub trait Monoid<K, V> {
+    /// Returns the identity element.
+    fn identity() -> Self;
+
+    /// Makes the element which represents a single entry.
+    fn lift(key: &K, value: &V) -> Self;
+
+    /// Combines two elements (`self` comes before `other` in the key order).
+    fn combine(&self, other: &Self) -> Self;
+}
+
+/// Augmentation which knows the fold of the entries in each subtree.
+///
+/// Collections augmented by such a type support range folding (e.g., `SplayMap::fold_range`).
+pub trait Folded<K, V>: Augment<K, V> {
+    /// The monoid which is used for folding.
+    type Monoid: Monoid<K, V>;
+
+    /// Returns the fold of the entries in the subtree.
+    fn folded(&self) -> &Self::Monoid;
+
+    /// Returns the mutable fold of the entries in the subtree.
+    ///
+    /// This is used to bring the fold up to date when a `Tag` is applied to the subtree.
+    fn folded_mut(&mut self) -> &mut Self::Monoid;
+}
+
+/// Augmentation which folds the entries in each subtree with the monoid `M`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Fold<M>(M);
+impl<K, V, M> Augment<K, V> for Fold<M>
+where
+    M: Monoid<K, V>,
+{
+    fn new(key: &K, value: &V) -> Self {
+        Fold(M::lift(key, value))
+    }
+    fn update(&mut self, key: &K, value: &V, lft: Option<&Self>, rgt: Option<&Self>) {
+        let mut acc = M::lift(key, value);
+        if let Some(lft) = lft {
+            acc = lft.0.combine(&acc);
+        }
+        if let Some(rgt) = rgt {
+            acc = acc.combine(&rgt.0);
+        }
+        self.0 = acc;
+    }
+}
+impl<K, V, M> Folded<K, V> for Fold<M>
+where
+    M: Monoid<K, V>,
+{
+    type Monoid = M;
+    fn folded(&self) -> &M {
+        &self.0
+    }
+    fn folded_mut(&mut self) -> &mut M {
+        &mut self.0
+    }
+}
+impl<K, V, M> Eager<K, V> for Fold<M> where M: Monoid<K, V> {}
+
+/// An update which is applied to all of the entries in a range at once.
+///
+/// `A` is the augmentation wrapped by `Lazy`.
+///
+/// # Examples
+/// ```
+/// use splay_tree::SplayMap;
+/// use splay_tree::augment::{Fold, Folded, Lazy, Monoid, Tag};
+///
+/// #[derive(Debug, PartialEq)]
+/// struct Sum {
+///     sum: i64,
+///     len: i64,
+/// }
+/// impl<K> Monoid<K, i64> for Sum {
+///     fn identity() -> Self {
+///         Sum { sum: 0, len: 0 }
+///     }
+///     fn lift(_key: &K, value: &i64) -> Self {
+///         Sum { sum: *value, len: 1 }
+///     }
+///     fn combine(&self, other: &Self) -> Self {
+///         Sum { sum: self.sum + other.sum, len: self.len + other.len }
+///     }
+/// }
+///
+/// #[derive(Clone)]
+/// struct Add(i64);
+/// impl<K> Tag<K, i64, Fold<Sum>> for Add {
+///     fn apply(&self, _key: &K, value: &mut i64) {
+///         *value += self.0;
+///     }
+///     fn apply_augment(&self, augment: &mut Fold<Sum>) {
+///         let sum = Folded::<K, i64>::folded_mut(augment);
+///         sum.sum += self.0 * sum.len;
+///     }
+///     fn compose(&mut self, later: &Self) {
+///         self.0 += later.0;
+///     }
+/// }
+///
+/// let mut map: SplayMap<_, _, Lazy<Add, Fold<Sum>>> = (0..10).map(|k| (k, 1)).collect();
+/// map.update_range(2..8, Add(10));
+/// assert_eq!(map.fold_range(..).sum, 70);
+/// assert_eq!(map.fold_range(7..).sum, 13);
+/// assert_eq!(map.get(&5), Some(&11));
+/// ```
+pub trait Tag<K, V, A = ()>: Clone {
+    /// Applies the update to a single entry.
+    fn apply(&self, key: &K, value: &mut V);
+
+    /// Applies the update to the augmentation of a subtree.
+    ///
+    /// The augmentation has to be brought up to date without looking into the subtree,
+    /// so, for example, a fold has to keep the number of entries if the update depends on it.
+    /// The default implementation does nothing.
+    fn apply_augment(&self, _augment: &mut A) {}
+
+    /// Merges the update `later` which is applied after `self`.
+    fn compose(&mut self, later: &Self);
+}
+
+/// Augmentation which allows to postpone updating the entries of a subtree.
+///
+/// Collections augmented by such a type support lazy range updates
+/// (e.g., `SplayMap::update_range`).
+pub trait Tagged<K, V>: Augment<K, V> {
+    /// The type of updates.
+    type Tag;
+
+    /// Applies `tag` to a single entry.
+    fn apply(tag: &Self::Tag, key: &K, value: &mut V);
+
+    /// Applies `tag` to all of the entries in the subtree whose root holds `key` and `value`.
+    ///
+    /// Only the root entry is updated immediately, the rest is left to `push_down`.
+    fn tag(&mut self, key: &K, value: &mut V, tag: &Self::Tag);
+}
+
+/// Augmentation which keeps the pending `Tag` of each subtree along with `A`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct Lazy<T, A = ()> {
+    augment: A,
+    pending: Option<T>,
+}
+impl<K, V, T, A> Augment<K, V> for Lazy<T, A>
+where
+    A: Augment<K, V>,
+    T: Tag<K, V, A>,
+{
+    const STATELESS: bool = A::STATELESS;
+    const LAZY: bool = true;
+
+    fn new(key: &K, value: &V) -> Self {
+        Lazy {
+            augment: A::new(key, value),
+            pending: None,
+        }
+    }
+    fn update(&mut self, key: &K, value: &V, lft: Option<&Self>, rgt: Option<&Self>) {
+        self.augment.update(
+            key,
+            value,
+            lft.map(|x| &x.augment),
+            rgt.map(|x| &x.augment),
+        );
+    }
+    fn push_down(
+        &mut self,
+        lft: Option<(&K, &mut V, &mut Self)>,
+        rgt: Option<(&K, &mut V, &mut Self)>,
+    ) -> bool {
+        if let Some(tag) = self.pending.take() {
+            for (key, value, child) in lft.into_iter().chain(rgt) {
+                child.tag(key, value, &tag);
+            }
+        }
+        false
+    }
+}
+impl<K, V, T, A> Tagged<K, V> for Lazy<T, A>
+where
+    A: Augment<K, V>,
+    T: Tag<K, V, A>,
+{
+    type Tag = T;
+
+    fn apply(tag: &T, key: &K, value: &mut V) {
+        tag.apply(key, value);
+    }
+    fn tag(&mut self, key: &K, value: &mut V, tag: &T) {
+        tag.apply(key, value);
+        tag.apply_augment(&mut self.augment);
+        match self.pending {
+            Some(ref mut pending) => pending.compose(tag),
+            None => self.pending = Some(tag.clone()),
+        }
+    }
+}
+impl<K, V, T, A> Counted<K, V> for Lazy<T, A>
+where
+    A: Counted<K, V>,
+    T: Tag<K, V, A>,
+{
+    fn count(&self) -> usize {
+        self.augment.count()
+    }
+}
+impl<K, V, T, A> Folded<K, V> for Lazy<T, A>
+where
+    A: Folded<K, V>,
+    T: Tag<K, V, A>,
+{
+    type Monoid = A::Monoid;
+    fn folded(&self) -> &Self::Monoid {
+        self.augment.folded()
+    }
+    fn folded_mut(&mut self) -> &mut Self::Monoid {
+        self.augment.folded_mut()
+    }
+}
+
+impl<K, V, A, B> Augment<K, V> for (A, B)
+where
+    A: Augment<K, V>,
+    B: Augment<K, V>,
+{
+    const STATELESS: bool = A::STATELESS && B::STATELESS;
+    const LAZY: bool = A::LAZY || B::LAZY;
+
+    fn new(key: &K, value: &V) -> Self {
+        (A::new(key, value), B::new(key, value))
+    }
+    fn update(&mut self, key: &K, value: &V, lft: Option<&Self>, rgt: Option<&Self>) {
+        self.0
+            .update(key, value, lft.map(|x| &x.0), rgt.map(|x| &x.0));
+        self.1
+            .update(key, value, lft.map(|x| &x.1), rgt.map(|x| &x.1));
+    }
+    fn push_down(
+        &mut self,
+        mut lft: Option<(&K, &mut V, &mut Self)>,
+        mut rgt: Option<(&K, &mut V, &mut Self)>,
+    ) -> bool {
+        let flip = self.0.push_down(
+            lft.as_mut().map(|x| (x.0, &mut *x.1, &mut (x.2).0)),
+            rgt.as_mut().map(|x| (x.0, &mut *x.1, &mut (x.2).0)),
+        );
+        let flip_again = self.1.push_down(
+            lft.as_mut().map(|x| (x.0, &mut *x.1, &mut (x.2).1)),
+            rgt.as_mut().map(|x| (x.0, &mut *x.1, &mut (x.2).1)),
+        );
+        flip != flip_again
+    }
+}
+impl<K, V, A, B> Counted<K, V> for (A, B)
+where
+    A: Counted<K, V>,
+    B: Augment<K, V>,
+{
+    fn count(&self) -> usize {
+        self.0.count()
+    }
+}
+impl<K, V, A, B> Folded<K, V> for (A, B)
+where
+    A: Augment<K, V>,
+    B: Folded<K, V>,
+{
+    type Monoid = B::Monoid;
+    fn folded(&self) -> &Self::Monoid {
+        self.1.folded()
+    }
+    fn folded_mut(&mut self) -> &mut Self::Monoid {
+        self.1.folded_mut()
+    }
+}
+impl<K, V, A, B> Eager<K, V> for (A, B)
+where
+    A: Eager<K, V>,
+    B: Eager<K, V>,
+{
+}
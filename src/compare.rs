@@ -0,0 +1,90 @@
+//! Comparators which determine the order of keys.
+//!
+//! The collections in this crate order their keys by `Ord` (i.e., `Natural`) by default,
+//! but any other comparator can be given instead, including closures.
+//!
+//! # Examples
+//! ```
+//! use splay_tree::SplaySet;
+//!
+//! let mut set = SplaySet::with_comparator(|a: &f64, b: &f64| a.partial_cmp(b).unwrap());
+//! set.insert(2.5);
+//! set.insert(-1.0);
+//! set.insert(0.5);
+//! assert_eq!(set.into_iter().collect::<Vec<_>>(), [-1.0, 0.5, 2.5]);
+//! ```
+use std::cmp::Ordering;
+
+/// A comparator which determines the order of keys.
+///
+/// It must be a total order, and must not change while it is used by a collection.
+pub trait Compare<T: ?Sized> {
+    /// Compares two keys.
+    fn compare(&self, a: &T, b: &T) -> Ordering;
+}
+impl<T: ?Sized, F> Compare<T> for F
+where
+    F: Fn(&T, &T) -> Ordering,
+{
+    fn compare(&self, a: &T, b: &T) -> Ordering {
+        self(a, b)
+    }
+}
+
+/// The comparator which orders keys by `Ord`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Natural;
+impl<T: ?Sized + Ord> Compare<T> for Natural {
+    fn compare(&self, a: &T, b: &T) -> Ordering {
+        a.cmp(b)
+    }
+}
+
+/// The comparator which reverses the order of another comparator (`Natural` by default).
+///
+/// # Examples
+/// ```
+/// use splay_tree::SplaySet;
+/// use splay_tree::compare::Reverse;
+///
+/// let set: SplaySet<_, (), Reverse> = vec![1, 3, 2].into_iter().collect();
+/// assert_eq!(set.into_iter().collect::<Vec<_>>(), [3, 2, 1]);
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Reverse<C = Natural>(pub C);
+impl<T: ?Sized, C> Compare<T> for Reverse<C>
+where
+    C: Compare<T>,
+{
+    fn compare(&self, a: &T, b: &T) -> Ordering {
+        self.0.compare(b, a)
+    }
+}
+
+/// The comparator which orders keys by `Ord` on the values extracted from them.
+///
+/// The function is called once for each of the two keys on every comparison,
+/// so it should be cheap (e.g., reading a field).
+///
+/// # Examples
+/// ```
+/// use splay_tree::SplaySet;
+/// use splay_tree::compare::ByKey;
+///
+/// let mut set = SplaySet::with_comparator(ByKey(|s: &&str| s.len()));
+/// set.insert("foo");
+/// set.insert("a");
+/// set.insert("ba");
+/// assert_eq!(set.into_iter().collect::<Vec<_>>(), ["a", "ba", "foo"]);
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ByKey<F>(pub F);
+impl<T: ?Sized, K, F> Compare<T> for ByKey<F>
+where
+    F: Fn(&T) -> K,
+    K: Ord,
+{
+    fn compare(&self, a: &T, b: &T) -> Ordering {
+        (self.0)(a).cmp(&(self.0)(b))
+    }
+}
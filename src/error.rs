@@ -0,0 +1,92 @@
+//! Error types.
+use std::collections;
+#[cfg(feature = "std")]
+use std::error;
+use std::fmt;
+
+/// The error type for `try_reserve`, `try_insert` and `try_push` methods.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TryReserveError {
+    /// The requested capacity exceeds the limit of the index type of the collection
+    /// (see [`Index`](../index/trait.Index.html)).
+    CapacityOverflow,
+
+    /// The allocator failed to allocate the nodes.
+    AllocError(collections::TryReserveError),
+}
+impl fmt::Display for TryReserveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            TryReserveError::CapacityOverflow => {
+                write!(f, "capacity exceeds the limit of the index type")
+            }
+            TryReserveError::AllocError(ref e) => write!(f, "{}", e),
+        }
+    }
+}
+#[cfg(feature = "std")]
+impl error::Error for TryReserveError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match *self {
+            TryReserveError::CapacityOverflow => None,
+            TryReserveError::AllocError(ref e) => Some(e),
+        }
+    }
+}
+
+/// The error type for `check_invariants` methods, which describes how the tree is corrupted.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InvariantError {
+    /// The root or a child link points outside of the arena.
+    LinkOutOfBounds {
+        /// The slot pointed to by the link.
+        link: usize,
+        /// The number of nodes in the arena.
+        len: usize,
+    },
+
+    /// A node is reachable from the root more than once
+    /// (i.e., the links form a cycle, or the node has two parents).
+    Revisited {
+        /// The slot of the node.
+        node: usize,
+    },
+
+    /// Some nodes in the arena are not reachable from the root.
+    Unreachable {
+        /// The number of nodes reachable from the root.
+        reachable: usize,
+        /// The number of nodes in the arena.
+        len: usize,
+    },
+
+    /// The key of a node is not greater than the key of its in-order predecessor.
+    Unordered {
+        /// The slot of the predecessor.
+        prev: usize,
+        /// The slot of the node.
+        node: usize,
+    },
+}
+impl fmt::Display for InvariantError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            InvariantError::LinkOutOfBounds { link, len } => {
+                write!(f, "a link points to the slot {} outside of the arena of {} nodes", link, len)
+            }
+            InvariantError::Revisited { node } => {
+                write!(f, "the node at the slot {} is reachable from the root more than once", node)
+            }
+            InvariantError::Unreachable { reachable, len } => {
+                write!(f, "only {} of the {} nodes are reachable from the root", reachable, len)
+            }
+            InvariantError::Unordered { prev, node } => write!(
+                f,
+                "the key at the slot {} is not greater than the key of its predecessor at the slot {}",
+                node, prev
+            ),
+        }
+    }
+}
+#[cfg(feature = "std")]
+impl error::Error for InvariantError {}
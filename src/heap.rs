@@ -3,43 +3,122 @@ use std;
 use std::cmp;
 use tree_core;
 use iter;
+use compare::{ByKey, Compare, Natural, Reverse};
+use index::Index;
+use error::{InvariantError, TryReserveError};
+use policy::SplayPolicy;
 
 /// `SplayHeap` iterator.
-pub struct Iter<'a, T: 'a> {
-    iter: iter::Iter<'a, Item<T>, ()>,
+pub struct Iter<'a, T: 'a, I: 'a = u32> {
+    iter: iter::Iter<'a, Item<T>, (), (), I>,
 }
-impl<'a, T: 'a> Iter<'a, T> {
-    fn new(tree: &'a tree_core::Tree<Item<T>, ()>) -> Self {
+impl<'a, T: 'a, I: 'a> Iter<'a, T, I>
+where
+    I: Index,
+{
+    fn new<C>(tree: &'a tree_core::Tree<Item<T>, (), (), C, I>) -> Self {
         Iter { iter: tree.iter() }
     }
 }
-impl<'a, T: 'a> Iterator for Iter<'a, T> {
+impl<'a, T: 'a, I: 'a> Iterator for Iter<'a, T, I>
+where
+    I: Index,
+{
     type Item = &'a T;
     fn next(&mut self) -> Option<Self::Item> {
         self.iter.next().map(|(i, _)| &i.0)
     }
 }
+impl<'a, T: 'a, I: 'a> DoubleEndedIterator for Iter<'a, T, I>
+where
+    I: Index,
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.iter.next_back().map(|(i, _)| &i.0)
+    }
+}
 
 /// An iterator that moves out of a `SplayHeap`.
-pub struct IntoIter<T>(iter::IntoIter<Item<T>, ()>);
-impl<T> Iterator for IntoIter<T> {
+pub struct IntoIter<T, I = u32>(iter::IntoIter<Item<T>, (), (), I>)
+where
+    I: Index;
+impl<T, I> Iterator for IntoIter<T, I>
+where
+    I: Index,
+{
     type Item = T;
     fn next(&mut self) -> Option<Self::Item> {
         self.0.next().map(|(k, _)| k.0)
     }
 }
+impl<T, I> DoubleEndedIterator for IntoIter<T, I>
+where
+    I: Index,
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.0.next_back().map(|(k, _)| k.0)
+    }
+}
 
-#[derive(Debug, Clone, PartialEq, Eq, PartialOrd)]
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+/// An iterator which removes the items of a `SplayHeap` matching a predicate.
+pub struct ExtractIf<'a, T: 'a, C: 'a, I: 'a, F>
+where
+    I: Index,
+    F: FnMut(&T) -> bool,
+{
+    inner: tree_core::Extract<'a, Item<T>, (), (), ItemCmp<C>, I>,
+    pred: F,
+}
+impl<'a, T: 'a, C: 'a, I: 'a, F> Iterator for ExtractIf<'a, T, C, I, F>
+where
+    I: Index,
+    F: FnMut(&T) -> bool,
+{
+    type Item = T;
+    fn next(&mut self) -> Option<Self::Item> {
+        let pred = &mut self.pred;
+        self.inner.next_by(|i, _| pred(&i.0)).map(|(i, _)| i.0)
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+/// A draining iterator over the items of a `SplayHeap`.
+pub struct Drain<T, I = u32>(iter::IntoIter<Item<T>, (), (), I>)
+where
+    I: Index;
+impl<T, I> Iterator for Drain<T, I>
+where
+    I: Index,
+{
+    type Item = T;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(|(k, _)| k.0)
+    }
+}
+impl<T, I> DoubleEndedIterator for Drain<T, I>
+where
+    I: Index,
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.0.next_back().map(|(k, _)| k.0)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
 struct Item<T>(T, u64);
 
-impl<T> Ord for Item<T>
+/// Orders the items from the greatest to the least, and the equal ones by their insertion order.
+#[derive(Debug, Clone, Default)]
+struct ItemCmp<C>(C);
+impl<T, C> Compare<Item<T>> for ItemCmp<C>
 where
-    T: Ord,
+    C: Compare<T>,
 {
-    fn cmp(&self, other: &Self) -> cmp::Ordering {
-        match self.0.cmp(&other.0) {
-            cmp::Ordering::Equal => self.1.cmp(&other.1),
+    fn compare(&self, a: &Item<T>, b: &Item<T>) -> cmp::Ordering {
+        match self.0.compare(&a.0, &b.0) {
+            cmp::Ordering::Equal => a.1.cmp(&b.1),
             cmp::Ordering::Less => cmp::Ordering::Greater,
             cmp::Ordering::Greater => cmp::Ordering::Less,
         }
@@ -48,14 +127,17 @@ where
 
 /// A priority queue implemented with a splay tree.
 ///
-/// This will be a max-heap.
+/// This will be a max-heap by default.
+/// `SplayHeap::new_min` makes a min-heap,
+/// and `with_comparator` and `with_key` make a heap ordered by any other criterion.
+/// The items equal to each other are always popped in insertion order.
 ///
 /// A splay tree based heap is a self-adjusting data structure.
 /// It performs pushing and popping in `O(log n)` amortized time.
 ///
 /// It is a logic error for a key to be modified in such a way that
 /// the key's ordering relative to any other key,
-/// as determined by the `Ord` trait, changes while it is in the map.
+/// as determined by the comparator (the `Ord` trait by default), changes while it is in the map.
 /// This is normally only possible through `Cell`, `RefCell`, global state, I/O, or unsafe code.
 ///
 /// # Examples
@@ -75,9 +157,8 @@ where
 /// assert_eq!(heap.pop(), None);
 /// ```
 #[derive(Debug, Clone)]
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-pub struct SplayHeap<T> {
-    tree: tree_core::Tree<Item<T>, ()>,
+pub struct SplayHeap<T, C = Natural, I = u32> {
+    tree: tree_core::Tree<Item<T>, (), (), ItemCmp<C>, I>,
     seq: u64,
 }
 impl<T> SplayHeap<T>
@@ -95,12 +176,112 @@ where
     /// assert_eq!(heap.pop(), Some(10));
     /// ```
     pub fn new() -> Self {
+        SplayHeap::with_comparator(Natural)
+    }
+
+    /// Makes a new empty `SplayHeap` which can hold at least `capacity` items
+    /// without reallocating.
+    ///
+    /// # Examples
+    /// ```
+    /// use splay_tree::SplayHeap;
+    ///
+    /// let mut heap = SplayHeap::with_capacity(10);
+    /// heap.push(1);
+    /// assert!(heap.capacity() >= 10);
+    /// ```
+    pub fn with_capacity(capacity: usize) -> Self {
+        let mut heap = SplayHeap::new();
+        heap.reserve(capacity);
+        heap
+    }
+}
+impl<T> SplayHeap<T, Reverse>
+where
+    T: Ord,
+{
+    /// Creates an empty `SplayHeap` as a min-heap.
+    ///
+    /// # Examples
+    /// ```
+    /// use splay_tree::SplayHeap;
+    /// let mut heap = SplayHeap::new_min();
+    ///
+    /// heap.push(3);
+    /// heap.push(1);
+    /// heap.push(2);
+    /// assert_eq!(heap.peek(), Some(&1));
+    /// assert_eq!(heap.pop(), Some(1));
+    /// assert_eq!(heap.pop(), Some(2));
+    /// ```
+    pub fn new_min() -> Self {
+        SplayHeap::with_comparator(Reverse(Natural))
+    }
+}
+impl<T, K, F> SplayHeap<T, ByKey<F>>
+where
+    F: Fn(&T) -> K,
+    K: Ord,
+{
+    /// Creates an empty `SplayHeap` which orders its items by the keys extracted by `f`.
+    ///
+    /// The heap pops the item with the greatest key first,
+    /// and the items with equal keys in insertion order.
+    /// A heap which pops the least key first is made by
+    /// `SplayHeap::with_comparator(Reverse(ByKey(f)))`.
+    ///
+    /// # Examples
+    /// ```
+    /// use splay_tree::SplayHeap;
+    /// use splay_tree::compare::{ByKey, Reverse};
+    ///
+    /// let mut heap = SplayHeap::with_key(|job: &(u8, &str)| job.0);
+    /// heap.push((1, "foo"));
+    /// heap.push((2, "bar"));
+    /// heap.push((1, "baz"));
+    /// assert_eq!(heap.into_iter().collect::<Vec<_>>(), [(2, "bar"), (1, "foo"), (1, "baz")]);
+    ///
+    /// let mut heap = SplayHeap::with_comparator(Reverse(ByKey(|job: &(u8, &str)| job.0)));
+    /// heap.push((2, "foo"));
+    /// heap.push((1, "bar"));
+    /// heap.push((2, "baz"));
+    /// assert_eq!(heap.into_iter().collect::<Vec<_>>(), [(1, "bar"), (2, "foo"), (2, "baz")]);
+    /// ```
+    pub fn with_key(f: F) -> Self {
+        SplayHeap::with_comparator(ByKey(f))
+    }
+}
+impl<T, C> SplayHeap<T, C>
+where
+    C: Compare<T>,
+{
+    /// Creates an empty `SplayHeap` which orders its items by the given comparator.
+    ///
+    /// The heap pops the greatest item according to `cmp` first.
+    ///
+    /// # Examples
+    /// ```
+    /// use splay_tree::SplayHeap;
+    /// let mut heap = SplayHeap::with_comparator(|a: &i32, b: &i32| b.cmp(a));
+    ///
+    /// heap.push(3);
+    /// heap.push(1);
+    /// heap.push(2);
+    /// assert_eq!(heap.pop(), Some(1));
+    /// assert_eq!(heap.pop(), Some(2));
+    /// ```
+    pub fn with_comparator(cmp: C) -> Self {
         SplayHeap {
-            tree: tree_core::Tree::new(),
+            tree: tree_core::Tree::with_comparator(ItemCmp(cmp)),
             seq: 0,
         }
     }
-
+}
+impl<T, C, I> SplayHeap<T, C, I>
+where
+    C: Compare<T>,
+    I: Index,
+{
     /// Returns the greatest item in the heap, or `None` if it is empty.
     ///
     /// # NOTICE
@@ -119,7 +300,7 @@ where
     /// assert_eq!(heap.peek(), Some(&5));
     /// ```
     pub fn peek(&mut self) -> Option<&T> {
-        self.tree.get_lftmost().map(|(i, _)| &i.0)
+        self.tree.lookup_lftmost().map(|(i, _)| &i.0)
     }
 
     /// Removes the greatest item from the heap and returns it, or `None` if it is empty.
@@ -137,6 +318,72 @@ where
         self.tree.take_lftmost().map(|(i, _)| i.0)
     }
 
+    /// Returns the greatest item in the heap, or `None` if it is empty.
+    ///
+    /// This is the same as `peek`.
+    ///
+    /// # Examples
+    /// ```
+    /// use splay_tree::SplayHeap;
+    /// let mut heap: SplayHeap<_> = vec![1, 3, 2].into_iter().collect();
+    ///
+    /// assert_eq!(heap.peek_max(), Some(&3));
+    /// ```
+    pub fn peek_max(&mut self) -> Option<&T> {
+        self.peek()
+    }
+
+    /// Removes the greatest item from the heap and returns it, or `None` if it is empty.
+    ///
+    /// This is the same as `pop`.
+    ///
+    /// # Examples
+    /// ```
+    /// use splay_tree::SplayHeap;
+    /// let mut heap: SplayHeap<_> = vec![1, 3, 2].into_iter().collect();
+    ///
+    /// assert_eq!(heap.pop_max(), Some(3));
+    /// assert_eq!(heap.pop_max(), Some(2));
+    /// ```
+    pub fn pop_max(&mut self) -> Option<T> {
+        self.pop()
+    }
+
+    /// Returns the least item in the heap, or `None` if it is empty.
+    ///
+    /// If several items are equal to the least one, the one pushed last is returned
+    /// (i.e., the items are popped from this end in the exact reverse order of `pop`).
+    ///
+    /// # Examples
+    /// ```
+    /// use splay_tree::SplayHeap;
+    /// let mut heap: SplayHeap<_> = vec![1, 3, 2].into_iter().collect();
+    ///
+    /// assert_eq!(heap.peek_min(), Some(&1));
+    /// assert_eq!(heap.peek_max(), Some(&3));
+    /// ```
+    pub fn peek_min(&mut self) -> Option<&T> {
+        self.tree.lookup_rgtmost().map(|(i, _)| &i.0)
+    }
+
+    /// Removes the least item from the heap and returns it, or `None` if it is empty.
+    ///
+    /// If several items are equal to the least one, the one pushed last is removed.
+    ///
+    /// # Examples
+    /// ```
+    /// use splay_tree::SplayHeap;
+    /// let mut heap: SplayHeap<_> = vec![1, 3, 2].into_iter().collect();
+    ///
+    /// assert_eq!(heap.pop_min(), Some(1));
+    /// assert_eq!(heap.pop_min(), Some(2));
+    /// assert_eq!(heap.pop_max(), Some(3));
+    /// assert_eq!(heap.pop_min(), None);
+    /// ```
+    pub fn pop_min(&mut self) -> Option<T> {
+        self.tree.take_rgtmost().map(|(i, _)| i.0)
+    }
+
     /// Pushes an item onto the heap.
     ///
     /// # Examples
@@ -156,6 +403,56 @@ where
         self.tree.insert(Item(item, seq), ());
     }
 
+    /// Pushes an item onto the heap in the same way as `push`,
+    /// but returns an error instead of panicking or aborting
+    /// if the heap cannot hold one more item.
+    ///
+    /// The heap is left unchanged in that case.
+    ///
+    /// # Examples
+    /// ```
+    /// use splay_tree::SplayHeap;
+    /// use splay_tree::compare::Natural;
+    /// use splay_tree::error::TryReserveError;
+    ///
+    /// let mut heap: SplayHeap<u8, Natural, u16> = (0..65535).map(|_| 1).collect();
+    /// assert_eq!(heap.try_push(2), Err(TryReserveError::CapacityOverflow));
+    /// assert_eq!(heap.peek(), Some(&1));
+    /// ```
+    pub fn try_push(&mut self, item: T) -> Result<(), TryReserveError> {
+        self.tree.try_reserve(1)?;
+        self.push(item);
+        Ok(())
+    }
+
+    /// Pushes an item onto the heap, and then removes and returns the least item
+    /// if the heap holds more than `capacity` items.
+    ///
+    /// The evicted item may be `item` itself,
+    /// which is the case if it is less than or equal to every other item.
+    /// At most one item is evicted, so the heap keeps the `capacity` greatest items
+    /// as long as it is only pushed by this method with the same `capacity`.
+    ///
+    /// # Examples
+    /// ```
+    /// use splay_tree::SplayHeap;
+    /// let mut heap = SplayHeap::new();
+    ///
+    /// assert_eq!(heap.push_bounded(3, 2), None);
+    /// assert_eq!(heap.push_bounded(1, 2), None);
+    /// assert_eq!(heap.push_bounded(5, 2), Some(1));
+    /// assert_eq!(heap.push_bounded(2, 2), Some(2));
+    /// assert_eq!(heap.into_iter().collect::<Vec<_>>(), [5, 3]);
+    /// ```
+    pub fn push_bounded(&mut self, item: T, capacity: usize) -> Option<T> {
+        self.push(item);
+        if self.len() > capacity {
+            self.pop_min()
+        } else {
+            None
+        }
+    }
+
     /// Drops all items from the heap.
     ///
     /// # Examples
@@ -168,10 +465,97 @@ where
     /// assert!(heap.is_empty());
     /// ```
     pub fn clear(&mut self) {
-        self.tree = tree_core::Tree::new();
+        self.tree.clear();
+    }
+
+    /// Retains only the items specified by the predicate.
+    ///
+    /// In other words, removes all items `x` such that `f(&x)` returns `false`.
+    /// The items are visited in sorted (descending) order, in a single pass over the tree.
+    ///
+    /// # Examples
+    /// ```
+    /// use splay_tree::SplayHeap;
+    /// let mut heap: SplayHeap<_> = (0..8).collect();
+    ///
+    /// heap.retain(|&x| x % 2 == 0);
+    /// assert_eq!(heap.into_iter().collect::<Vec<_>>(), [6, 4, 2, 0]);
+    /// ```
+    pub fn retain<F>(&mut self, mut f: F)
+    where
+        F: FnMut(&T) -> bool,
+    {
+        self.extract_if(|x| !f(x)).for_each(drop);
+    }
+
+    /// Creates an iterator that visits all items in sorted (descending) order,
+    /// and uses a closure to determine if an item should be removed.
+    ///
+    /// If the closure returns `true`, the item is removed from the heap and yielded.
+    /// If the closure returns `false`, the item remains in the heap and will not be yielded.
+    ///
+    /// If the iterator is dropped before being fully consumed,
+    /// it retains the remaining items.
+    ///
+    /// # Examples
+    /// ```
+    /// use splay_tree::SplayHeap;
+    /// let mut heap: SplayHeap<_> = (0..8).collect();
+    ///
+    /// assert_eq!(heap.extract_if(|x| x % 3 == 0).collect::<Vec<_>>(), [6, 3, 0]);
+    /// assert_eq!(heap.pop(), Some(7));
+    /// ```
+    pub fn extract_if<F>(&mut self, pred: F) -> ExtractIf<'_, T, C, I, F>
+    where
+        F: FnMut(&T) -> bool,
+    {
+        ExtractIf {
+            inner: self.tree.extract(),
+            pred,
+        }
+    }
+
+    /// Clears the heap, returning all items as an iterator in sorted (descending) order.
+    ///
+    /// The heap is emptied even if the iterator is not fully consumed.
+    ///
+    /// # Examples
+    /// ```
+    /// use splay_tree::SplayHeap;
+    /// let mut heap: SplayHeap<_> = vec![1, 3, 2].into_iter().collect();
+    ///
+    /// assert_eq!(heap.drain().collect::<Vec<_>>(), [3, 2, 1]);
+    /// assert!(heap.is_empty());
+    /// ```
+    pub fn drain(&mut self) -> Drain<T, I> {
+        Drain(self.tree.drain())
+    }
+
+    /// Checks the internal structure of the heap, and returns an error describing the corruption if any.
+    ///
+    /// This verifies that the items are in order (the items equal to each other are ordered by insertion), and that every node in the arena
+    /// is reachable from the root exactly once (i.e., the links have no cycles).
+    /// It takes linear time, and is intended for debugging.
+    ///
+    /// If the `check-invariants` feature is enabled, this is run after every mutating operation
+    /// in debug builds, which panic on a corruption.
+    ///
+    /// # Examples
+    /// ```
+    /// use splay_tree::SplayHeap;
+    ///
+    /// let mut heap: SplayHeap<_> = (0..100).map(|i| i % 10).collect();
+    /// heap.pop();
+    /// assert_eq!(heap.check_invariants(), Ok(()));
+    /// ```
+    pub fn check_invariants(&self) -> Result<(), InvariantError> {
+        self.tree.check_invariants()
     }
 }
-impl<T> SplayHeap<T> {
+impl<T, C, I> SplayHeap<T, C, I>
+where
+    I: Index,
+{
     /// Returns an iterator visiting all items in sorted (descending) order.
     ///
     /// # Examples
@@ -184,7 +568,7 @@ impl<T> SplayHeap<T> {
     ///   println!("{}", x);
     /// }
     /// ```
-    pub fn iter(&self) -> Iter<T> {
+    pub fn iter(&self) -> Iter<T, I> {
         Iter::new(&self.tree)
     }
 
@@ -219,64 +603,252 @@ impl<T> SplayHeap<T> {
     pub fn is_empty(&self) -> bool {
         self.len() == 0
     }
+
+    /// Returns the number of items the heap can hold without reallocating.
+    ///
+    /// # Examples
+    /// ```
+    /// use splay_tree::SplayHeap;
+    ///
+    /// let heap: SplayHeap<u8> = SplayHeap::with_capacity(10);
+    /// assert!(heap.capacity() >= 10);
+    /// ```
+    pub fn capacity(&self) -> usize {
+        self.tree.capacity()
+    }
+
+    /// Reserves capacity for at least `additional` more items to be inserted in the heap.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the new capacity exceeds the limit of the index type
+    /// or overflows `usize`.
+    ///
+    /// # Examples
+    /// ```
+    /// use splay_tree::SplayHeap;
+    ///
+    /// let mut heap = SplayHeap::new();
+    /// heap.push(1);
+    /// heap.reserve(10);
+    /// assert!(heap.capacity() >= 11);
+    /// ```
+    pub fn reserve(&mut self, additional: usize) {
+        self.tree.reserve(additional);
+    }
+
+    /// Tries to reserve capacity for at least `additional` more items to be inserted in the heap.
+    ///
+    /// Unlike `reserve`, this returns an error instead of panicking or aborting
+    /// if the capacity exceeds the limit of the index type or the allocator fails.
+    ///
+    /// # Examples
+    /// ```
+    /// use splay_tree::SplayHeap;
+    /// use splay_tree::compare::Natural;
+    /// use splay_tree::error::TryReserveError;
+    ///
+    /// let mut heap: SplayHeap<u8, Natural, u16> = SplayHeap::default();
+    /// assert_eq!(heap.try_reserve(10), Ok(()));
+    /// assert!(heap.capacity() >= 10);
+    /// assert_eq!(heap.try_reserve(65536), Err(TryReserveError::CapacityOverflow));
+    /// ```
+    pub fn try_reserve(&mut self, additional: usize) -> Result<(), TryReserveError> {
+        self.tree.try_reserve(additional)
+    }
+
+    /// Shrinks the capacity of the heap as much as possible.
+    ///
+    /// # Examples
+    /// ```
+    /// use splay_tree::SplayHeap;
+    ///
+    /// let mut heap = SplayHeap::with_capacity(100);
+    /// heap.push(1);
+    /// heap.shrink_to_fit();
+    /// assert!(heap.capacity() < 100);
+    /// ```
+    pub fn shrink_to_fit(&mut self) {
+        self.tree.shrink_to_fit();
+    }
+
+    /// Returns the policy which decides how the lookups restructure the heap.
+    ///
+    /// # Examples
+    /// ```
+    /// use splay_tree::SplayHeap;
+    /// use splay_tree::policy::SplayPolicy;
+    ///
+    /// let heap: SplayHeap<u8> = SplayHeap::new();
+    /// assert_eq!(heap.splay_policy(), SplayPolicy::Full);
+    /// ```
+    pub fn splay_policy(&self) -> SplayPolicy {
+        self.tree.policy()
+    }
+
+    /// Sets the policy which decides how the lookups restructure the heap.
+    ///
+    /// The policy applies to `peek`, `peek_max` and `peek_min`.
+    /// See [`policy`](../policy/index.html) for details.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the probability of `SplayPolicy::Probability` is not in the range from 0 to 1.
+    ///
+    /// # Examples
+    /// ```
+    /// use splay_tree::SplayHeap;
+    /// use splay_tree::policy::SplayPolicy;
+    ///
+    /// let mut heap = (0..10).collect::<SplayHeap<_>>();
+    /// heap.set_splay_policy(SplayPolicy::Threshold(2));
+    /// assert_eq!(heap.peek(), Some(&9));
+    /// assert_eq!(heap.pop(), Some(9));
+    /// assert_eq!(heap.peek(), Some(&8));
+    /// ```
+    pub fn set_splay_policy(&mut self, policy: SplayPolicy) {
+        self.tree.set_policy(policy);
+    }
 }
-impl<T> Default for SplayHeap<T>
+impl<T, C, I> Default for SplayHeap<T, C, I>
 where
-    T: Ord,
+    C: Compare<T> + Default,
+    I: Index,
 {
     fn default() -> Self {
-        SplayHeap::new()
+        SplayHeap {
+            tree: tree_core::Tree::new(),
+            seq: 0,
+        }
     }
 }
-impl<T> std::iter::FromIterator<T> for SplayHeap<T>
+impl<T, C, I> std::iter::FromIterator<T> for SplayHeap<T, C, I>
 where
-    T: Ord,
+    C: Compare<T> + Default,
+    I: Index,
 {
-    fn from_iter<I>(iter: I) -> Self
+    fn from_iter<It>(iter: It) -> Self
     where
-        I: IntoIterator<Item = T>,
+        It: IntoIterator<Item = T>,
     {
-        let mut heap = SplayHeap::new();
-        for x in iter {
-            heap.push(x);
-        }
+        let mut heap = SplayHeap::default();
+        heap.extend(iter);
         heap
     }
 }
-impl<T> IntoIterator for SplayHeap<T> {
+#[cfg(feature = "serde")]
+impl<T, C, I> ::serde::Serialize for SplayHeap<T, C, I>
+where
+    T: ::serde::Serialize,
+    C: Compare<T>,
+    I: Index,
+{
+    /// Serializes the heap as a sequence of the items in the order they would be popped.
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: ::serde::Serializer,
+    {
+        use serde::ser::SerializeSeq;
+        let mut seq = serializer.serialize_seq(Some(self.len()))?;
+        for (item, _) in self.tree.iter() {
+            seq.serialize_element(&item.0)?;
+        }
+        seq.end()
+    }
+}
+#[cfg(feature = "serde")]
+impl<'de, T, C, I> ::serde::Deserialize<'de> for SplayHeap<T, C, I>
+where
+    T: ::serde::Deserialize<'de>,
+    C: Compare<T> + Default,
+    I: Index,
+{
+    /// Deserializes a sequence of the items, which are pushed onto the heap in order.
+    ///
+    /// The comparator is made by `Default`.
+    /// Fails if the number of the items exceeds the limit of the index type.
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: ::serde::Deserializer<'de>,
+    {
+        struct Visitor<T, C, I>(std::marker::PhantomData<SplayHeap<T, C, I>>);
+        impl<'de, T, C, I> ::serde::de::Visitor<'de> for Visitor<T, C, I>
+        where
+            T: ::serde::Deserialize<'de>,
+            C: Compare<T> + Default,
+            I: Index,
+        {
+            type Value = SplayHeap<T, C, I>;
+
+            fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                f.write_str("a sequence")
+            }
+            fn visit_seq<M>(self, mut access: M) -> Result<Self::Value, M::Error>
+            where
+                M: ::serde::de::SeqAccess<'de>,
+            {
+                use std::vec::Vec;
+                let mut items = Vec::with_capacity(cmp::min(access.size_hint().unwrap_or(0), 4096));
+                while let Some(item) = access.next_element()? {
+                    if items.len() == I::NULL.to_usize() {
+                        return Err(::serde::de::Error::custom(tree_core::FULL));
+                    }
+                    items.push(item);
+                }
+                let mut heap = SplayHeap::default();
+                heap.extend(items);
+                Ok(heap)
+            }
+        }
+        deserializer.deserialize_seq(Visitor(std::marker::PhantomData))
+    }
+}
+impl<T, C, I> IntoIterator for SplayHeap<T, C, I>
+where
+    I: Index,
+{
     type Item = T;
-    type IntoIter = IntoIter<T>;
+    type IntoIter = IntoIter<T, I>;
     fn into_iter(self) -> Self::IntoIter {
         IntoIter(self.tree.into_iter())
     }
 }
-impl<'a, T> IntoIterator for &'a SplayHeap<T> {
+impl<'a, T, C, I> IntoIterator for &'a SplayHeap<T, C, I>
+where
+    I: Index,
+{
     type Item = &'a T;
-    type IntoIter = Iter<'a, T>;
+    type IntoIter = Iter<'a, T, I>;
     fn into_iter(self) -> Self::IntoIter {
         self.iter()
     }
 }
-impl<T> Extend<T> for SplayHeap<T>
+impl<T, C, I> Extend<T> for SplayHeap<T, C, I>
 where
-    T: Ord,
+    C: Compare<T>,
+    I: Index,
 {
-    fn extend<I>(&mut self, iter: I)
+    fn extend<It>(&mut self, iter: It)
     where
-        I: IntoIterator<Item = T>,
+        It: IntoIterator<Item = T>,
     {
-        for x in iter {
-            self.push(x);
-        }
+        let seq = &mut self.seq;
+        self.tree.extend(iter.into_iter().map(|x| {
+            let item = Item(x, *seq);
+            *seq = seq.wrapping_add(1);
+            (item, ())
+        }));
     }
 }
-impl<'a, T> Extend<&'a T> for SplayHeap<T>
+impl<'a, T, C, I> Extend<&'a T> for SplayHeap<T, C, I>
 where
-    T: Copy + 'a + Ord,
+    T: Copy + 'a,
+    C: Compare<T>,
+    I: Index,
 {
-    fn extend<I>(&mut self, iter: I)
+    fn extend<It>(&mut self, iter: It)
     where
-        I: IntoIterator<Item = &'a T>,
+        It: IntoIterator<Item = &'a T>,
     {
         for x in iter {
             self.push(*x);
@@ -0,0 +1,72 @@
+//! Index types which link the nodes of the trees.
+//!
+//! The nodes of every collection in this crate are stored in an arena (a `Vec`),
+//! and each node refers to its children by their positions in the arena.
+//! The width of the positions is chosen by the last type parameter of the collections
+//! (`u32` by default):
+//!
+//! - `u16` makes the nodes smaller, but limits the collection to 65,535 entries.
+//! - `u32` limits the collection to about 4 billion entries.
+//! - `u64` (or `usize` on 64-bit platforms) lifts the limit practically.
+//!
+//! # Examples
+//! ```
+//! use splay_tree::SplayMap;
+//! use splay_tree::compare::Natural;
+//!
+//! let mut map: SplayMap<u8, u8, (), Natural, u16> = SplayMap::default();
+//! map.insert(1, 10);
+//! map.insert(2, 20);
+//! assert_eq!(map.get(&2), Some(&20));
+//! ```
+use std::fmt;
+use std::hash;
+
+/// An unsigned integer type which indexes the nodes of a tree.
+///
+/// The maximum value of the type is reserved for the null link,
+/// so a collection can hold at most `MAX` entries of the type
+/// (e.g., 65,535 entries for `u16`).
+/// Inserting more entries than that panics.
+///
+/// This trait is sealed, and implemented for `u16`, `u32`, `u64` and `usize`.
+pub trait Index: Copy + Eq + Ord + hash::Hash + fmt::Debug + private::Sealed {
+    /// The null link, which is never a valid index.
+    const NULL: Self;
+
+    /// Converts `n` to the index type.
+    ///
+    /// `n` must be less than `NULL`.
+    #[doc(hidden)]
+    fn from_usize(n: usize) -> Self;
+
+    /// Converts the index to `usize`.
+    #[doc(hidden)]
+    fn to_usize(self) -> usize;
+}
+
+macro_rules! impl_index {
+    ($($t:ty),*) => {
+        $(
+            impl private::Sealed for $t {}
+            impl Index for $t {
+                const NULL: Self = <$t>::MAX;
+
+                #[inline]
+                fn from_usize(n: usize) -> Self {
+                    debug_assert!(n < Self::NULL as usize);
+                    n as $t
+                }
+                #[inline]
+                fn to_usize(self) -> usize {
+                    self as usize
+                }
+            }
+        )*
+    };
+}
+impl_index!(u16, u32, u64, usize);
+
+mod private {
+    pub trait Sealed {}
+}
@@ -1,38 +1,103 @@
 //! Iterators for splay tree
-use std::mem;
+use std::mem::ManuallyDrop;
+use std::ptr;
 use std::vec::Vec;
-use tree_core::Node;
-use tree_core::NodeIndex;
+use index::Index;
+use tree_core::{Node, Positional};
 
+/// The position of a node in the arena.
+///
+/// The iterators handle the links as `usize`,
+/// so that only the ones borrowing the nodes depend on the index type of the tree.
+pub type NodeIndex = usize;
 pub type MaybeNodeIndex = Option<NodeIndex>;
 
 pub trait Nodes {
     type Entry;
-    fn get_node(&mut self, index: NodeIndex) -> (Self::Entry, MaybeNodeIndex, MaybeNodeIndex);
-}
+    /// Returns the left and right children of the node.
+    fn children(&self, index: NodeIndex) -> (MaybeNodeIndex, MaybeNodeIndex);
 
-#[derive(Clone)]
-enum Visit<E> {
-    Elem(E),
-    Node(NodeIndex),
+    /// Returns the entry of the node, which is called at most once for each node.
+    fn get_entry(&mut self, index: NodeIndex) -> Self::Entry;
+
+    /// Returns `true` if the subtree rooted at the node is reversed by a pending update,
+    /// which is not pushed down to the children yet.
+    fn is_reversed(&self, _index: NodeIndex) -> bool {
+        false
+    }
 }
 
+/// In-order iterator which can be consumed from both ends.
+///
+/// The top of `front` is always the smallest remaining node and
+/// the top of `back` is always the largest one.
+/// Each node on the stacks is paired with `true` if its children are swapped
+/// by the pending reversals of the node and its ancestors.
 #[derive(Clone)]
-pub struct InOrderIter<N>
-where
-    N: Nodes,
-{
+pub struct InOrderIter<N> {
     nodes: N,
-    stack: Vec<Visit<N::Entry>>,
+    front: Vec<(NodeIndex, bool)>,
+    back: Vec<(NodeIndex, bool)>,
 }
 impl<N> InOrderIter<N>
 where
     N: Nodes,
 {
     pub fn new(root: MaybeNodeIndex, nodes: N) -> Self {
+        let mut this = InOrderIter {
+            nodes,
+            front: Vec::new(),
+            back: Vec::new(),
+        };
+        this.push_lft_spine(root, false);
+        this.push_rgt_spine(root, false);
+        this
+    }
+
+    /// Makes an iterator which visits the nodes between `first` and `last` (inclusive).
+    ///
+    /// `last` must be `first` itself, the right child of `first` or
+    /// the left child of the right child of `first`.
+    pub fn range(first: NodeIndex, last: NodeIndex, nodes: N) -> Self {
+        let mut back = vec![(first, false)];
+        if first != last {
+            back.push((last, false));
+        }
         InOrderIter {
-            nodes: nodes,
-            stack: root.map(Visit::Node).into_iter().collect(),
+            nodes,
+            front: vec![(first, false)],
+            back,
+        }
+    }
+
+    /// Makes an iterator which yields nothing.
+    pub fn empty(nodes: N) -> Self {
+        InOrderIter::new(None, nodes)
+    }
+
+    /// Returns the children of the node `n` in the order in which they are visited.
+    fn children(&self, n: NodeIndex, swapped: bool) -> (MaybeNodeIndex, MaybeNodeIndex) {
+        let (lft, rgt) = self.nodes.children(n);
+        if swapped {
+            (rgt, lft)
+        } else {
+            (lft, rgt)
+        }
+    }
+
+    fn push_lft_spine(&mut self, mut node: MaybeNodeIndex, mut swapped: bool) {
+        while let Some(n) = node {
+            swapped ^= self.nodes.is_reversed(n);
+            self.front.push((n, swapped));
+            node = self.children(n, swapped).0;
+        }
+    }
+
+    fn push_rgt_spine(&mut self, mut node: MaybeNodeIndex, mut swapped: bool) {
+        while let Some(n) = node {
+            swapped ^= self.nodes.is_reversed(n);
+            self.back.push((n, swapped));
+            node = self.children(n, swapped).1;
         }
     }
 }
@@ -42,62 +107,147 @@ where
 {
     type Item = N::Entry;
     fn next(&mut self) -> Option<Self::Item> {
-        while let Some(v) = self.stack.pop() {
-            match v {
-                Visit::Node(n) => {
-                    let (e, lft, rgt) = self.nodes.get_node(n);
-                    rgt.map(|rgt| self.stack.push(Visit::Node(rgt)));
-                    self.stack.push(Visit::Elem(e));
-                    lft.map(|lft| self.stack.push(Visit::Node(lft)));
-                }
-                Visit::Elem(e) => {
-                    return Some(e);
-                }
-            }
+        let (n, swapped) = self.front.pop()?;
+        if self.back.last().map(|x| x.0) == Some(n) {
+            self.front.clear();
+            self.back.clear();
+        } else {
+            let rgt = self.children(n, swapped).1;
+            self.push_lft_spine(rgt, swapped);
+        }
+        Some(self.nodes.get_entry(n))
+    }
+}
+impl<N> DoubleEndedIterator for InOrderIter<N>
+where
+    N: Nodes,
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let (n, swapped) = self.back.pop()?;
+        if self.front.last().map(|x| x.0) == Some(n) {
+            self.front.clear();
+            self.back.clear();
+        } else {
+            let lft = self.children(n, swapped).0;
+            self.push_rgt_spine(lft, swapped);
         }
-        None
+        Some(self.nodes.get_entry(n))
     }
 }
 
-pub type Iter<'a, K, V> = InOrderIter<&'a [Node<K, V>]>;
-impl<'a, K: 'a, V: 'a> Nodes for &'a [Node<K, V>] {
+pub type Iter<'a, K, V, A = (), I = u32> = InOrderIter<&'a [Node<K, V, A, I>]>;
+impl<'a, K: 'a, V: 'a, A: 'a, I: Index + 'a> Nodes for &'a [Node<K, V, A, I>] {
     type Entry = (&'a K, &'a V);
-    fn get_node(&mut self, index: NodeIndex) -> (Self::Entry, MaybeNodeIndex, MaybeNodeIndex) {
-        let n = unsafe { self.get_unchecked(index as usize) };
-        (n.into(), n.lft(), n.rgt())
+    fn children(&self, index: NodeIndex) -> (MaybeNodeIndex, MaybeNodeIndex) {
+        let n = unsafe { self.get_unchecked(index) };
+        (n.lft().map(I::to_usize), n.rgt().map(I::to_usize))
+    }
+    fn get_entry(&mut self, index: NodeIndex) -> Self::Entry {
+        let n = unsafe { self.get_unchecked(index) };
+        n.into()
+    }
+}
+
+/// In-order iterator which sees through the pending reversals of a positional tree.
+pub type PositionalIter<'a, V, A, I = u32> = InOrderIter<PositionalNodes<'a, V, A, I>>;
+
+/// The nodes of a positional tree, whose pending reversals are resolved while iterating.
+pub struct PositionalNodes<'a, V: 'a, A: 'a, I: 'a>(pub &'a [Node<I, V, A, I>]);
+impl<'a, V: 'a, A: 'a, I: 'a> Clone for PositionalNodes<'a, V, A, I> {
+    fn clone(&self) -> Self {
+        PositionalNodes(self.0)
+    }
+}
+impl<'a, V: 'a, A: 'a, I: 'a> Nodes for PositionalNodes<'a, V, A, I>
+where
+    A: Positional<V, I>,
+    I: Index,
+{
+    type Entry = (&'a I, &'a V);
+    fn children(&self, index: NodeIndex) -> (MaybeNodeIndex, MaybeNodeIndex) {
+        self.0.children(index)
+    }
+    fn get_entry(&mut self, index: NodeIndex) -> Self::Entry {
+        self.0.get_entry(index)
+    }
+    fn is_reversed(&self, index: NodeIndex) -> bool {
+        self.0[index].aug.is_reversed()
     }
 }
 
-pub type IterMut<'a, K, V> = InOrderIter<&'a mut [Node<K, V>]>;
-impl<'a, K: 'a, V: 'a> Nodes for &'a mut [Node<K, V>] {
+pub type IterMut<'a, K, V, A = (), I = u32> = InOrderIter<&'a mut [Node<K, V, A, I>]>;
+impl<'a, K: 'a, V: 'a, A: 'a, I: Index + 'a> Nodes for &'a mut [Node<K, V, A, I>] {
     type Entry = (&'a K, &'a mut V);
-    fn get_node(&mut self, index: NodeIndex) -> (Self::Entry, MaybeNodeIndex, MaybeNodeIndex) {
-        let n = unsafe { self.get_unchecked_mut(index as usize) };
-        let (lft, rgt) = (n.lft(), n.rgt());
-        let n = unsafe { &mut *(n as *mut _) as &mut Node<_, _> };
-        (n.into(), lft, rgt)
+    fn children(&self, index: NodeIndex) -> (MaybeNodeIndex, MaybeNodeIndex) {
+        let n = unsafe { self.get_unchecked(index) };
+        (n.lft().map(I::to_usize), n.rgt().map(I::to_usize))
+    }
+    fn get_entry(&mut self, index: NodeIndex) -> Self::Entry {
+        let n = unsafe { self.get_unchecked_mut(index) };
+        let n = unsafe { &mut *(n as *mut _) as &mut Node<_, _, _, _> };
+        n.into()
     }
 }
 
-pub type IntoIter<K, V> = InOrderIter<OwnedNodes<K, V>>;
-pub struct OwnedNodes<K, V>(pub Vec<Node<K, V>>);
-impl<K, V> Nodes for OwnedNodes<K, V> {
+pub type IntoIter<K, V, A = (), I = u32> = InOrderIter<OwnedNodes<K, V, A, I>>;
+
+/// The nodes moved out of a tree, whose entries are taken in any order.
+///
+/// The entries are moved out of the nodes in place and recorded in a bitmap,
+/// so the links remain available and the entries which are not taken
+/// are dropped exactly once along with the nodes.
+pub struct OwnedNodes<K, V, A = (), I = u32>
+where
+    I: Index,
+{
+    nodes: Vec<ManuallyDrop<Node<K, V, A, I>>>,
+    taken: Vec<u64>,
+}
+impl<K, V, A, I> OwnedNodes<K, V, A, I>
+where
+    I: Index,
+{
+    /// Takes over the nodes without moving them.
+    pub fn new(nodes: Vec<Node<K, V, A, I>>) -> Self {
+        let mut nodes = ManuallyDrop::new(nodes);
+        let (ptr, len, cap) = (nodes.as_mut_ptr(), nodes.len(), nodes.capacity());
+        // `ManuallyDrop<T>` has the same layout as `T`.
+        let nodes = unsafe { Vec::from_raw_parts(ptr as *mut ManuallyDrop<_>, len, cap) };
+        let taken = vec![0; len.div_ceil(64)];
+        OwnedNodes { nodes, taken }
+    }
+    fn is_taken(&self, index: NodeIndex) -> bool {
+        self.taken[index / 64] & (1 << (index % 64)) != 0
+    }
+}
+impl<K, V, A, I> Nodes for OwnedNodes<K, V, A, I>
+where
+    I: Index,
+{
     type Entry = (K, V);
-    fn get_node(&mut self, index: NodeIndex) -> (Self::Entry, MaybeNodeIndex, MaybeNodeIndex) {
-        let n = mem::replace(
-            unsafe { self.0.get_unchecked_mut(index as usize) },
-            unsafe { mem::zeroed() },
-        );
-        let (lft, rgt) = (n.lft(), n.rgt());
-        (n.into(), lft, rgt)
+    fn children(&self, index: NodeIndex) -> (MaybeNodeIndex, MaybeNodeIndex) {
+        let n = &self.nodes[index];
+        (n.lft().map(I::to_usize), n.rgt().map(I::to_usize))
+    }
+    fn get_entry(&mut self, index: NodeIndex) -> Self::Entry {
+        assert!(!self.is_taken(index), "Each entry is visited only once");
+        self.taken[index / 64] |= 1 << (index % 64);
+
+        // The node is never dropped as a whole after this, so each field is moved or dropped here once.
+        let n = &mut self.nodes[index];
+        let entry = unsafe { (ptr::read(&n.key), ptr::read(&n.val)) };
+        unsafe { ptr::drop_in_place(&mut n.aug) };
+        entry
     }
 }
-impl<K, V> Drop for OwnedNodes<K, V> {
+impl<K, V, A, I> Drop for OwnedNodes<K, V, A, I>
+where
+    I: Index,
+{
     fn drop(&mut self) {
-        let is_sentinel = |n: &Node<_, _>| n.lft().is_some() && n.lft() == n.rgt();
-        for e in mem::replace(&mut self.0, Vec::new()) {
-            if is_sentinel(&e) {
-                mem::forget(e);
+        for index in 0..self.nodes.len() {
+            if !self.is_taken(index) {
+                unsafe { ManuallyDrop::drop(&mut self.nodes[index]) };
             }
         }
     }
@@ -4,7 +4,6 @@
 #![warn(missing_docs)]
 
 #[cfg(feature = "serde")]
-#[macro_use]
 extern crate serde;
 
 #[cfg(not(feature = "std"))]
@@ -14,7 +13,7 @@ pub extern crate alloc;
 #[cfg(not(feature = "std"))]
 mod std {
     pub use alloc::*;
-    pub use core::{borrow, cmp, fmt, hash, iter, mem, ops, slice, u32};
+    pub use core::{borrow, cmp, fmt, hash, iter, marker, mem, ops, ptr, slice, u32};
 }
 
 mod tree_core;
@@ -23,6 +22,12 @@ mod vec_like;
 pub mod map;
 pub mod set;
 pub mod heap;
+pub mod vec;
+pub mod augment;
+pub mod compare;
+pub mod index;
+pub mod error;
+pub mod policy;
 
 #[doc(inline)]
 pub use map::SplayMap;
@@ -32,3 +37,6 @@ pub use set::SplaySet;
 
 #[doc(inline)]
 pub use heap::SplayHeap;
+
+#[doc(inline)]
+pub use vec::SplayVec;
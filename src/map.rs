@@ -1,9 +1,17 @@
 //! A map based on a splay tree.
 use std;
 use std::mem;
+use std::cmp::Ordering;
 use std::borrow::Borrow;
+use std::ops::RangeBounds;
 use tree_core;
 use iter;
+use augment::{Augment, Counted, Eager, Folded, Tagged};
+use compare::{Compare, Natural};
+use index::Index;
+use tree_core::Merged;
+use error::{InvariantError, TryReserveError};
+use policy::SplayPolicy;
 
 /// A map based on a splay tree.
 ///
@@ -12,7 +20,7 @@ use iter;
 ///
 /// It is a logic error for a key to be modified in such a way that
 /// the key's ordering relative to any other key,
-/// as determined by the `Ord` trait, changes while it is in the map.
+/// as determined by the comparator (the `Ord` trait by default), changes while it is in the map.
 /// This is normally only possible through `Cell`, `RefCell`, global state, I/O, or unsafe code.
 ///
 /// # Examples
@@ -48,15 +56,14 @@ use iter;
 ///     let k = rand::random::<u8>();
 ///     *count.entry(k).or_insert(0) += 1;
 /// }
-/// for k in 0..0x100 {
+/// for k in 0..=0xFF {
 ///     println!("{}: {}", k, count.get(&k).unwrap_or(&0));
 /// }
 /// # }
 /// ```
-#[derive(Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-pub struct SplayMap<K, V> {
-    tree: tree_core::Tree<K, V>,
+#[derive(Debug, Clone)]
+pub struct SplayMap<K, V, A = (), C = Natural, I = u32> {
+    tree: tree_core::Tree<K, V, A, C, I>,
 }
 impl<K, V> SplayMap<K, V>
 where
@@ -78,6 +85,51 @@ where
         }
     }
 
+    /// Makes a new empty `SplayMap` which can hold at least `capacity` entries
+    /// without reallocating.
+    ///
+    /// # Examples
+    /// ```
+    /// use splay_tree::SplayMap;
+    ///
+    /// let mut map = SplayMap::with_capacity(10);
+    /// map.insert("foo", 1);
+    /// assert!(map.capacity() >= 10);
+    /// ```
+    pub fn with_capacity(capacity: usize) -> Self {
+        let mut map = SplayMap::new();
+        map.reserve(capacity);
+        map
+    }
+}
+impl<K, V, C> SplayMap<K, V, (), C>
+where
+    C: Compare<K>,
+{
+    /// Makes a new empty `SplayMap` which orders its keys by the given comparator.
+    ///
+    /// # Examples
+    /// ```
+    /// use splay_tree::SplayMap;
+    ///
+    /// let mut map = SplayMap::with_comparator(|a: &&str, b: &&str| b.cmp(a));
+    /// map.insert("foo", 1);
+    /// map.insert("bar", 2);
+    /// map.insert("baz", 3);
+    /// assert_eq!(map.keys().cloned().collect::<Vec<_>>(), ["foo", "baz", "bar"]);
+    /// ```
+    pub fn with_comparator(cmp: C) -> Self {
+        SplayMap {
+            tree: tree_core::Tree::with_comparator(cmp),
+        }
+    }
+}
+impl<K, V, A, C, I> SplayMap<K, V, A, C, I>
+where
+    C: Compare<K>,
+    A: Augment<K, V>,
+    I: Index,
+{
     /// Clears the map, removing all values.
     ///
     /// # Examples
@@ -90,7 +142,7 @@ where
     /// assert!(map.is_empty());
     /// ```
     pub fn clear(&mut self) {
-        self.tree = tree_core::Tree::new();
+        self.tree.clear();
     }
 
     /// Returns true if the map contains a value for the specified key.
@@ -115,9 +167,9 @@ where
     pub fn contains_key<Q: ?Sized>(&mut self, key: &Q) -> bool
     where
         K: Borrow<Q>,
-        Q: Ord,
+        C: Compare<Q>,
     {
-        self.tree.contains_key(key)
+        self.tree.lookup(key).is_some()
     }
 
     /// Returns a reference to the value corresponding to the key.
@@ -142,9 +194,10 @@ where
     pub fn get<Q: ?Sized>(&mut self, key: &Q) -> Option<&V>
     where
         K: Borrow<Q>,
-        Q: Ord,
+        C: Compare<Q>,
     {
-        self.get_mut(key).map(|v| &*v)
+        let found = self.tree.lookup(key);
+        found.map(move |i| &self.tree.node_ref(i).val)
     }
 
     /// Returns a mutable reference to the value corresponding to the key.
@@ -164,7 +217,7 @@ where
     pub fn get_mut<Q: ?Sized>(&mut self, key: &Q) -> Option<&mut V>
     where
         K: Borrow<Q>,
-        Q: Ord,
+        C: Compare<Q>,
     {
         self.tree.get(key)
     }
@@ -186,9 +239,9 @@ where
     pub fn find_lower_bound_key<Q: ?Sized>(&mut self, key: &Q) -> Option<&K>
     where
         K: Borrow<Q>,
-        Q: Ord,
+        C: Compare<Q>,
     {
-        self.tree.find_lower_bound(key)
+        self.tree.find_lower_bound(key).map(|n| &n.key)
     }
 
     /// Finds a minimum key which satisfies "greater than `key`" condition in the map.
@@ -208,9 +261,205 @@ where
     pub fn find_upper_bound_key<Q: ?Sized>(&mut self, key: &Q) -> Option<&K>
     where
         K: Borrow<Q>,
-        Q: Ord,
+        C: Compare<Q>,
+    {
+        self.tree.find_upper_bound(key).map(|n| &n.key)
+    }
+
+    /// Finds the entry with a maximum key which satisfies "less than `key`" condition in the map.
+    ///
+    /// # Examples
+    /// ```
+    /// use splay_tree::SplayMap;
+    ///
+    /// let mut map = SplayMap::new();
+    /// map.insert(1, "a");
+    /// map.insert(3, "c");
+    ///
+    /// assert_eq!(map.find_less(&1), None);
+    /// assert_eq!(map.find_less(&3), Some((&1, &"a")));
+    /// assert_eq!(map.find_less(&4), Some((&3, &"c")));
+    /// ```
+    pub fn find_less<Q: ?Sized>(&mut self, key: &Q) -> Option<(&K, &V)>
+    where
+        K: Borrow<Q>,
+        C: Compare<Q>,
+    {
+        self.tree.find_less(key).map(|n| (&n.key, &n.val))
+    }
+
+    /// Finds the entry with a maximum key which satisfies "less than `key`" condition in the map,
+    /// and returns the key and a mutable reference to the value.
+    ///
+    /// # Examples
+    /// ```
+    /// use splay_tree::SplayMap;
+    ///
+    /// let mut map = SplayMap::new();
+    /// map.insert(1, "a");
+    /// map.insert(3, "c");
+    ///
+    /// if let Some((_, v)) = map.find_less_mut(&2) {
+    ///     *v = "x";
+    /// }
+    /// assert_eq!(map.into_iter().collect::<Vec<_>>(), [(1, "x"), (3, "c")]);
+    /// ```
+    pub fn find_less_mut<Q: ?Sized>(&mut self, key: &Q) -> Option<(&K, &mut V)>
+    where
+        K: Borrow<Q>,
+        C: Compare<Q>,
+    {
+        self.tree.find_less(key).map(|n| (&n.key, &mut n.val))
+    }
+
+    /// Finds the entry with a maximum key which satisfies "less than or equal to `key`" condition in the map.
+    ///
+    /// # Examples
+    /// ```
+    /// use splay_tree::SplayMap;
+    ///
+    /// let mut map = SplayMap::new();
+    /// map.insert(1, "a");
+    /// map.insert(3, "c");
+    ///
+    /// assert_eq!(map.find_less_or_equal(&0), None);
+    /// assert_eq!(map.find_less_or_equal(&1), Some((&1, &"a")));
+    /// assert_eq!(map.find_less_or_equal(&2), Some((&1, &"a")));
+    /// ```
+    pub fn find_less_or_equal<Q: ?Sized>(&mut self, key: &Q) -> Option<(&K, &V)>
+    where
+        K: Borrow<Q>,
+        C: Compare<Q>,
+    {
+        self.tree.find_less_or_equal(key).map(|n| (&n.key, &n.val))
+    }
+
+    /// Finds the entry with a maximum key which satisfies "less than or equal to `key`" condition in the map,
+    /// and returns the key and a mutable reference to the value.
+    ///
+    /// # Examples
+    /// ```
+    /// use splay_tree::SplayMap;
+    ///
+    /// let mut map = SplayMap::new();
+    /// map.insert(1, "a");
+    /// map.insert(3, "c");
+    ///
+    /// if let Some((_, v)) = map.find_less_or_equal_mut(&2) {
+    ///     *v = "x";
+    /// }
+    /// assert_eq!(map.into_iter().collect::<Vec<_>>(), [(1, "x"), (3, "c")]);
+    /// ```
+    pub fn find_less_or_equal_mut<Q: ?Sized>(&mut self, key: &Q) -> Option<(&K, &mut V)>
+    where
+        K: Borrow<Q>,
+        C: Compare<Q>,
+    {
+        self.tree.find_less_or_equal(key).map(|n| (&n.key, &mut n.val))
+    }
+
+    /// Finds the entry with a minimum key which satisfies "greater than `key`" condition in the map.
+    ///
+    /// # Examples
+    /// ```
+    /// use splay_tree::SplayMap;
+    ///
+    /// let mut map = SplayMap::new();
+    /// map.insert(1, "a");
+    /// map.insert(3, "c");
+    ///
+    /// assert_eq!(map.find_greater(&0), Some((&1, &"a")));
+    /// assert_eq!(map.find_greater(&1), Some((&3, &"c")));
+    /// assert_eq!(map.find_greater(&3), None);
+    /// ```
+    pub fn find_greater<Q: ?Sized>(&mut self, key: &Q) -> Option<(&K, &V)>
+    where
+        K: Borrow<Q>,
+        C: Compare<Q>,
+    {
+        self.tree.find_upper_bound(key).map(|n| (&n.key, &n.val))
+    }
+
+    /// Finds the entry with a minimum key which satisfies "greater than `key`" condition in the map,
+    /// and returns the key and a mutable reference to the value.
+    ///
+    /// # Examples
+    /// ```
+    /// use splay_tree::SplayMap;
+    ///
+    /// let mut map = SplayMap::new();
+    /// map.insert(1, "a");
+    /// map.insert(3, "c");
+    ///
+    /// if let Some((_, v)) = map.find_greater_mut(&2) {
+    ///     *v = "x";
+    /// }
+    /// assert_eq!(map.into_iter().collect::<Vec<_>>(), [(1, "a"), (3, "x")]);
+    /// ```
+    pub fn find_greater_mut<Q: ?Sized>(&mut self, key: &Q) -> Option<(&K, &mut V)>
+    where
+        K: Borrow<Q>,
+        C: Compare<Q>,
+    {
+        self.tree.find_upper_bound(key).map(|n| (&n.key, &mut n.val))
+    }
+
+    /// Gets an iterator over a sub-range of entries in the map, sorted by key.
+    ///
+    /// The first entry of the range is located in `O(log n)` amortized time,
+    /// and then only the entries in the range are visited.
+    ///
+    /// # Panics
+    ///
+    /// Panics if range `start > end`.
+    /// Panics if range `start == end` and both bounds are `Excluded`.
+    ///
+    /// # Examples
+    /// ```
+    /// use splay_tree::SplayMap;
+    ///
+    /// let mut map: SplayMap<_, _> = (0..10).map(|n| (n, n * 10)).collect();
+    ///
+    /// assert_eq!(map.range(3..6).collect::<Vec<_>>(),
+    ///            [(&3, &30), (&4, &40), (&5, &50)]);
+    /// assert_eq!(map.range(7..).rev().map(|(k, _)| *k).collect::<Vec<_>>(),
+    ///            [9, 8, 7]);
+    /// ```
+    pub fn range<Q: ?Sized, R>(&mut self, range: R) -> Range<'_, K, V, A, I>
+    where
+        K: Borrow<Q>,
+        C: Compare<Q>,
+        R: RangeBounds<Q>,
+    {
+        let bounds = self.tree.range(range);
+        Range(self.tree.iter_range(bounds))
+    }
+
+    /// Gets a mutable iterator over a sub-range of entries in the map, sorted by key.
+    ///
+    /// # Panics
+    ///
+    /// Panics if range `start > end`.
+    /// Panics if range `start == end` and both bounds are `Excluded`.
+    ///
+    /// # Examples
+    /// ```
+    /// use splay_tree::SplayMap;
+    ///
+    /// let mut map: SplayMap<_, _> = (0..5).map(|n| (n, n)).collect();
+    /// for (_, v) in map.range_mut(1..=3) {
+    ///     *v += 10;
+    /// }
+    /// assert_eq!(map.values().cloned().collect::<Vec<_>>(), [0, 11, 12, 13, 4]);
+    /// ```
+    pub fn range_mut<Q: ?Sized, R>(&mut self, range: R) -> RangeMut<'_, K, V, A, I>
+    where
+        K: Borrow<Q>,
+        C: Compare<Q>,
+        R: RangeBounds<Q>,
     {
-        self.tree.find_upper_bound(key)
+        let bounds = self.tree.range(range);
+        RangeMut(self.tree.iter_range_mut(bounds))
     }
 
     /// Gets the entry which have the minimum key in the map.
@@ -304,6 +553,57 @@ where
         self.tree.insert(key, value)
     }
 
+    /// Inserts a key-value pair into the map in the same way as `insert`,
+    /// but returns an error instead of panicking or aborting
+    /// if the map cannot hold one more entry.
+    ///
+    /// The map is left unchanged in that case.
+    ///
+    /// # Examples
+    /// ```
+    /// use splay_tree::SplayMap;
+    /// use splay_tree::compare::Natural;
+    /// use splay_tree::error::TryReserveError;
+    ///
+    /// let entries = (0..65535).map(|i| (i, i));
+    /// let mut map: SplayMap<u16, u16, (), Natural, u16> = SplayMap::from_sorted_iter(entries);
+    /// assert_eq!(map.try_insert(0, 1), Ok(Some(0)));
+    /// assert_eq!(map.try_insert(65535, 0), Err(TryReserveError::CapacityOverflow));
+    /// assert_eq!(map.len(), 65535);
+    /// ```
+    pub fn try_insert(&mut self, key: K, value: V) -> Result<Option<V>, TryReserveError> {
+        self.tree.try_insert(key, value)
+    }
+
+    /// Makes a new map from an iterator of entries sorted by their keys in strictly ascending order.
+    ///
+    /// This takes `O(n)` time, and the entries are linked as a perfectly balanced tree.
+    /// (`FromIterator` and `Extend` also detect ascending runs of entries,
+    /// but they compare each entry with the previous one.)
+    ///
+    /// # Panics
+    ///
+    /// In debug builds, panics if the keys are not strictly ascending.
+    /// In release builds, such input leaves the map in an unspecified (but memory-safe) state.
+    ///
+    /// # Examples
+    /// ```
+    /// use splay_tree::SplayMap;
+    ///
+    /// let mut map: SplayMap<_, _> = SplayMap::from_sorted_iter((0..100).map(|n| (n, n * 2)));
+    /// assert_eq!(map.len(), 100);
+    /// assert_eq!(map.get(&50), Some(&100));
+    /// ```
+    pub fn from_sorted_iter<T>(iter: T) -> Self
+    where
+        T: IntoIterator<Item = (K, V)>,
+        C: Default,
+    {
+        let mut map = SplayMap::default();
+        map.tree.rebuild_from_sorted(iter);
+        map
+    }
+
     /// Removes a key from the map,
     /// returning the value at the key if the key was previously in the map.
     ///
@@ -322,328 +622,1683 @@ where
     pub fn remove<Q: ?Sized>(&mut self, key: &Q) -> Option<V>
     where
         K: Borrow<Q>,
-        Q: Ord,
+        C: Compare<Q>,
     {
         self.tree.remove(key)
     }
 
-    /// Gets the given key's corresponding entry in the map for in-place manipulation.
+    /// Splits the map into two at the given key.
+    /// Returns everything after the given key, including the key.
     ///
     /// # Examples
     /// ```
     /// use splay_tree::SplayMap;
     ///
-    /// let mut count = SplayMap::new();
+    /// let mut a = SplayMap::new();
+    /// a.insert(1, "a");
+    /// a.insert(2, "b");
+    /// a.insert(3, "c");
+    /// a.insert(17, "d");
+    /// a.insert(41, "e");
     ///
-    /// // count the number of occurrences of letters in the vec
-    /// for x in vec!["a", "b", "a", "c", "a", "b"] {
-    ///     *count.entry(x).or_insert(0) += 1;
-    /// }
+    /// let b = a.split_off(&3);
     ///
-    /// assert_eq!(count.get("a"), Some(&3));
+    /// assert_eq!(a.len(), 2);
+    /// assert_eq!(b.len(), 3);
+    ///
+    /// assert_eq!(a.into_iter().collect::<Vec<_>>(), [(1, "a"), (2, "b")]);
+    /// assert_eq!(b.into_iter().collect::<Vec<_>>(), [(3, "c"), (17, "d"), (41, "e")]);
     /// ```
-    pub fn entry(&mut self, key: K) -> Entry<K, V> {
-        if self.contains_key(&key) {
-            Entry::Occupied(OccupiedEntry {
-                tree: &mut self.tree,
-            })
-        } else {
-            Entry::Vacant(VacantEntry {
-                key: key,
-                tree: &mut self.tree,
-            })
+    pub fn split_off<Q: ?Sized>(&mut self, key: &Q) -> Self
+    where
+        K: Borrow<Q>,
+        C: Compare<Q> + Clone,
+    {
+        SplayMap {
+            tree: self.tree.split_off(key),
         }
     }
-}
-impl<K, V> SplayMap<K, V> {
-    /// Returns the number of elements in the map.
+
+    /// Moves all elements from `other` into `self`, leaving `other` empty.
+    ///
+    /// If a key from `other` is already present in `self`,
+    /// the respective value from `self` will be overwritten with the respective value from `other`.
     ///
     /// # Examples
     /// ```
     /// use splay_tree::SplayMap;
     ///
-    /// let mut map = SplayMap::new();
-    /// map.insert("foo", 1);
-    /// map.insert("bar", 2);
-    /// assert_eq!(map.len(), 2);
-    /// ```
-    pub fn len(&self) -> usize {
-        self.tree.len()
-    }
-
-    /// Returns true if the map contains no elements.
+    /// let mut a = SplayMap::new();
+    /// a.insert(1, "a");
+    /// a.insert(2, "b");
+    /// a.insert(3, "c");
     ///
-    /// #  Examples
-    /// ```
-    /// use splay_tree::SplayMap;
+    /// let mut b = SplayMap::new();
+    /// b.insert(3, "d");
+    /// b.insert(4, "e");
+    /// b.insert(5, "f");
     ///
-    /// let mut map = SplayMap::new();
-    /// assert!(map.is_empty());
+    /// a.append(&mut b);
     ///
-    /// map.insert("foo", 1);
-    /// assert!(!map.is_empty());
+    /// assert_eq!(a.len(), 5);
+    /// assert_eq!(b.len(), 0);
     ///
-    /// map.clear();
-    /// assert!(map.is_empty());
+    /// assert_eq!(a.get(&3), Some(&"d"));
+    /// assert_eq!(a.get(&5), Some(&"f"));
     /// ```
-    pub fn is_empty(&self) -> bool {
-        self.len() == 0
+    pub fn append(&mut self, other: &mut Self)
+    where
+        C: Clone,
+    {
+        self.tree.append(&mut other.tree);
     }
 
-    /// Gets an iterator over the entries of the map, sorted by key.
+    /// Merges `other` into the map by a linear merge of their entries in ascending order.
+    ///
+    /// The entries whose keys are contained in only one of the maps are kept as they are.
+    /// For each key contained in both maps, `f` is called with the key and the values of
+    /// `self` and `other` (in this order), and the entry is kept with the returned value,
+    /// or removed if `f` returns `None`.
+    ///
+    /// This takes `O(n + m)` time, and the resulting tree is perfectly balanced.
+    /// If `f` or the comparator panics, the entries which are not merged yet are dropped.
     ///
     /// # Examples
     /// ```
     /// use splay_tree::SplayMap;
     ///
-    /// let map: SplayMap<_, _> =
-    ///     vec![("foo", 1), ("bar", 2), ("baz", 3)].into_iter().collect();
-    /// assert_eq!(vec![(&"bar", &2), (&"baz", &3), (&"foo", &1)],
-    ///            map.iter().collect::<Vec<_>>());
+    /// let mut base: SplayMap<_, _> = vec![("host", "localhost"), ("port", "80"), ("user", "root")]
+    ///     .into_iter()
+    ///     .collect();
+    /// let overrides: SplayMap<_, _> = vec![("port", "8080"), ("timeout", "10"), ("user", "")]
+    ///     .into_iter()
+    ///     .collect();
+    ///
+    /// base.merge_with(overrides, |_, _, new| if new.is_empty() { None } else { Some(new) });
+    /// assert_eq!(
+    ///     base.into_iter().collect::<Vec<_>>(),
+    ///     [("host", "localhost"), ("port", "8080"), ("timeout", "10")]
+    /// );
     /// ```
-    pub fn iter(&self) -> Iter<K, V> {
-        Iter::new(&self.tree)
+    pub fn merge_with<F>(&mut self, other: Self, mut f: F)
+    where
+        F: FnMut(&K, V, V) -> Option<V>,
+    {
+        self.tree.merge_with(other.tree, |merged| match merged {
+            Merged::Lft(e) | Merged::Rgt(e) => Some(e),
+            Merged::Both((k, l), (_, r)) => f(&k, l, r).map(|v| (k, v)),
+        });
     }
 
-    /// Gets a mutable iterator over the entries of the map, soretd by key.
+    /// Retains only the entries specified by the predicate.
+    ///
+    /// In other words, removes all pairs `(k, v)` such that `f(&k, &mut v)` returns `false`.
+    /// The entries are visited in ascending key order, in a single pass over the tree,
+    /// and then the tree is rebuilt into a balanced shape.
     ///
     /// # Examples
     /// ```
     /// use splay_tree::SplayMap;
     ///
-    /// let mut map: SplayMap<_, _> =
-    ///     vec![("foo", 1), ("bar", 2), ("baz", 3)].into_iter().collect();
-    /// for (_, v) in map.iter_mut() {
-    ///    *v += 10;
-    /// }
-    /// assert_eq!(map.get("bar"), Some(&12));
+    /// let mut map: SplayMap<_, _> = (0..8).map(|x| (x, x * 10)).collect();
+    /// map.retain(|&k, _| k % 2 == 0);
+    /// assert_eq!(map.into_iter().collect::<Vec<_>>(), [(0, 0), (2, 20), (4, 40), (6, 60)]);
     /// ```
-    pub fn iter_mut(&mut self) -> IterMut<K, V> {
-        IterMut::new(&mut self.tree)
+    pub fn retain<F>(&mut self, mut f: F)
+    where
+        F: FnMut(&K, &mut V) -> bool,
+    {
+        self.extract_if(|k, v| !f(k, v)).for_each(drop);
     }
 
-    /// Gets an iterator over the keys of the map, in sorted order.
+    /// Creates an iterator that visits all entries in ascending key order,
+    /// and uses a closure to determine if an entry should be removed.
+    ///
+    /// If the closure returns `true`, the entry is removed from the map and yielded.
+    /// If the closure returns `false`, the entry remains in the map and will not be yielded.
+    ///
+    /// If the iterator is dropped before being fully consumed,
+    /// it retains the remaining entries.
+    /// If it is leaked (e.g., by `mem::forget`), the map may lose the entries
+    /// which are not visited yet.
     ///
     /// # Examples
     /// ```
     /// use splay_tree::SplayMap;
     ///
-    /// let map: SplayMap<_, _> =
-    ///     vec![("foo", 1), ("bar", 2), ("baz", 3)].into_iter().collect();
-    /// assert_eq!(vec!["bar", "baz", "foo"],
-    ///            map.keys().cloned().collect::<Vec<_>>());
+    /// let mut map: SplayMap<_, _> = (0..8).map(|x| (x, x)).collect();
+    /// let evens = map.extract_if(|k, _| k % 2 == 0).collect::<Vec<_>>();
+    /// assert_eq!(evens, [(0, 0), (2, 2), (4, 4), (6, 6)]);
+    /// assert_eq!(map.keys().cloned().collect::<Vec<_>>(), [1, 3, 5, 7]);
     /// ```
-    pub fn keys(&self) -> Keys<K, V> {
-        Keys::new(&self.tree)
+    pub fn extract_if<F>(&mut self, pred: F) -> ExtractIf<'_, K, V, A, C, I, F>
+    where
+        F: FnMut(&K, &mut V) -> bool,
+    {
+        ExtractIf {
+            inner: self.tree.extract(),
+            pred,
+        }
     }
 
-    /// Gets an iterator over the values of the map, in order by key.
+    /// Clears the map, returning all entries as an iterator in ascending key order.
+    ///
+    /// The map is emptied even if the iterator is not fully consumed.
     ///
     /// # Examples
     /// ```
     /// use splay_tree::SplayMap;
     ///
-    /// let map: SplayMap<_, _> =
-    ///     vec![("foo", 1), ("bar", 2), ("baz", 3)].into_iter().collect();
-    /// assert_eq!(vec![2, 3, 1],
-    ///            map.values().cloned().collect::<Vec<_>>());
+    /// let mut map: SplayMap<_, _> = vec![(2, "b"), (1, "a")].into_iter().collect();
+    /// assert_eq!(map.drain().collect::<Vec<_>>(), [(1, "a"), (2, "b")]);
+    /// assert!(map.is_empty());
     /// ```
-    pub fn values(&self) -> Values<K, V> {
-        Values::new(&self.tree)
+    pub fn drain(&mut self) -> Drain<K, V, A, I> {
+        Drain(self.tree.drain())
     }
 
-    /// Gets a mutable iterator over the values of the map, in order by key.
+    /// Gets the given key's corresponding entry in the map for in-place manipulation.
     ///
     /// # Examples
     /// ```
     /// use splay_tree::SplayMap;
     ///
-    /// let mut map: SplayMap<_, _> =
-    ///     vec![("foo", 1), ("bar", 2), ("baz", 3)].into_iter().collect();
-    /// for v in map.values_mut() {
-    ///     *v += 10;
-    /// }
+    /// let mut count = SplayMap::new();
+    ///
+    /// // count the number of occurrences of letters in the vec
+    /// for x in vec!["a", "b", "a", "c", "a", "b"] {
+    ///     *count.entry(x).or_insert(0) += 1;
+    /// }
+    ///
+    /// assert_eq!(count.get("a"), Some(&3));
+    /// ```
+    pub fn entry(&mut self, key: K) -> Entry<'_, K, V, A, C, I> {
+        if self.tree.contains_key(&key) {
+            Entry::Occupied(OccupiedEntry {
+                tree: &mut self.tree,
+            })
+        } else {
+            Entry::Vacant(VacantEntry {
+                key,
+                tree: &mut self.tree,
+            })
+        }
+    }
+
+    /// Returns a cursor pointing at the entry which have the minimum key in the map.
+    ///
+    /// If the map is empty, the cursor points at the "ghost" non-element.
+    ///
+    /// # Examples
+    /// ```
+    /// use splay_tree::SplayMap;
+    ///
+    /// let mut map: SplayMap<_, _> = vec![(1, "a"), (2, "b")].into_iter().collect();
+    /// let mut cursor = map.cursor_front();
+    /// assert_eq!(cursor.key(), Some(&1));
+    /// cursor.move_next();
+    /// assert_eq!(cursor.key(), Some(&2));
+    /// cursor.move_next();
+    /// assert_eq!(cursor.key(), None);
+    /// ```
+    pub fn cursor_front(&mut self) -> Cursor<'_, K, V, A, C, I> {
+        Cursor(self.cursor_front_mut())
+    }
+
+    /// Returns a cursor pointing at the entry which have the maximum key in the map.
+    ///
+    /// If the map is empty, the cursor points at the "ghost" non-element.
+    ///
+    /// # Examples
+    /// ```
+    /// use splay_tree::SplayMap;
+    ///
+    /// let mut map: SplayMap<_, _> = vec![(1, "a"), (2, "b")].into_iter().collect();
+    /// let mut cursor = map.cursor_back();
+    /// assert_eq!(cursor.value(), Some(&"b"));
+    /// cursor.move_prev();
+    /// assert_eq!(cursor.value(), Some(&"a"));
+    /// ```
+    pub fn cursor_back(&mut self) -> Cursor<'_, K, V, A, C, I> {
+        Cursor(self.cursor_back_mut())
+    }
+
+    /// Returns a cursor pointing at the entry which have the minimum key
+    /// that satisfies "greater than or equal to `key`" condition in the map.
+    ///
+    /// If there is no such entry, the cursor points at the "ghost" non-element.
+    ///
+    /// # Examples
+    /// ```
+    /// use splay_tree::SplayMap;
+    ///
+    /// let mut map: SplayMap<_, _> = vec![(1, "a"), (3, "c")].into_iter().collect();
+    /// assert_eq!(map.cursor_at(&1).key(), Some(&1));
+    /// assert_eq!(map.cursor_at(&2).key(), Some(&3));
+    /// assert_eq!(map.cursor_at(&4).key(), None);
+    /// ```
+    pub fn cursor_at<Q: ?Sized>(&mut self, key: &Q) -> Cursor<'_, K, V, A, C, I>
+    where
+        K: Borrow<Q>,
+        C: Compare<Q>,
+    {
+        Cursor(self.cursor_at_mut(key))
+    }
+
+    /// Returns a mutable cursor pointing at the entry which have the minimum key in the map.
+    ///
+    /// If the map is empty, the cursor points at the "ghost" non-element.
+    ///
+    /// # Examples
+    /// ```
+    /// use splay_tree::SplayMap;
+    ///
+    /// let mut map: SplayMap<_, _> = (0..10).map(|i| (i, i * 10)).collect();
+    /// {
+    ///     // Removes the entries with odd keys, and doubles the rest.
+    ///     let mut cursor = map.cursor_front_mut();
+    ///     while let Some(&k) = cursor.key() {
+    ///         if k % 2 == 1 {
+    ///             cursor.remove_current();
+    ///         } else {
+    ///             *cursor.value_mut().unwrap() *= 2;
+    ///             cursor.move_next();
+    ///         }
+    ///     }
+    /// }
+    /// assert_eq!(map.into_iter().collect::<Vec<_>>(),
+    ///            [(0, 0), (2, 40), (4, 80), (6, 120), (8, 160)]);
+    /// ```
+    pub fn cursor_front_mut(&mut self) -> CursorMut<'_, K, V, A, C, I> {
+        let ghost = self.tree.get_lftmost().is_none();
+        CursorMut {
+            tree: &mut self.tree,
+            ghost,
+        }
+    }
+
+    /// Returns a mutable cursor pointing at the entry which have the maximum key in the map.
+    ///
+    /// If the map is empty, the cursor points at the "ghost" non-element.
+    ///
+    /// # Examples
+    /// ```
+    /// use splay_tree::SplayMap;
+    ///
+    /// let mut map: SplayMap<_, _> = vec![(1, "a"), (5, "e")].into_iter().collect();
+    /// {
+    ///     let mut cursor = map.cursor_back_mut();
+    ///     cursor.insert_before(3, "c");
+    ///     cursor.insert_after(7, "g");
+    ///     assert_eq!(cursor.key(), Some(&5));
+    /// }
+    /// assert_eq!(map.keys().cloned().collect::<Vec<_>>(), [1, 3, 5, 7]);
+    /// ```
+    pub fn cursor_back_mut(&mut self) -> CursorMut<'_, K, V, A, C, I> {
+        let ghost = self.tree.get_rgtmost().is_none();
+        CursorMut {
+            tree: &mut self.tree,
+            ghost,
+        }
+    }
+
+    /// Returns a mutable cursor pointing at the entry which have the minimum key
+    /// that satisfies "greater than or equal to `key`" condition in the map.
+    ///
+    /// If there is no such entry, the cursor points at the "ghost" non-element.
+    ///
+    /// # Examples
+    /// ```
+    /// use splay_tree::SplayMap;
+    ///
+    /// let mut map: SplayMap<_, _> = (0..10).map(|i| (i, ())).collect();
+    /// {
+    ///     let mut cursor = map.cursor_at_mut(&3);
+    ///     cursor.remove_current();
+    ///     cursor.remove_current();
+    ///     assert_eq!(cursor.key(), Some(&5));
+    /// }
+    /// assert_eq!(map.len(), 8);
+    /// ```
+    pub fn cursor_at_mut<Q: ?Sized>(&mut self, key: &Q) -> CursorMut<'_, K, V, A, C, I>
+    where
+        K: Borrow<Q>,
+        C: Compare<Q>,
+    {
+        let ghost = !self.tree.splay_lower_bound(key);
+        CursorMut {
+            tree: &mut self.tree,
+            ghost,
+        }
+    }
+
+    /// Checks the internal structure of the map, and returns an error describing the corruption if any.
+    ///
+    /// This verifies that the keys are in strictly ascending order, and that every node in the arena
+    /// is reachable from the root exactly once (i.e., the links have no cycles).
+    /// It takes linear time, and is intended for debugging.
+    ///
+    /// If the `check-invariants` feature is enabled, this is run after every mutating operation
+    /// in debug builds, which panic on a corruption.
+    ///
+    /// # Examples
+    /// ```
+    /// use splay_tree::SplayMap;
+    ///
+    /// let mut map: SplayMap<_, _> = (0..100).map(|i| (i, i)).collect();
+    /// map.remove(&50);
+    /// map.split_off(&70);
+    /// assert_eq!(map.check_invariants(), Ok(()));
+    /// ```
+    pub fn check_invariants(&self) -> Result<(), InvariantError> {
+        self.tree.check_invariants()
+    }
+}
+impl<K, V, A, C, I> SplayMap<K, V, A, C, I>
+where
+    C: Compare<K>,
+    A: Counted<K, V>,
+    I: Index,
+{
+    /// Returns the number of keys in the map which are less than `key`,
+    /// or `None` if the map does not contain `key`.
+    ///
+    /// # Examples
+    /// ```
+    /// use splay_tree::SplayMap;
+    /// use splay_tree::augment::Count;
+    ///
+    /// let mut map: SplayMap<_, _, Count> = SplayMap::default();
+    /// map.insert("foo", 1);
+    /// map.insert("bar", 2);
+    ///
+    /// assert_eq!(map.rank("bar"), Some(0));
+    /// assert_eq!(map.rank("foo"), Some(1));
+    /// assert_eq!(map.rank("baz"), None);
+    /// ```
+    pub fn rank<Q: ?Sized>(&mut self, key: &Q) -> Option<usize>
+    where
+        K: Borrow<Q>,
+        C: Compare<Q>,
+    {
+        self.tree.rank(key)
+    }
+
+    /// Gets the entry which has the `index`-th smallest key in the map (zero-based).
+    ///
+    /// # Examples
+    /// ```
+    /// use splay_tree::SplayMap;
+    /// use splay_tree::augment::Count;
+    ///
+    /// let mut map: SplayMap<_, _, Count> = SplayMap::default();
+    /// map.insert("foo", 1);
+    /// map.insert("bar", 2);
+    ///
+    /// assert_eq!(map.select(0), Some((&"bar", &2)));
+    /// assert_eq!(map.select(1), Some((&"foo", &1)));
+    /// assert_eq!(map.select(2), None);
+    /// ```
+    pub fn select(&mut self, index: usize) -> Option<(&K, &V)> {
+        match self.tree.select(index) {
+            Some(i) => Some(self.tree.node_ref(i).into()),
+            None => None,
+        }
+    }
+
+    /// Returns the number of entries whose keys are contained in `range`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if range `start > end`.
+    /// Panics if range `start == end` and both bounds are `Excluded`.
+    ///
+    /// # Examples
+    /// ```
+    /// use splay_tree::SplayMap;
+    /// use splay_tree::augment::Count;
+    ///
+    /// let mut map: SplayMap<_, _, Count> = (0..10).map(|i| (i * 10, i)).collect();
+    /// assert_eq!(map.count_range(15..45), 3);
+    /// assert_eq!(map.count_range(..=50), 6);
+    /// assert_eq!(map.count_range(..), 10);
+    /// ```
+    pub fn count_range<Q: ?Sized, R>(&mut self, range: R) -> usize
+    where
+        K: Borrow<Q>,
+        C: Compare<Q>,
+        R: RangeBounds<Q>,
+    {
+        self.tree.count_range(range)
+    }
+}
+impl<K, V, A, C, I> SplayMap<K, V, A, C, I>
+where
+    C: Compare<K>,
+    A: Folded<K, V>,
+    I: Index,
+{
+    /// Folds the entries whose keys are contained in `range` with the monoid of the augmentation.
+    ///
+    /// # Panics
+    ///
+    /// Panics if range `start > end`.
+    /// Panics if range `start == end` and both bounds are `Excluded`.
+    ///
+    /// # Examples
+    /// ```
+    /// use splay_tree::SplayMap;
+    /// use splay_tree::augment::{Fold, Monoid};
+    ///
+    /// #[derive(Debug, PartialEq)]
+    /// struct Volume(u64);
+    /// impl<K> Monoid<K, u64> for Volume {
+    ///     fn identity() -> Self {
+    ///         Volume(0)
+    ///     }
+    ///     fn lift(_timestamp: &K, volume: &u64) -> Self {
+    ///         Volume(*volume)
+    ///     }
+    ///     fn combine(&self, other: &Self) -> Self {
+    ///         Volume(self.0 + other.0)
+    ///     }
+    /// }
+    ///
+    /// let mut map: SplayMap<u32, u64, Fold<Volume>> = SplayMap::default();
+    /// map.insert(100, 3);
+    /// map.insert(200, 5);
+    /// map.insert(300, 7);
+    ///
+    /// assert_eq!(map.fold_range(150..=300), Volume(12));
+    /// assert_eq!(map.fold_range(..), Volume(15));
+    /// assert_eq!(map.fold_range(400..), Volume(0));
+    /// ```
+    pub fn fold_range<Q: ?Sized, R>(&mut self, range: R) -> A::Monoid
+    where
+        K: Borrow<Q>,
+        C: Compare<Q>,
+        R: RangeBounds<Q>,
+    {
+        self.tree.fold_range(range)
+    }
+}
+impl<K, V, A, C, I> SplayMap<K, V, A, C, I>
+where
+    C: Compare<K>,
+    A: Tagged<K, V>,
+    I: Index,
+{
+    /// Applies `tag` to the values of the entries whose keys are contained in `range`.
+    ///
+    /// The values are updated lazily in `O(log n)` amortized time:
+    /// they are brought up to date as they are visited by the operations which take `&mut self`.
+    /// Since `Lazy` does not implement `Eager`, the values of such a map are only exposed
+    /// through the operations taking `&mut self` (e.g., `get` and `iter_mut`).
+    ///
+    /// # Panics
+    ///
+    /// Panics if range `start > end`.
+    /// Panics if range `start == end` and both bounds are `Excluded`.
+    ///
+    /// # Examples
+    /// ```
+    /// use splay_tree::SplayMap;
+    /// use splay_tree::augment::{Lazy, Tag};
+    ///
+    /// #[derive(Clone)]
+    /// struct Add(i32);
+    /// impl<K> Tag<K, i32> for Add {
+    ///     fn apply(&self, _key: &K, value: &mut i32) {
+    ///         *value += self.0;
+    ///     }
+    ///     fn compose(&mut self, later: &Self) {
+    ///         self.0 += later.0;
+    ///     }
+    /// }
+    ///
+    /// let mut map: SplayMap<_, _, Lazy<Add>> = (0..8).map(|k| (k, 0)).collect();
+    /// map.update_range(2..5, Add(5));
+    /// map.update_range(4.., Add(1));
+    /// assert_eq!(map.get(&4), Some(&6));
+    ///
+    /// assert_eq!(map.values_mut().map(|v| *v).collect::<Vec<_>>(), [0, 0, 5, 5, 6, 1, 1, 1]);
+    /// ```
+    pub fn update_range<Q: ?Sized, R>(&mut self, range: R, tag: A::Tag)
+    where
+        K: Borrow<Q>,
+        C: Compare<Q>,
+        R: RangeBounds<Q>,
+    {
+        self.tree.update_range(range, &tag)
+    }
+
+    /// Pushes all of the pending lazy updates down to the values.
+    ///
+    /// # Examples
+    /// ```
+    /// use splay_tree::SplayMap;
+    /// use splay_tree::augment::{Lazy, Tag};
+    ///
+    /// #[derive(Clone)]
+    /// struct Assign(char);
+    /// impl<K> Tag<K, char> for Assign {
+    ///     fn apply(&self, _key: &K, value: &mut char) {
+    ///         *value = self.0;
+    ///     }
+    ///     fn compose(&mut self, later: &Self) {
+    ///         self.0 = later.0;
+    ///     }
+    /// }
+    ///
+    /// let mut map: SplayMap<_, _, Lazy<Assign>> = (0..4).map(|k| (k, 'a')).collect();
+    /// map.update_range(1.., Assign('b'));
+    /// map.update_range(..3, Assign('c'));
+    /// map.flush();
+    /// assert_eq!(map.values_mut().map(|v| *v).collect::<String>(), "cccb");
+    /// ```
+    pub fn flush(&mut self) {
+        self.tree.flush();
+    }
+}
+impl<K, V, A, C, I> SplayMap<K, V, A, C, I>
+where
+    C: Compare<K>,
+    I: Index,
+{
+    /// Returns true if the map contains a value for the specified key,
+    /// without splaying the tree.
+    ///
+    /// Unlike `contains_key`, this function only requires `&self`,
+    /// but the cost is proportional to the current depth of the key
+    /// rather than `O(log n)` amortized.
+    ///
+    /// # Examples
+    /// ```
+    /// use splay_tree::SplayMap;
+    ///
+    /// let mut map = SplayMap::new();
+    /// map.insert("foo", 1);
+    ///
+    /// let map = &map;
+    /// assert!(map.peek_contains_key("foo"));
+    /// assert!(!map.peek_contains_key("bar"));
+    /// ```
+    pub fn peek_contains_key<Q: ?Sized>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        C: Compare<Q>,
+    {
+        self.tree.find_ref(key).is_some()
+    }
+
+    /// Returns a reference to the value corresponding to the key,
+    /// without splaying the tree.
+    ///
+    /// # Examples
+    /// ```
+    /// use splay_tree::SplayMap;
+    ///
+    /// let mut map = SplayMap::new();
+    /// map.insert("foo", 1);
+    ///
+    /// let map = &map;
+    /// assert_eq!(map.peek_get("foo"), Some(&1));
+    /// assert_eq!(map.peek_get("bar"), None);
+    /// ```
+    pub fn peek_get<Q: ?Sized>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        C: Compare<Q>,
+        A: Eager<K, V>,
+    {
+        self.tree.find_ref(key).map(|node| &node.val)
+    }
+
+    /// Finds a minimum key which satisfies "greater than or equal to `key`" condition in the map,
+    /// without splaying the tree.
+    ///
+    /// # Examples
+    /// ```
+    /// use splay_tree::SplayMap;
+    ///
+    /// let mut map = SplayMap::new();
+    /// map.insert(1, ());
+    /// map.insert(3, ());
+    ///
+    /// let map = &map;
+    /// assert_eq!(map.peek_lower_bound_key(&0), Some(&1));
+    /// assert_eq!(map.peek_lower_bound_key(&1), Some(&1));
+    /// assert_eq!(map.peek_lower_bound_key(&4), None);
+    /// ```
+    pub fn peek_lower_bound_key<Q: ?Sized>(&self, key: &Q) -> Option<&K>
+    where
+        K: Borrow<Q>,
+        C: Compare<Q>,
+    {
+        self.tree.lower_bound_ref(key).map(|node| &node.key)
+    }
+
+    /// Finds a minimum key which satisfies "greater than `key`" condition in the map,
+    /// without splaying the tree.
+    ///
+    /// # Examples
+    /// ```
+    /// use splay_tree::SplayMap;
+    ///
+    /// let mut map = SplayMap::new();
+    /// map.insert(1, ());
+    /// map.insert(3, ());
+    ///
+    /// let map = &map;
+    /// assert_eq!(map.peek_upper_bound_key(&0), Some(&1));
+    /// assert_eq!(map.peek_upper_bound_key(&1), Some(&3));
+    /// assert_eq!(map.peek_upper_bound_key(&4), None);
+    /// ```
+    pub fn peek_upper_bound_key<Q: ?Sized>(&self, key: &Q) -> Option<&K>
+    where
+        K: Borrow<Q>,
+        C: Compare<Q>,
+    {
+        self.tree.upper_bound_ref(key).map(|node| &node.key)
+    }
+}
+impl<K, V, A, C, I> SplayMap<K, V, A, C, I>
+where
+    I: Index,
+{
+    /// Returns the number of elements in the map.
+    ///
+    /// # Examples
+    /// ```
+    /// use splay_tree::SplayMap;
+    ///
+    /// let mut map = SplayMap::new();
+    /// map.insert("foo", 1);
+    /// map.insert("bar", 2);
+    /// assert_eq!(map.len(), 2);
+    /// ```
+    pub fn len(&self) -> usize {
+        self.tree.len()
+    }
+
+    /// Returns true if the map contains no elements.
+    ///
+    /// #  Examples
+    /// ```
+    /// use splay_tree::SplayMap;
+    ///
+    /// let mut map = SplayMap::new();
+    /// assert!(map.is_empty());
+    ///
+    /// map.insert("foo", 1);
+    /// assert!(!map.is_empty());
+    ///
+    /// map.clear();
+    /// assert!(map.is_empty());
+    /// ```
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the number of entries the map can hold without reallocating.
+    ///
+    /// # Examples
+    /// ```
+    /// use splay_tree::SplayMap;
+    ///
+    /// let map: SplayMap<u8, u8> = SplayMap::with_capacity(10);
+    /// assert!(map.capacity() >= 10);
+    /// ```
+    pub fn capacity(&self) -> usize {
+        self.tree.capacity()
+    }
+
+    /// Reserves capacity for at least `additional` more entries to be inserted in the map.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the new capacity exceeds the limit of the index type
+    /// or overflows `usize`.
+    ///
+    /// # Examples
+    /// ```
+    /// use splay_tree::SplayMap;
+    ///
+    /// let mut map = SplayMap::new();
+    /// map.insert("foo", 1);
+    /// map.reserve(10);
+    /// assert!(map.capacity() >= 11);
+    /// ```
+    pub fn reserve(&mut self, additional: usize) {
+        self.tree.reserve(additional);
+    }
+
+    /// Tries to reserve capacity for at least `additional` more entries to be inserted in the map.
+    ///
+    /// Unlike `reserve`, this returns an error instead of panicking or aborting
+    /// if the capacity exceeds the limit of the index type or the allocator fails.
+    ///
+    /// # Examples
+    /// ```
+    /// use splay_tree::SplayMap;
+    /// use splay_tree::compare::Natural;
+    /// use splay_tree::error::TryReserveError;
+    ///
+    /// let mut map: SplayMap<u8, u8, (), Natural, u16> = SplayMap::default();
+    /// assert_eq!(map.try_reserve(10), Ok(()));
+    /// assert!(map.capacity() >= 10);
+    /// assert_eq!(map.try_reserve(65536), Err(TryReserveError::CapacityOverflow));
+    /// ```
+    pub fn try_reserve(&mut self, additional: usize) -> Result<(), TryReserveError> {
+        self.tree.try_reserve(additional)
+    }
+
+    /// Shrinks the capacity of the map as much as possible.
+    ///
+    /// # Examples
+    /// ```
+    /// use splay_tree::SplayMap;
+    ///
+    /// let mut map = SplayMap::with_capacity(100);
+    /// map.insert("foo", 1);
+    /// map.shrink_to_fit();
+    /// assert!(map.capacity() < 100);
+    /// ```
+    pub fn shrink_to_fit(&mut self) {
+        self.tree.shrink_to_fit();
+    }
+
+    /// Returns the policy which decides how the lookups restructure the map.
+    ///
+    /// # Examples
+    /// ```
+    /// use splay_tree::SplayMap;
+    /// use splay_tree::policy::SplayPolicy;
+    ///
+    /// let map: SplayMap<u8, u8> = SplayMap::new();
+    /// assert_eq!(map.splay_policy(), SplayPolicy::Full);
+    /// ```
+    pub fn splay_policy(&self) -> SplayPolicy {
+        self.tree.policy()
+    }
+
+    /// Sets the policy which decides how the lookups restructure the map.
+    ///
+    /// The policy applies to `contains_key`, `get` and `get_mut`
+    /// (except that `get_mut` always splays the entry fully if the map has an augmentation).
+    /// See [`policy`](../policy/index.html) for details.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the probability of `SplayPolicy::Probability` is not in the range from 0 to 1.
+    ///
+    /// # Examples
+    /// ```
+    /// use splay_tree::SplayMap;
+    /// use splay_tree::policy::SplayPolicy;
+    ///
+    /// let mut map = (0..10).map(|i| (i, i)).collect::<SplayMap<_, _>>();
+    /// map.set_splay_policy(SplayPolicy::Semi);
+    /// map.get_mut(&3).map(|v| *v = 30);
+    /// assert_eq!(map.get(&3), Some(&30));
+    /// assert!(map.contains_key(&9));
+    /// ```
+    pub fn set_splay_policy(&mut self, policy: SplayPolicy) {
+        self.tree.set_policy(policy);
+    }
+
+    /// Gets the entry which have the minimum key in the map, without splaying the tree.
+    ///
+    /// # Examples
+    /// ```
+    /// use splay_tree::SplayMap;
+    ///
+    /// let map: SplayMap<_, _> = vec![(3, "c"), (1, "a"), (2, "b")].into_iter().collect();
+    /// assert_eq!(map.first(), Some((&1, &"a")));
+    /// ```
+    pub fn first(&self) -> Option<(&K, &V)>
+    where
+        A: Eager<K, V>,
+    {
+        self.tree.lftmost_ref().map(|node| node.into())
+    }
+
+    /// Gets the entry which have the maximum key in the map, without splaying the tree.
+    ///
+    /// # Examples
+    /// ```
+    /// use splay_tree::SplayMap;
+    ///
+    /// let map: SplayMap<_, _> = vec![(3, "c"), (1, "a"), (2, "b")].into_iter().collect();
+    /// assert_eq!(map.last(), Some((&3, &"c")));
+    /// ```
+    pub fn last(&self) -> Option<(&K, &V)>
+    where
+        A: Eager<K, V>,
+    {
+        self.tree.rgtmost_ref().map(|node| node.into())
+    }
+
+    /// Gets an iterator over the entries of the map, sorted by key.
+    ///
+    /// # Examples
+    /// ```
+    /// use splay_tree::SplayMap;
+    ///
+    /// let map: SplayMap<_, _> =
+    ///     vec![("foo", 1), ("bar", 2), ("baz", 3)].into_iter().collect();
+    /// assert_eq!(vec![(&"bar", &2), (&"baz", &3), (&"foo", &1)],
+    ///            map.iter().collect::<Vec<_>>());
+    /// ```
+    pub fn iter(&self) -> Iter<'_, K, V, A, I>
+    where
+        A: Eager<K, V>,
+    {
+        Iter::new(&self.tree)
+    }
+
+    /// Gets a mutable iterator over the entries of the map, soretd by key.
+    ///
+    /// # Examples
+    /// ```
+    /// use splay_tree::SplayMap;
+    ///
+    /// let mut map: SplayMap<_, _> =
+    ///     vec![("foo", 1), ("bar", 2), ("baz", 3)].into_iter().collect();
+    /// for (_, v) in map.iter_mut() {
+    ///    *v += 10;
+    /// }
+    /// assert_eq!(map.get("bar"), Some(&12));
+    /// ```
+    pub fn iter_mut(&mut self) -> IterMut<K, V, A, I>
+    where
+        A: Augment<K, V>,
+    {
+        IterMut::new(&mut self.tree)
+    }
+
+    /// Gets an iterator over the keys of the map, in sorted order.
+    ///
+    /// # Examples
+    /// ```
+    /// use splay_tree::SplayMap;
+    ///
+    /// let map: SplayMap<_, _> =
+    ///     vec![("foo", 1), ("bar", 2), ("baz", 3)].into_iter().collect();
+    /// assert_eq!(vec!["bar", "baz", "foo"],
+    ///            map.keys().cloned().collect::<Vec<_>>());
+    /// ```
+    pub fn keys(&self) -> Keys<K, V, A, I> {
+        Keys::new(&self.tree)
+    }
+
+    /// Gets an iterator over the values of the map, in order by key.
+    ///
+    /// # Examples
+    /// ```
+    /// use splay_tree::SplayMap;
+    ///
+    /// let map: SplayMap<_, _> =
+    ///     vec![("foo", 1), ("bar", 2), ("baz", 3)].into_iter().collect();
+    /// assert_eq!(vec![2, 3, 1],
+    ///            map.values().cloned().collect::<Vec<_>>());
+    /// ```
+    pub fn values(&self) -> Values<'_, K, V, A, I>
+    where
+        A: Eager<K, V>,
+    {
+        Values::new(&self.tree)
+    }
+
+    /// Gets a mutable iterator over the values of the map, in order by key.
+    ///
+    /// # Examples
+    /// ```
+    /// use splay_tree::SplayMap;
+    ///
+    /// let mut map: SplayMap<_, _> =
+    ///     vec![("foo", 1), ("bar", 2), ("baz", 3)].into_iter().collect();
+    /// for v in map.values_mut() {
+    ///     *v += 10;
+    /// }
     /// assert_eq!(vec![12, 13, 11],
     ///            map.values().cloned().collect::<Vec<_>>());
     /// ```
-    pub fn values_mut(&mut self) -> ValuesMut<K, V> {
+    pub fn values_mut(&mut self) -> ValuesMut<K, V, A, I>
+    where
+        A: Augment<K, V>,
+    {
         ValuesMut::new(&mut self.tree)
     }
 }
-impl<K, V> Default for SplayMap<K, V>
+impl<K, V, A, C, I> std::hash::Hash for SplayMap<K, V, A, C, I>
+where
+    K: std::hash::Hash,
+    V: std::hash::Hash,
+    A: Eager<K, V>,
+    I: Index,
+{
+    fn hash<H>(&self, state: &mut H)
+    where
+        H: std::hash::Hasher,
+    {
+        self.tree.hash(state);
+    }
+}
+impl<K, V, A, C, I> PartialEq for SplayMap<K, V, A, C, I>
+where
+    K: PartialEq,
+    V: PartialEq,
+    A: Eager<K, V>,
+    I: Index,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.tree == other.tree
+    }
+}
+impl<K, V, A, C, I> Eq for SplayMap<K, V, A, C, I>
+where
+    K: Eq,
+    V: Eq,
+    A: Eager<K, V>,
+    I: Index,
+{
+}
+impl<K, V, A, C, I> PartialOrd for SplayMap<K, V, A, C, I>
+where
+    K: PartialOrd,
+    V: PartialOrd,
+    A: Eager<K, V>,
+    I: Index,
+{
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        self.tree.partial_cmp(&other.tree)
+    }
+}
+impl<K, V, A, C, I> Ord for SplayMap<K, V, A, C, I>
 where
     K: Ord,
+    V: Ord,
+    A: Eager<K, V>,
+    I: Index,
+{
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.tree.cmp(&other.tree)
+    }
+}
+impl<K, V, A, C, I> Default for SplayMap<K, V, A, C, I>
+where
+    C: Compare<K> + Default,
+    A: Augment<K, V>,
+    I: Index,
 {
     fn default() -> Self {
-        SplayMap::new()
+        SplayMap {
+            tree: tree_core::Tree::new(),
+        }
     }
 }
-impl<K, V> std::iter::FromIterator<(K, V)> for SplayMap<K, V>
+impl<K, V, A, C, I> std::iter::FromIterator<(K, V)> for SplayMap<K, V, A, C, I>
 where
-    K: Ord,
+    C: Compare<K> + Default,
+    A: Augment<K, V>,
+    I: Index,
 {
-    fn from_iter<I>(iter: I) -> Self
+    fn from_iter<It>(iter: It) -> Self
     where
-        I: IntoIterator<Item = (K, V)>,
+        It: IntoIterator<Item = (K, V)>,
     {
-        let mut map = SplayMap::new();
-        for (k, v) in iter {
-            map.insert(k, v);
-        }
+        let mut map = SplayMap::default();
+        map.extend(iter);
         map
     }
 }
-impl<'a, K, V> IntoIterator for &'a SplayMap<K, V>
+#[cfg(feature = "serde")]
+impl<K, V, A, C, I> ::serde::Serialize for SplayMap<K, V, A, C, I>
+where
+    K: ::serde::Serialize,
+    V: ::serde::Serialize,
+    C: Compare<K>,
+    A: Eager<K, V>,
+    I: Index,
+{
+    /// Serializes the map as a map of the entries in ascending order of the keys,
+    /// in the same representation as `BTreeMap`.
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: ::serde::Serializer,
+    {
+        use serde::ser::SerializeMap;
+        let mut map = serializer.serialize_map(Some(self.len()))?;
+        for (k, v) in self.tree.iter() {
+            map.serialize_entry(k, v)?;
+        }
+        map.end()
+    }
+}
+#[cfg(feature = "serde")]
+impl<'de, K, V, A, C, I> ::serde::Deserialize<'de> for SplayMap<K, V, A, C, I>
+where
+    K: ::serde::Deserialize<'de>,
+    V: ::serde::Deserialize<'de>,
+    C: Compare<K> + Default,
+    A: Augment<K, V>,
+    I: Index,
+{
+    /// Deserializes a map of the entries, and links them into a perfectly balanced tree.
+    ///
+    /// The comparator is made by `Default`.
+    /// Fails if the keys are not sorted in strictly ascending order by the comparator,
+    /// or the number of the entries exceeds the limit of the index type.
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: ::serde::Deserializer<'de>,
+    {
+        struct Visitor<K, V, A, C, I>(std::marker::PhantomData<SplayMap<K, V, A, C, I>>);
+        impl<'de, K, V, A, C, I> ::serde::de::Visitor<'de> for Visitor<K, V, A, C, I>
+        where
+            K: ::serde::Deserialize<'de>,
+            V: ::serde::Deserialize<'de>,
+            C: Compare<K> + Default,
+            A: Augment<K, V>,
+            I: Index,
+        {
+            type Value = SplayMap<K, V, A, C, I>;
+
+            fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                f.write_str("a map")
+            }
+            fn visit_map<M>(self, mut access: M) -> Result<Self::Value, M::Error>
+            where
+                M: ::serde::de::MapAccess<'de>,
+            {
+                let tree =
+                    tree_core::Tree::deserialize_sorted(C::default(), || access.next_entry())?;
+                Ok(SplayMap { tree })
+            }
+        }
+        deserializer.deserialize_map(Visitor(std::marker::PhantomData))
+    }
+}
+impl<'a, K, V, A, C, I> IntoIterator for &'a SplayMap<K, V, A, C, I>
+where
+    K: 'a,
+    V: 'a,
+    A: Eager<K, V>,
+    I: Index,
+{
+    type Item = (&'a K, &'a V);
+    type IntoIter = Iter<'a, K, V, A, I>;
+    fn into_iter(self) -> Self::IntoIter {
+        Iter::new(&self.tree)
+    }
+}
+impl<'a, K, V, A, C, I> IntoIterator for &'a mut SplayMap<K, V, A, C, I>
+where
+    K: 'a,
+    V: 'a,
+    A: Augment<K, V>,
+    I: Index,
+{
+    type Item = (&'a K, &'a mut V);
+    type IntoIter = IterMut<'a, K, V, A, I>;
+    fn into_iter(self) -> Self::IntoIter {
+        IterMut::new(&mut self.tree)
+    }
+}
+impl<K, V, A, C, I> IntoIterator for SplayMap<K, V, A, C, I>
+where
+    A: Augment<K, V>,
+    I: Index,
+{
+    type Item = (K, V);
+    type IntoIter = IntoIter<K, V, A, I>;
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter::new(self.tree)
+    }
+}
+impl<K, V, A, C, I> Extend<(K, V)> for SplayMap<K, V, A, C, I>
+where
+    C: Compare<K>,
+    A: Augment<K, V>,
+    I: Index,
+{
+    fn extend<T>(&mut self, iter: T)
+    where
+        T: IntoIterator<Item = (K, V)>,
+    {
+        self.tree.extend(iter);
+    }
+}
+impl<'a, K, V, A, C, I> Extend<(&'a K, &'a V)> for SplayMap<K, V, A, C, I>
+where
+    K: 'a + Copy,
+    V: 'a + Copy,
+    A: Augment<K, V>,
+    C: Compare<K>,
+    I: Index,
+{
+    fn extend<T>(&mut self, iter: T)
+    where
+        T: IntoIterator<Item = (&'a K, &'a V)>,
+    {
+        self.tree.extend(iter.into_iter().map(|(k, v)| (*k, *v)));
+    }
+}
+
+/// An iterator over a SplayMap's entries.
+pub struct Iter<'a, K: 'a, V: 'a, A: 'a = (), I: 'a = u32>(iter::Iter<'a, K, V, A, I>);
+impl<'a, K: 'a, V: 'a, A: 'a, I: 'a> Iter<'a, K, V, A, I>
+where
+    I: Index,
+{
+    fn new<C>(tree: &'a tree_core::Tree<K, V, A, C, I>) -> Self {
+        Iter(tree.iter())
+    }
+}
+impl<'a, K: 'a, V: 'a, A: 'a, I: 'a> Iterator for Iter<'a, K, V, A, I>
+where
+    I: Index,
+{
+    type Item = (&'a K, &'a V);
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next()
+    }
+}
+impl<'a, K: 'a, V: 'a, A: 'a, I: 'a> DoubleEndedIterator for Iter<'a, K, V, A, I>
+where
+    I: Index,
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.0.next_back()
+    }
+}
+
+/// A mutable iterator over a SplayMap's entries.
+pub struct IterMut<'a, K: 'a, V: 'a, A: 'a = (), I: 'a = u32>(iter::IterMut<'a, K, V, A, I>);
+impl<'a, K: 'a, V: 'a, A: 'a, I: 'a> IterMut<'a, K, V, A, I>
+where
+    A: Augment<K, V>,
+    I: Index,
+{
+    fn new<C>(tree: &'a mut tree_core::Tree<K, V, A, C, I>) -> Self {
+        IterMut(tree.iter_mut())
+    }
+}
+impl<'a, K: 'a, V: 'a, A: 'a, I: 'a> Iterator for IterMut<'a, K, V, A, I>
+where
+    I: Index,
+{
+    type Item = (&'a K, &'a mut V);
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next()
+    }
+}
+impl<'a, K: 'a, V: 'a, A: 'a, I: 'a> DoubleEndedIterator for IterMut<'a, K, V, A, I>
+where
+    I: Index,
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.0.next_back()
+    }
+}
+
+/// An owning iterator over a SplayMap's entries.
+pub struct IntoIter<K, V, A = (), I = u32>(iter::IntoIter<K, V, A, I>)
+where
+    I: Index;
+impl<K, V, A, I> IntoIter<K, V, A, I>
+where
+    A: Augment<K, V>,
+    I: Index,
+{
+    fn new<C>(tree: tree_core::Tree<K, V, A, C, I>) -> Self {
+        IntoIter(tree.into_iter())
+    }
+}
+impl<K, V, A, I> Iterator for IntoIter<K, V, A, I>
+where
+    I: Index,
+{
+    type Item = (K, V);
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next()
+    }
+}
+impl<K, V, A, I> DoubleEndedIterator for IntoIter<K, V, A, I>
+where
+    I: Index,
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.0.next_back()
+    }
+}
+
+/// An iterator over a SplayMap's keys.
+pub struct Keys<'a, K: 'a, V: 'a, A: 'a = (), I: 'a = u32>(Iter<'a, K, V, A, I>);
+impl<'a, K: 'a, V: 'a, A: 'a, I: 'a> Keys<'a, K, V, A, I>
+where
+    I: Index,
+{
+    fn new<C>(tree: &'a tree_core::Tree<K, V, A, C, I>) -> Self {
+        Keys(Iter::new(tree))
+    }
+}
+impl<'a, K: 'a, V: 'a, A: 'a, I: 'a> Iterator for Keys<'a, K, V, A, I>
+where
+    I: Index,
+{
+    type Item = &'a K;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(|(k, _)| k)
+    }
+}
+impl<'a, K: 'a, V: 'a, A: 'a, I: 'a> DoubleEndedIterator for Keys<'a, K, V, A, I>
+where
+    I: Index,
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.0.next_back().map(|(k, _)| k)
+    }
+}
+
+/// An iterator over a SplayMap's values.
+pub struct Values<'a, K: 'a, V: 'a, A: 'a = (), I: 'a = u32>(Iter<'a, K, V, A, I>);
+impl<'a, K: 'a, V: 'a, A: 'a, I: 'a> Values<'a, K, V, A, I>
+where
+    I: Index,
+{
+    fn new<C>(tree: &'a tree_core::Tree<K, V, A, C, I>) -> Self {
+        Values(Iter::new(tree))
+    }
+}
+impl<'a, K: 'a, V: 'a, A: 'a, I: 'a> Iterator for Values<'a, K, V, A, I>
 where
-    K: 'a,
-    V: 'a,
+    I: Index,
 {
-    type Item = (&'a K, &'a V);
-    type IntoIter = Iter<'a, K, V>;
-    fn into_iter(self) -> Self::IntoIter {
-        Iter::new(&self.tree)
+    type Item = &'a V;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(|(_, v)| v)
     }
 }
-impl<'a, K, V> IntoIterator for &'a mut SplayMap<K, V>
+impl<'a, K: 'a, V: 'a, A: 'a, I: 'a> DoubleEndedIterator for Values<'a, K, V, A, I>
 where
-    K: 'a,
-    V: 'a,
+    I: Index,
 {
-    type Item = (&'a K, &'a mut V);
-    type IntoIter = IterMut<'a, K, V>;
-    fn into_iter(self) -> Self::IntoIter {
-        IterMut::new(&mut self.tree)
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.0.next_back().map(|(_, v)| v)
     }
 }
-impl<K, V> IntoIterator for SplayMap<K, V> {
-    type Item = (K, V);
-    type IntoIter = IntoIter<K, V>;
-    fn into_iter(self) -> Self::IntoIter {
-        IntoIter::new(self.tree)
+
+/// A mutable iterator over a SplayMap's values.
+pub struct ValuesMut<'a, K: 'a, V: 'a, A: 'a = (), I: 'a = u32>(IterMut<'a, K, V, A, I>);
+impl<'a, K: 'a, V: 'a, A: 'a, I: 'a> ValuesMut<'a, K, V, A, I>
+where
+    A: Augment<K, V>,
+    I: Index,
+{
+    fn new<C>(tree: &'a mut tree_core::Tree<K, V, A, C, I>) -> Self {
+        ValuesMut(IterMut::new(tree))
     }
 }
-impl<K, V> Extend<(K, V)> for SplayMap<K, V>
+impl<'a, K: 'a, V: 'a, A: 'a, I: 'a> Iterator for ValuesMut<'a, K, V, A, I>
 where
-    K: Ord,
+    I: Index,
 {
-    fn extend<T>(&mut self, iter: T)
-    where
-        T: IntoIterator<Item = (K, V)>,
-    {
-        for (k, v) in iter {
-            self.insert(k, v);
-        }
+    type Item = &'a mut V;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(|(_, v)| v)
     }
 }
-impl<'a, K, V> Extend<(&'a K, &'a V)> for SplayMap<K, V>
+impl<'a, K: 'a, V: 'a, A: 'a, I: 'a> DoubleEndedIterator for ValuesMut<'a, K, V, A, I>
 where
-    K: 'a + Copy + Ord,
-    V: 'a + Copy,
+    I: Index,
 {
-    fn extend<T>(&mut self, iter: T)
-    where
-        T: IntoIterator<Item = (&'a K, &'a V)>,
-    {
-        for (k, v) in iter {
-            self.insert(*k, *v);
-        }
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.0.next_back().map(|(_, v)| v)
     }
 }
 
-/// An iterator over a SplayMap's entries.
-pub struct Iter<'a, K: 'a, V: 'a>(iter::Iter<'a, K, V>);
-impl<'a, K: 'a, V: 'a> Iter<'a, K, V> {
-    fn new(tree: &'a tree_core::Tree<K, V>) -> Self {
-        Iter(tree.iter())
-    }
-}
-impl<'a, K: 'a, V: 'a> Iterator for Iter<'a, K, V> {
+/// An iterator over a sub-range of a SplayMap's entries.
+pub struct Range<'a, K: 'a, V: 'a, A: 'a = (), I: 'a = u32>(iter::Iter<'a, K, V, A, I>);
+impl<'a, K: 'a, V: 'a, A: 'a, I: 'a> Iterator for Range<'a, K, V, A, I>
+where
+    I: Index,
+{
     type Item = (&'a K, &'a V);
     fn next(&mut self) -> Option<Self::Item> {
         self.0.next()
     }
 }
-
-/// A mutable iterator over a SplayMap's entries.
-pub struct IterMut<'a, K: 'a, V: 'a>(iter::IterMut<'a, K, V>);
-impl<'a, K: 'a, V: 'a> IterMut<'a, K, V> {
-    fn new(tree: &'a mut tree_core::Tree<K, V>) -> Self {
-        IterMut(tree.iter_mut())
+impl<'a, K: 'a, V: 'a, A: 'a, I: 'a> DoubleEndedIterator for Range<'a, K, V, A, I>
+where
+    I: Index,
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.0.next_back()
     }
 }
-impl<'a, K: 'a, V: 'a> Iterator for IterMut<'a, K, V> {
+
+/// A mutable iterator over a sub-range of a SplayMap's entries.
+pub struct RangeMut<'a, K: 'a, V: 'a, A: 'a = (), I: 'a = u32>(iter::IterMut<'a, K, V, A, I>);
+impl<'a, K: 'a, V: 'a, A: 'a, I: 'a> Iterator for RangeMut<'a, K, V, A, I>
+where
+    I: Index,
+{
     type Item = (&'a K, &'a mut V);
     fn next(&mut self) -> Option<Self::Item> {
         self.0.next()
     }
 }
-
-/// An owning iterator over a SplayMap's entries.
-pub struct IntoIter<K, V>(iter::IntoIter<K, V>);
-impl<K, V> IntoIter<K, V> {
-    fn new(tree: tree_core::Tree<K, V>) -> Self {
-        IntoIter(tree.into_iter())
+impl<'a, K: 'a, V: 'a, A: 'a, I: 'a> DoubleEndedIterator for RangeMut<'a, K, V, A, I>
+where
+    I: Index,
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.0.next_back()
     }
 }
-impl<K, V> Iterator for IntoIter<K, V> {
+
+/// An iterator which removes the entries of a SplayMap matching a predicate.
+///
+/// This is created by the `extract_if` method on `SplayMap`.
+pub struct ExtractIf<'a, K: 'a, V: 'a, A: 'a, C: 'a, I: 'a, F>
+where
+    A: Augment<K, V>,
+    I: Index,
+    F: FnMut(&K, &mut V) -> bool,
+{
+    inner: tree_core::Extract<'a, K, V, A, C, I>,
+    pred: F,
+}
+impl<'a, K: 'a, V: 'a, A: 'a, C: 'a, I: 'a, F> Iterator for ExtractIf<'a, K, V, A, C, I, F>
+where
+    A: Augment<K, V>,
+    F: FnMut(&K, &mut V) -> bool,
+    I: Index,
+{
     type Item = (K, V);
     fn next(&mut self) -> Option<Self::Item> {
-        self.0.next()
+        let pred = &mut self.pred;
+        self.inner.next_by(|k, v| pred(k, v))
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
     }
 }
 
-/// An iterator over a SplayMap's keys.
-pub struct Keys<'a, K: 'a, V: 'a>(Iter<'a, K, V>);
-impl<'a, K: 'a, V: 'a> Keys<'a, K, V> {
-    fn new(tree: &'a tree_core::Tree<K, V>) -> Self {
-        Keys(Iter::new(tree))
+/// A draining iterator over a SplayMap's entries.
+///
+/// This is created by the `drain` method on `SplayMap`.
+/// The map is emptied as soon as this is created, so this owns the entries like `IntoIter`.
+pub struct Drain<K, V, A = (), I = u32>(iter::IntoIter<K, V, A, I>)
+where
+    I: Index;
+impl<K, V, A, I> Iterator for Drain<K, V, A, I>
+where
+    I: Index,
+{
+    type Item = (K, V);
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next()
     }
 }
-impl<'a, K: 'a, V: 'a> Iterator for Keys<'a, K, V> {
-    type Item = &'a K;
-    fn next(&mut self) -> Option<Self::Item> {
-        self.0.next().map(|(k, _)| k)
+impl<K, V, A, I> DoubleEndedIterator for Drain<K, V, A, I>
+where
+    I: Index,
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.0.next_back()
     }
 }
 
-/// An iterator over a SplayMap's values.
-pub struct Values<'a, K: 'a, V: 'a>(Iter<'a, K, V>);
-impl<'a, K: 'a, V: 'a> Values<'a, K, V> {
-    fn new(tree: &'a tree_core::Tree<K, V>) -> Self {
-        Values(Iter::new(tree))
+/// A cursor over a `SplayMap`.
+///
+/// A cursor points at either an entry of the map or the "ghost" non-element,
+/// which sits between the last and the first entries.
+/// Moving the cursor splays the entry it points at to the root of the tree,
+/// so visiting all of the entries one after another takes `O(n)` amortized time.
+///
+/// See `CursorMut` for a cursor which can also modify the map.
+pub struct Cursor<'a, K: 'a, V: 'a, A: 'a = (), C: 'a = Natural, I: 'a = u32>(CursorMut<'a, K, V, A, C, I>);
+impl<'a, K: 'a, V: 'a, A: 'a, C: 'a, I: 'a> Cursor<'a, K, V, A, C, I>
+where
+    C: Compare<K>,
+    A: Augment<K, V>,
+    I: Index,
+{
+    /// Returns a reference to the key of the entry which the cursor is pointing at,
+    /// or `None` if the cursor is pointing at the "ghost" non-element.
+    pub fn key(&self) -> Option<&K> {
+        self.0.key()
     }
-}
-impl<'a, K: 'a, V: 'a> Iterator for Values<'a, K, V> {
-    type Item = &'a V;
-    fn next(&mut self) -> Option<Self::Item> {
-        self.0.next().map(|(_, v)| v)
+
+    /// Returns a reference to the value of the entry which the cursor is pointing at,
+    /// or `None` if the cursor is pointing at the "ghost" non-element.
+    pub fn value(&self) -> Option<&V> {
+        self.0.value()
     }
-}
 
-/// A mutable iterator over a SplayMap's values.
-pub struct ValuesMut<'a, K: 'a, V: 'a>(IterMut<'a, K, V>);
-impl<'a, K: 'a, V: 'a> ValuesMut<'a, K, V> {
-    fn new(tree: &'a mut tree_core::Tree<K, V>) -> Self {
-        ValuesMut(IterMut::new(tree))
+    /// Moves the cursor to the next entry.
+    ///
+    /// If the cursor is pointing at the last entry, this moves it to the "ghost" non-element,
+    /// and if it is pointing at the "ghost", this moves it to the first entry.
+    pub fn move_next(&mut self) {
+        self.0.move_next()
+    }
+
+    /// Moves the cursor to the previous entry.
+    ///
+    /// If the cursor is pointing at the first entry, this moves it to the "ghost" non-element,
+    /// and if it is pointing at the "ghost", this moves it to the last entry.
+    pub fn move_prev(&mut self) {
+        self.0.move_prev()
     }
 }
-impl<'a, K: 'a, V: 'a> Iterator for ValuesMut<'a, K, V> {
-    type Item = &'a mut V;
-    fn next(&mut self) -> Option<Self::Item> {
-        self.0.next().map(|(_, v)| v)
+
+/// A cursor over a `SplayMap` with editing operations.
+///
+/// It can freely seek back and forth like `Cursor`, and can safely mutate the map
+/// during the walk, since its lifetime is tied to the mutable borrow of the map.
+pub struct CursorMut<'a, K: 'a, V: 'a, A: 'a = (), C: 'a = Natural, I: 'a = u32> {
+    // The current entry is always the root of the tree, unless the cursor is at the ghost.
+    tree: &'a mut tree_core::Tree<K, V, A, C, I>,
+    ghost: bool,
+}
+impl<'a, K: 'a, V: 'a, A: 'a, C: 'a, I: 'a> CursorMut<'a, K, V, A, C, I>
+where
+    C: Compare<K>,
+    A: Augment<K, V>,
+    I: Index,
+{
+    /// Returns a reference to the key of the entry which the cursor is pointing at,
+    /// or `None` if the cursor is pointing at the "ghost" non-element.
+    pub fn key(&self) -> Option<&K> {
+        if self.ghost {
+            None
+        } else {
+            Some(&self.tree.root_ref().key)
+        }
+    }
+
+    /// Returns a reference to the value of the entry which the cursor is pointing at,
+    /// or `None` if the cursor is pointing at the "ghost" non-element.
+    pub fn value(&self) -> Option<&V> {
+        if self.ghost {
+            None
+        } else {
+            Some(&self.tree.root_ref().val)
+        }
+    }
+
+    /// Returns a mutable reference to the value of the entry which the cursor is pointing at,
+    /// or `None` if the cursor is pointing at the "ghost" non-element.
+    pub fn value_mut(&mut self) -> Option<&mut V> {
+        if self.ghost {
+            None
+        } else {
+            Some(&mut self.tree.root_mut().val)
+        }
+    }
+
+    /// Moves the cursor to the next entry.
+    ///
+    /// If the cursor is pointing at the last entry, this moves it to the "ghost" non-element,
+    /// and if it is pointing at the "ghost", this moves it to the first entry.
+    pub fn move_next(&mut self) {
+        if self.ghost {
+            self.ghost = self.tree.get_lftmost().is_none();
+        } else {
+            self.ghost = !self.tree.splay_next();
+        }
+    }
+
+    /// Moves the cursor to the previous entry.
+    ///
+    /// If the cursor is pointing at the first entry, this moves it to the "ghost" non-element,
+    /// and if it is pointing at the "ghost", this moves it to the last entry.
+    pub fn move_prev(&mut self) {
+        if self.ghost {
+            self.ghost = self.tree.get_rgtmost().is_none();
+        } else {
+            self.ghost = !self.tree.splay_prev();
+        }
+    }
+
+    /// Removes the entry which the cursor is pointing at, and returns it.
+    ///
+    /// The cursor is moved to the next entry (or to the "ghost" if there is none).
+    /// If the cursor is pointing at the "ghost", this does nothing and returns `None`.
+    ///
+    /// # Examples
+    /// ```
+    /// use splay_tree::SplayMap;
+    ///
+    /// let mut map: SplayMap<_, _> = vec![(1, "a"), (2, "b")].into_iter().collect();
+    /// let mut cursor = map.cursor_front_mut();
+    /// assert_eq!(cursor.remove_current(), Some((1, "a")));
+    /// assert_eq!(cursor.key(), Some(&2));
+    /// assert_eq!(cursor.remove_current(), Some((2, "b")));
+    /// assert_eq!(cursor.key(), None);
+    /// assert_eq!(cursor.remove_current(), None);
+    /// ```
+    pub fn remove_current(&mut self) -> Option<(K, V)> {
+        if self.ghost {
+            None
+        } else {
+            let (entry, has_next) = self.tree.pop_root_splay_next();
+            self.ghost = !has_next;
+            Some(entry)
+        }
+    }
+
+    /// Inserts a new entry into the map just after the current one.
+    ///
+    /// If the cursor is pointing at the "ghost", the entry is inserted at the front of the map.
+    /// The cursor does not move.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `key` is not greater than the current key,
+    /// or not less than the next key.
+    ///
+    /// # Examples
+    /// ```
+    /// use splay_tree::SplayMap;
+    ///
+    /// let mut map: SplayMap<_, _> = vec![(1, "a"), (3, "c")].into_iter().collect();
+    /// {
+    ///     let mut cursor = map.cursor_front_mut();
+    ///     cursor.insert_after(2, "b");
+    ///     assert_eq!(cursor.key(), Some(&1));
+    /// }
+    /// assert_eq!(map.keys().cloned().collect::<Vec<_>>(), [1, 2, 3]);
+    /// ```
+    pub fn insert_after(&mut self, key: K, value: V) {
+        if self.ghost {
+            if self.tree.get_lftmost().is_some() {
+                assert!(
+                    self.compare_root(&key) == Ordering::Less,
+                    "the key inserted at the front must be less than the first key"
+                );
+            }
+            self.tree.insert(key, value);
+            return;
+        }
+        assert!(
+            self.compare_root(&key) == Ordering::Greater,
+            "the key inserted after the current one must be greater than it"
+        );
+        if self.tree.splay_next() && self.compare_root(&key) != Ordering::Less {
+            self.tree.splay_prev();
+            panic!("the key inserted after the current one must be less than the next key");
+        }
+        self.tree.insert(key, value);
+        self.tree.splay_prev();
+    }
+
+    /// Inserts a new entry into the map just before the current one.
+    ///
+    /// If the cursor is pointing at the "ghost", the entry is inserted at the back of the map.
+    /// The cursor does not move.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `key` is not less than the current key,
+    /// or not greater than the previous key.
+    ///
+    /// # Examples
+    /// ```
+    /// use splay_tree::SplayMap;
+    ///
+    /// let mut map: SplayMap<_, _> = vec![(1, "a"), (3, "c")].into_iter().collect();
+    /// {
+    ///     let mut cursor = map.cursor_back_mut();
+    ///     cursor.insert_before(2, "b");
+    ///     assert_eq!(cursor.key(), Some(&3));
+    /// }
+    /// assert_eq!(map.keys().cloned().collect::<Vec<_>>(), [1, 2, 3]);
+    /// ```
+    pub fn insert_before(&mut self, key: K, value: V) {
+        if self.ghost {
+            if self.tree.get_rgtmost().is_some() {
+                assert!(
+                    self.compare_root(&key) == Ordering::Greater,
+                    "the key inserted at the back must be greater than the last key"
+                );
+            }
+            self.tree.insert(key, value);
+            return;
+        }
+        assert!(
+            self.compare_root(&key) == Ordering::Less,
+            "the key inserted before the current one must be less than it"
+        );
+        if self.tree.splay_prev() && self.compare_root(&key) != Ordering::Greater {
+            self.tree.splay_next();
+            panic!("the key inserted before the current one must be greater than the previous key");
+        }
+        self.tree.insert(key, value);
+        self.tree.splay_next();
+    }
+
+    fn compare_root(&self, key: &K) -> Ordering {
+        self.tree.comparator().compare(key, &self.tree.root_ref().key)
     }
 }
 
 /// A view into a single entry in a map, which may either be vacant or occupied.
-pub enum Entry<'a, K: 'a, V: 'a> {
+pub enum Entry<'a, K: 'a, V: 'a, A: 'a = (), C: 'a = Natural, I: 'a = u32> {
     /// An occupied entry
-    Occupied(OccupiedEntry<'a, K, V>),
+    Occupied(OccupiedEntry<'a, K, V, A, C, I>),
     /// A vacant entry
-    Vacant(VacantEntry<'a, K, V>),
+    Vacant(VacantEntry<'a, K, V, A, C, I>),
 }
-impl<'a, K: 'a, V: 'a> Entry<'a, K, V>
+impl<'a, K: 'a, V: 'a, A: 'a, C: 'a, I: 'a> Entry<'a, K, V, A, C, I>
 where
-    K: Ord,
+    C: Compare<K>,
+    A: Augment<K, V>,
+    I: Index,
 {
     /// Returns a reference to this entry's key.
     pub fn key(&self) -> &K {
@@ -670,15 +2325,107 @@ where
             Entry::Vacant(e) => e.insert(default()),
         }
     }
+
+    /// Ensures a value is in the entry by inserting, if empty, the result of the default function
+    /// called with the entry's key, and returns a mutable reference to the value in the entry.
+    ///
+    /// # Examples
+    /// ```
+    /// use splay_tree::SplayMap;
+    ///
+    /// let mut map = SplayMap::new();
+    /// map.entry("poneyland").or_insert_with_key(|key| key.len());
+    /// assert_eq!(map.get("poneyland"), Some(&9));
+    /// ```
+    pub fn or_insert_with_key<F: FnOnce(&K) -> V>(self, default: F) -> &'a mut V {
+        match self {
+            Entry::Occupied(e) => e.into_mut(),
+            Entry::Vacant(e) => {
+                let value = default(&e.key);
+                e.insert(value)
+            }
+        }
+    }
+
+    /// Provides in-place mutable access to an occupied entry before any potential inserts into the map.
+    ///
+    /// # Examples
+    /// ```
+    /// use splay_tree::SplayMap;
+    ///
+    /// let mut map = SplayMap::new();
+    /// map.entry("poneyland").and_modify(|e| *e += 1).or_insert(42);
+    /// assert_eq!(map.get("poneyland"), Some(&42));
+    ///
+    /// map.entry("poneyland").and_modify(|e| *e += 1).or_insert(42);
+    /// assert_eq!(map.get("poneyland"), Some(&43));
+    /// ```
+    pub fn and_modify<F: FnOnce(&mut V)>(self, f: F) -> Self {
+        match self {
+            Entry::Occupied(mut e) => {
+                f(e.get_mut());
+                Entry::Occupied(e)
+            }
+            Entry::Vacant(e) => Entry::Vacant(e),
+        }
+    }
+
+    /// Sets the value of the entry, and returns an `OccupiedEntry`.
+    ///
+    /// # Examples
+    /// ```
+    /// use splay_tree::SplayMap;
+    ///
+    /// let mut map = SplayMap::new();
+    /// let entry = map.entry("poneyland").insert_entry("hoho");
+    /// assert_eq!(entry.key(), &"poneyland");
+    /// assert_eq!(map.get("poneyland"), Some(&"hoho"));
+    /// ```
+    pub fn insert_entry(self, value: V) -> OccupiedEntry<'a, K, V, A, C, I> {
+        match self {
+            Entry::Occupied(mut e) => {
+                e.insert(value);
+                e
+            }
+            Entry::Vacant(e) => e.insert_entry(value),
+        }
+    }
+}
+impl<'a, K: 'a, V: 'a, A: 'a, C: 'a, I: 'a> Entry<'a, K, V, A, C, I>
+where
+    C: Compare<K>,
+    A: Augment<K, V>,
+    V: Default,
+    I: Index,
+{
+    /// Ensures a value is in the entry by inserting the default value if empty,
+    /// and returns a mutable reference to the value in the entry.
+    ///
+    /// # Examples
+    /// ```
+    /// use splay_tree::SplayMap;
+    ///
+    /// let mut map: SplayMap<&str, Option<usize>> = SplayMap::new();
+    /// map.entry("poneyland").or_default();
+    /// assert_eq!(map.get("poneyland"), Some(&None));
+    /// ```
+    pub fn or_default(self) -> &'a mut V {
+        match self {
+            Entry::Occupied(e) => e.into_mut(),
+            Entry::Vacant(e) => e.insert(V::default()),
+        }
+    }
 }
 
 /// An occupied Entry.
-pub struct OccupiedEntry<'a, K: 'a, V: 'a> {
-    tree: &'a mut tree_core::Tree<K, V>,
+pub struct OccupiedEntry<'a, K: 'a, V: 'a, A: 'a = (), C: 'a = Natural, I: 'a = u32> {
+    tree: &'a mut tree_core::Tree<K, V, A, C, I>,
 }
-impl<'a, K: 'a, V: 'a> OccupiedEntry<'a, K, V>
+impl<'a, K: 'a, V: 'a, A: 'a, C: 'a, I: 'a> OccupiedEntry<'a, K, V, A, C, I>
 where
-    K: Ord,
+    C: Compare<K>,
+    A: Augment<K, V>,
+    I: Index,
 {
     /// Gets a reference to the key in the entry.
     pub fn key(&self) -> &K {
@@ -708,18 +2455,72 @@ where
 
     /// Takes the value of the entry out of the map, and returns it.
     pub fn remove(self) -> V {
-        self.tree.pop_root().unwrap().1
+        self.remove_entry().1
+    }
+
+    /// Takes the key-value pair of the entry out of the map, and returns it.
+    ///
+    /// # Examples
+    /// ```
+    /// use splay_tree::SplayMap;
+    /// use splay_tree::map::Entry;
+    ///
+    /// let mut map = SplayMap::new();
+    /// map.insert("poneyland", 12);
+    ///
+    /// if let Entry::Occupied(o) = map.entry("poneyland") {
+    ///     assert_eq!(o.remove_entry(), ("poneyland", 12));
+    /// }
+    /// assert!(map.is_empty());
+    /// ```
+    pub fn remove_entry(self) -> (K, V) {
+        self.tree.pop_root().unwrap()
+    }
+
+    /// Replaces the key of the entry with `key`, and returns the old key.
+    ///
+    /// This is useful when keys which compare equal are distinguishable
+    /// (e.g., they hold extra data which does not take part in the comparison).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `key` does not compare equal to the key of the entry.
+    ///
+    /// # Examples
+    /// ```
+    /// use std::rc::Rc;
+    /// use splay_tree::SplayMap;
+    /// use splay_tree::map::Entry;
+    ///
+    /// let mut map: SplayMap<Rc<String>, u32> = SplayMap::new();
+    /// let old = Rc::new("foo".to_string());
+    /// map.insert(old.clone(), 1);
+    ///
+    /// let new = Rc::new("foo".to_string());
+    /// if let Entry::Occupied(o) = map.entry(new.clone()) {
+    ///     assert!(Rc::ptr_eq(&o.replace_key(new.clone()), &old));
+    /// }
+    /// assert!(Rc::ptr_eq(map.keys().next().unwrap(), &new));
+    /// ```
+    pub fn replace_key(self, key: K) -> K {
+        assert!(
+            self.tree.comparator().compare(&key, self.key()) == Ordering::Equal,
+            "The key does not compare equal to the key of the entry"
+        );
+        mem::replace(&mut self.tree.root_mut().key, key)
     }
 }
 
 /// A vacant Entry.
-pub struct VacantEntry<'a, K: 'a, V: 'a> {
+pub struct VacantEntry<'a, K: 'a, V: 'a, A: 'a = (), C: 'a = Natural, I: 'a = u32> {
     key: K,
-    tree: &'a mut tree_core::Tree<K, V>,
+    tree: &'a mut tree_core::Tree<K, V, A, C, I>,
 }
-impl<'a, K: 'a, V: 'a> VacantEntry<'a, K, V>
+impl<'a, K: 'a, V: 'a, A: 'a, C: 'a, I: 'a> VacantEntry<'a, K, V, A, C, I>
 where
-    K: Ord,
+    C: Compare<K>,
+    A: Augment<K, V>,
+    I: Index,
 {
     /// Gets a reference to the key that would be used
     /// when inserting a value through the VacantEntry.
@@ -730,7 +2531,30 @@ where
     /// Sets the value of the entry with the VacantEntry's key,
     /// and returns a mutable reference to it.
     pub fn insert(self, value: V) -> &'a mut V {
+        self.insert_entry(value).into_mut()
+    }
+
+    /// Sets the value of the entry with the VacantEntry's key,
+    /// and returns an `OccupiedEntry`.
+    pub fn insert_entry(self, value: V) -> OccupiedEntry<'a, K, V, A, C, I> {
         self.tree.insert(self.key, value);
-        &mut self.tree.root_mut().val
+        OccupiedEntry { tree: self.tree }
+    }
+
+    /// Takes ownership of the key.
+    ///
+    /// # Examples
+    /// ```
+    /// use splay_tree::SplayMap;
+    /// use splay_tree::map::Entry;
+    ///
+    /// let mut map: SplayMap<&str, u32> = SplayMap::new();
+    /// if let Entry::Vacant(v) = map.entry("poneyland") {
+    ///     assert_eq!(v.into_key(), "poneyland");
+    /// }
+    /// assert!(map.is_empty());
+    /// ```
+    pub fn into_key(self) -> K {
+        self.key
     }
 }
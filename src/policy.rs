@@ -0,0 +1,53 @@
+//! Policies which decide how much the lookups restructure the trees.
+//!
+//! Splaying every node found by a lookup to the root is what gives splay trees
+//! their amortized bounds, but the rotations are not free.
+//! The lookups which leave the collections unchanged otherwise
+//! (e.g., `SplayMap::get`, `SplaySet::contains` and `SplayHeap::peek`)
+//! restructure the tree according to the policy set by `set_splay_policy`,
+//! so that the trade-off can be tuned for an access pattern.
+//!
+//! The other operations (insertions, removals, range queries, and so on)
+//! need the located node at the root, so they always splay it fully.
+//!
+//! # Examples
+//! ```
+//! use splay_tree::SplayMap;
+//! use splay_tree::policy::SplayPolicy;
+//!
+//! let mut map = (0..100).map(|i| (i, i * 2)).collect::<SplayMap<_, _>>();
+//! map.set_splay_policy(SplayPolicy::Threshold(8));
+//! assert_eq!(map.get(&42), Some(&84));
+//! assert_eq!(map.splay_policy(), SplayPolicy::Threshold(8));
+//! ```
+
+/// A policy which decides how a lookup restructures the tree.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum SplayPolicy {
+    /// Splays the node found by every lookup to the root with the top-down splaying.
+    ///
+    /// This is the default.
+    #[default]
+    Full,
+
+    /// Semi-splays the node found by every lookup.
+    ///
+    /// Each zig-zig step rotates only the parent over the grandparent,
+    /// and continues from the parent,
+    /// so the search path is roughly halved but the node moves only halfway up.
+    Semi,
+
+    /// Splays the node found by a lookup to the root only if its depth
+    /// (the number of links from the root) exceeds the threshold.
+    ///
+    /// The tree is left unchanged otherwise.
+    Threshold(usize),
+
+    /// Splays the node found by a lookup to the root with the given probability
+    /// (from `0.0` to `1.0`).
+    ///
+    /// The tree is left unchanged otherwise.
+    /// The decisions are drawn from a pseudo-random generator with a fixed seed,
+    /// so the same sequence of operations always restructures the tree in the same way.
+    Probability(f64),
+}
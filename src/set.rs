@@ -4,9 +4,16 @@ use std::ops;
 use std::cmp;
 use std::iter::Peekable;
 use std::borrow::Borrow;
+use std::ops::RangeBounds;
 use tree_core;
 use iter;
 use vec_like;
+use augment::{Augment, Counted};
+use compare::{Compare, Natural};
+use index::Index;
+use tree_core::Merged;
+use error::{InvariantError, TryReserveError};
+use policy::SplayPolicy;
 
 
 /// A set based on splay tree.
@@ -16,7 +23,7 @@ use vec_like;
 ///
 /// It is a logic error for a key to be modified in such a way that
 /// the key's ordering relative to any other key,
-/// as determined by the `Ord` trait, changes while it is in the map.
+/// as determined by the comparator (the `Ord` trait by default), changes while it is in the map.
 /// This is normally only possible through `Cell`, `RefCell`, global state, I/O, or unsafe code.
 ///
 /// # Examples
@@ -36,10 +43,9 @@ use vec_like;
 ///
 /// assert_eq!(vec!["baz", "foo"], set.into_iter().collect::<Vec<_>>());
 /// ```
-#[derive(Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-pub struct SplaySet<T> {
-    tree: tree_core::Tree<T, ()>,
+#[derive(Debug, Clone)]
+pub struct SplaySet<T, A = (), C = Natural, I = u32> {
+    tree: tree_core::Tree<T, (), A, C, I>,
 }
 impl<T> SplaySet<T>
 where
@@ -60,6 +66,51 @@ where
         }
     }
 
+    /// Makes a new empty `SplaySet` which can hold at least `capacity` values
+    /// without reallocating.
+    ///
+    /// # Examples
+    /// ```
+    /// use splay_tree::SplaySet;
+    ///
+    /// let mut set = SplaySet::with_capacity(10);
+    /// set.insert("foo");
+    /// assert!(set.capacity() >= 10);
+    /// ```
+    pub fn with_capacity(capacity: usize) -> Self {
+        let mut set = SplaySet::new();
+        set.reserve(capacity);
+        set
+    }
+}
+impl<T, C> SplaySet<T, (), C>
+where
+    C: Compare<T>,
+{
+    /// Makes a new empty `SplaySet` which orders its values by the given comparator.
+    ///
+    /// # Examples
+    /// ```
+    /// use splay_tree::SplaySet;
+    ///
+    /// let mut set = SplaySet::with_comparator(|a: &i32, b: &i32| b.cmp(a));
+    /// set.insert(1);
+    /// set.insert(3);
+    /// set.insert(2);
+    /// assert_eq!(set.into_iter().collect::<Vec<_>>(), [3, 2, 1]);
+    /// ```
+    pub fn with_comparator(cmp: C) -> Self {
+        SplaySet {
+            tree: tree_core::Tree::with_comparator(cmp),
+        }
+    }
+}
+impl<T, A, C, I> SplaySet<T, A, C, I>
+where
+    C: Compare<T>,
+    A: Augment<T, ()>,
+    I: Index,
+{
     /// Clears the set, removing all values.
     ///
     /// # Examples
@@ -72,7 +123,7 @@ where
     /// assert!(set.is_empty());
     /// ```
     pub fn clear(&mut self) {
-        self.tree = tree_core::Tree::new();
+        self.tree.clear();
     }
 
     /// Returns true if the set contains a value.
@@ -95,9 +146,9 @@ where
     pub fn contains<Q: ?Sized>(&mut self, value: &Q) -> bool
     where
         T: Borrow<Q>,
-        Q: Ord,
+        C: Compare<Q>,
     {
-        self.tree.contains_key(value)
+        self.tree.lookup(value).is_some()
     }
 
     /// Returns a reference to the value in the set, if any, that is equal to the given value.
@@ -120,18 +171,15 @@ where
     pub fn get<Q: ?Sized>(&mut self, value: &Q) -> Option<&T>
     where
         T: Borrow<Q>,
-        Q: Ord,
+        C: Compare<Q>,
     {
-        if self.tree.get(value).is_some() {
-            Some(&self.tree.root_ref().key)
-        } else {
-            None
-        }
+        let found = self.tree.lookup(value);
+        found.map(move |i| &self.tree.node_ref(i).key)
     }
 
-    fn node_mut(&mut self, index: usize) -> Option<&mut tree_core::Node<T, ()>> {
+    fn node_mut(&mut self, index: usize) -> Option<&mut tree_core::Node<T, (), A, I>> {
         if index < self.tree.len() {
-                Some(self.tree.node_mut(index as tree_core::NodeIndex))
+                Some(self.tree.node_mut(I::from_usize(index)))
         } else {
             None
         }
@@ -140,9 +188,9 @@ where
     pub fn find_less<Q: ?Sized>(&mut self, value: &Q) -> Option<&T>
     where
         T: Borrow<Q>,
-        Q: Ord,
+        C: Compare<Q>,
     {
-        self.tree.find_less(value)
+        self.tree.find_less(value).map(|n| &n.key)
     }
 
     /// Finds a minimum element which
@@ -166,9 +214,9 @@ where
     pub fn find_lower_bound<Q: ?Sized>(&mut self, value: &Q) -> Option<&T>
     where
         T: Borrow<Q>,
-        Q: Ord,
+        C: Compare<Q>,
     {
-        self.tree.find_lower_bound(value)
+        self.tree.find_lower_bound(value).map(|n| &n.key)
     }
 
     /// Finds a minimum element which satisfies "greater than `value`" condition in the set.
@@ -191,9 +239,40 @@ where
     pub fn find_upper_bound<Q: ?Sized>(&mut self, value: &Q) -> Option<&T>
     where
         T: Borrow<Q>,
-        Q: Ord,
+        C: Compare<Q>,
     {
-        self.tree.find_upper_bound(value)
+        self.tree.find_upper_bound(value).map(|n| &n.key)
+    }
+
+    /// Gets an iterator over a sub-range of elements in the set, in ascending order.
+    ///
+    /// The first element of the range is located in `O(log n)` amortized time,
+    /// and then only the elements in the range are visited.
+    ///
+    /// # Panics
+    ///
+    /// Panics if range `start > end`.
+    /// Panics if range `start == end` and both bounds are `Excluded`.
+    ///
+    /// # Examples
+    /// ```
+    /// use splay_tree::SplaySet;
+    /// use std::ops::Bound::{Excluded, Included};
+    ///
+    /// let mut set: SplaySet<_> = vec![1, 3, 5, 7, 9].into_iter().collect();
+    ///
+    /// assert_eq!(set.range(2..7).cloned().collect::<Vec<_>>(), [3, 5]);
+    /// assert_eq!(set.range((Excluded(3), Included(9))).rev().cloned().collect::<Vec<_>>(),
+    ///            [9, 7, 5]);
+    /// ```
+    pub fn range<Q: ?Sized, R>(&mut self, range: R) -> Range<'_, T, A, I>
+    where
+        T: Borrow<Q>,
+        C: Compare<Q>,
+        R: RangeBounds<Q>,
+    {
+        let bounds = self.tree.range(range);
+        Range(self.tree.iter_range(bounds))
     }
 
     /// Gets the minimum value in the map.
@@ -284,6 +363,56 @@ where
         self.tree.insert(value, ()).is_none()
     }
 
+    /// Adds a value to the set in the same way as `insert`,
+    /// but returns an error instead of panicking or aborting
+    /// if the set cannot hold one more value.
+    ///
+    /// The set is left unchanged in that case.
+    ///
+    /// # Examples
+    /// ```
+    /// use splay_tree::SplaySet;
+    /// use splay_tree::compare::Natural;
+    /// use splay_tree::error::TryReserveError;
+    ///
+    /// let mut set: SplaySet<u16, (), Natural, u16> = SplaySet::from_sorted_iter(0..65535);
+    /// assert_eq!(set.try_insert(0), Ok(false));
+    /// assert_eq!(set.try_insert(65535), Err(TryReserveError::CapacityOverflow));
+    /// assert_eq!(set.len(), 65535);
+    /// ```
+    pub fn try_insert(&mut self, value: T) -> Result<bool, TryReserveError> {
+        self.tree.try_insert(value, ()).map(|old| old.is_none())
+    }
+
+    /// Makes a new set from an iterator of values sorted in strictly ascending order.
+    ///
+    /// This takes `O(n)` time, and the values are linked as a perfectly balanced tree.
+    /// (`FromIterator` and `Extend` also detect ascending runs of values,
+    /// but they compare each value with the previous one.)
+    ///
+    /// # Panics
+    ///
+    /// In debug builds, panics if the values are not strictly ascending.
+    /// In release builds, such input leaves the set in an unspecified (but memory-safe) state.
+    ///
+    /// # Examples
+    /// ```
+    /// use splay_tree::SplaySet;
+    ///
+    /// let mut set: SplaySet<_> = SplaySet::from_sorted_iter(0..100);
+    /// assert_eq!(set.len(), 100);
+    /// assert!(set.contains(&50));
+    /// ```
+    pub fn from_sorted_iter<It>(iter: It) -> Self
+    where
+        It: IntoIterator<Item = T>,
+        C: Default,
+    {
+        let mut set = SplaySet::default();
+        set.tree.rebuild_from_sorted(iter.into_iter().map(|x| (x, ())));
+        set
+    }
+
     /// Adds a value to the set, replacing the existing value, if any,
     /// that is equal to the given one.
     /// Returns the replaced value.
@@ -319,11 +448,56 @@ where
     pub fn remove<Q: ?Sized>(&mut self, value: &Q) -> bool
     where
         T: Borrow<Q>,
-        Q: Ord,
+        C: Compare<Q>,
     {
         self.tree.remove(value).is_some()
     }
 
+    /// Splits the set into two at the given value.
+    /// Returns everything after the given value, including the value.
+    ///
+    /// # Examples
+    /// ```
+    /// use splay_tree::SplaySet;
+    ///
+    /// let mut a: SplaySet<_> = vec![1, 2, 3, 17, 41].into_iter().collect();
+    /// let b = a.split_off(&3);
+    ///
+    /// assert_eq!(a.into_iter().collect::<Vec<_>>(), [1, 2]);
+    /// assert_eq!(b.into_iter().collect::<Vec<_>>(), [3, 17, 41]);
+    /// ```
+    pub fn split_off<Q: ?Sized>(&mut self, value: &Q) -> Self
+    where
+        T: Borrow<Q>,
+        C: Compare<Q> + Clone,
+    {
+        SplaySet {
+            tree: self.tree.split_off(value),
+        }
+    }
+
+    /// Moves all elements from `other` into `self`, leaving `other` empty.
+    ///
+    /// # Examples
+    /// ```
+    /// use splay_tree::SplaySet;
+    ///
+    /// let mut a: SplaySet<_> = vec![1, 2, 3].into_iter().collect();
+    /// let mut b: SplaySet<_> = vec![3, 4, 5].into_iter().collect();
+    ///
+    /// a.append(&mut b);
+    ///
+    /// assert_eq!(a.len(), 5);
+    /// assert_eq!(b.len(), 0);
+    /// assert_eq!(a.into_iter().collect::<Vec<_>>(), [1, 2, 3, 4, 5]);
+    /// ```
+    pub fn append(&mut self, other: &mut Self)
+    where
+        C: Clone,
+    {
+        self.tree.append(&mut other.tree);
+    }
+
     /// Removes and returns the value in the set, if any, that is equal to the given one.
     ///
     /// The value may be any borrowed form of the set's value type,
@@ -341,15 +515,82 @@ where
     pub fn take<Q: ?Sized>(&mut self, value: &Q) -> Option<T>
     where
         T: Borrow<Q>,
-        Q: Ord,
+        C: Compare<Q>,
     {
-        if self.contains(value) {
+        if self.tree.contains_key(value) {
             self.tree.pop_root().map(|(e, _)| e)
         } else {
             None
         }
     }
 
+    /// Retains only the elements specified by the predicate.
+    ///
+    /// In other words, removes all elements `e` such that `f(&e)` returns `false`.
+    /// The elements are visited in ascending order, in a single pass over the tree,
+    /// and then the tree is rebuilt into a balanced shape.
+    ///
+    /// # Examples
+    /// ```
+    /// use splay_tree::SplaySet;
+    ///
+    /// let mut set: SplaySet<_> = (0..8).collect();
+    /// set.retain(|&x| x % 2 == 0);
+    /// assert_eq!(set.into_iter().collect::<Vec<_>>(), [0, 2, 4, 6]);
+    /// ```
+    pub fn retain<F>(&mut self, mut f: F)
+    where
+        F: FnMut(&T) -> bool,
+    {
+        self.extract_if(|x| !f(x)).for_each(drop);
+    }
+
+    /// Creates an iterator that visits all elements in ascending order,
+    /// and uses a closure to determine if an element should be removed.
+    ///
+    /// If the closure returns `true`, the element is removed from the set and yielded.
+    /// If the closure returns `false`, the element remains in the set and will not be yielded.
+    ///
+    /// If the iterator is dropped before being fully consumed,
+    /// it retains the remaining elements.
+    /// If it is leaked (e.g., by `mem::forget`), the set may lose the elements
+    /// which are not visited yet.
+    ///
+    /// # Examples
+    /// ```
+    /// use splay_tree::SplaySet;
+    ///
+    /// let mut set: SplaySet<_> = (0..8).collect();
+    /// let evens = set.extract_if(|x| x % 2 == 0).collect::<Vec<_>>();
+    /// assert_eq!(evens, [0, 2, 4, 6]);
+    /// assert_eq!(set.into_iter().collect::<Vec<_>>(), [1, 3, 5, 7]);
+    /// ```
+    pub fn extract_if<F>(&mut self, pred: F) -> ExtractIf<'_, T, A, C, I, F>
+    where
+        F: FnMut(&T) -> bool,
+    {
+        ExtractIf {
+            inner: self.tree.extract(),
+            pred,
+        }
+    }
+
+    /// Clears the set, returning all elements as an iterator in ascending order.
+    ///
+    /// The set is emptied even if the iterator is not fully consumed.
+    ///
+    /// # Examples
+    /// ```
+    /// use splay_tree::SplaySet;
+    ///
+    /// let mut set: SplaySet<_> = vec![2, 1, 3].into_iter().collect();
+    /// assert_eq!(set.drain().collect::<Vec<_>>(), [1, 2, 3]);
+    /// assert!(set.is_empty());
+    /// ```
+    pub fn drain(&mut self) -> Drain<T, A, I> {
+        Drain(self.tree.drain())
+    }
+
     /// Visits the values representing the difference, in ascending order.
     ///
     /// # Examples
@@ -362,8 +603,12 @@ where
     /// assert_eq!(a.difference(&b).cloned().collect::<Vec<_>>(),
     ///            [1]);
     /// ```
-    pub fn difference<'a>(&'a self, other: &'a Self) -> Difference<'a, T> {
-        Difference(self.iter().peekable(), other.iter().peekable())
+    pub fn difference<'a>(&'a self, other: &'a Self) -> Difference<'a, T, A, C, I> {
+        Difference(
+            self.iter().peekable(),
+            other.iter().peekable(),
+            self.tree.comparator(),
+        )
     }
 
     /// Visits the values representing the symmetric difference, in ascending order.
@@ -378,8 +623,12 @@ where
     /// assert_eq!(a.symmetric_difference(&b).cloned().collect::<Vec<_>>(),
     ///            [1, 4]);
     /// ```
-    pub fn symmetric_difference<'a>(&'a self, other: &'a Self) -> SymmetricDifference<'a, T> {
-        SymmetricDifference(self.iter().peekable(), other.iter().peekable())
+    pub fn symmetric_difference<'a>(&'a self, other: &'a Self) -> SymmetricDifference<'a, T, A, C, I> {
+        SymmetricDifference(
+            self.iter().peekable(),
+            other.iter().peekable(),
+            self.tree.comparator(),
+        )
     }
 
     /// Visits the values representing the intersection, in ascending order.
@@ -394,8 +643,12 @@ where
     /// assert_eq!(a.intersection(&b).cloned().collect::<Vec<_>>(),
     ///            [2, 3]);
     /// ```
-    pub fn intersection<'a>(&'a self, other: &'a Self) -> Intersection<'a, T> {
-        Intersection(self.iter().peekable(), other.iter().peekable())
+    pub fn intersection<'a>(&'a self, other: &'a Self) -> Intersection<'a, T, A, C, I> {
+        Intersection(
+            self.iter().peekable(),
+            other.iter().peekable(),
+            self.tree.comparator(),
+        )
     }
 
     /// Visits the values representing the union, in ascending order.
@@ -410,8 +663,91 @@ where
     /// assert_eq!(a.union(&b).cloned().collect::<Vec<_>>(),
     ///            [1, 2, 3, 4]);
     /// ```
-    pub fn union<'a>(&'a self, other: &'a Self) -> Union<'a, T> {
-        Union(self.iter().peekable(), other.iter().peekable())
+    pub fn union<'a>(&'a self, other: &'a Self) -> Union<'a, T, A, C, I> {
+        Union(
+            self.iter().peekable(),
+            other.iter().peekable(),
+            self.tree.comparator(),
+        )
+    }
+
+    /// Moves all values of `other` into the set, in linear time.
+    ///
+    /// If a value is contained in both sets, the one of `self` is kept.
+    /// The resulting tree is perfectly balanced.
+    ///
+    /// # Examples
+    /// ```
+    /// use splay_tree::SplaySet;
+    ///
+    /// let mut a: SplaySet<_> = vec![1, 2, 3].into_iter().collect();
+    /// let b: SplaySet<_> = vec![2, 3, 4].into_iter().collect();
+    ///
+    /// a.union_with(b);
+    /// assert_eq!(a.into_iter().collect::<Vec<_>>(), [1, 2, 3, 4]);
+    /// ```
+    pub fn union_with(&mut self, other: Self) {
+        self.tree.merge_with(other.tree, |merged| match merged {
+            Merged::Lft(x) | Merged::Rgt(x) | Merged::Both(x, _) => Some(x),
+        });
+    }
+
+    /// Retains only the values which are also contained in `other`, in linear time.
+    ///
+    /// The resulting tree is perfectly balanced.
+    ///
+    /// # Examples
+    /// ```
+    /// use splay_tree::SplaySet;
+    ///
+    /// let mut a: SplaySet<_> = vec![1, 2, 3].into_iter().collect();
+    /// let b: SplaySet<_> = vec![2, 3, 4].into_iter().collect();
+    ///
+    /// a.intersect_with(&b);
+    /// assert_eq!(a.into_iter().collect::<Vec<_>>(), [2, 3]);
+    /// ```
+    pub fn intersect_with(&mut self, other: &Self) {
+        self.tree.retain_by_keys(other.iter(), true);
+    }
+
+    /// Removes all values which are contained in `other`, in linear time.
+    ///
+    /// The resulting tree is perfectly balanced.
+    ///
+    /// # Examples
+    /// ```
+    /// use splay_tree::SplaySet;
+    ///
+    /// let mut a: SplaySet<_> = vec![1, 2, 3].into_iter().collect();
+    /// let b: SplaySet<_> = vec![2, 3, 4].into_iter().collect();
+    ///
+    /// a.difference_with(&b);
+    /// assert_eq!(a.into_iter().collect::<Vec<_>>(), [1]);
+    /// ```
+    pub fn difference_with(&mut self, other: &Self) {
+        self.tree.retain_by_keys(other.iter(), false);
+    }
+
+    /// Moves the values of `other` which are not contained in the set into it,
+    /// and removes the values which are contained in both, in linear time.
+    ///
+    /// The resulting tree is perfectly balanced.
+    ///
+    /// # Examples
+    /// ```
+    /// use splay_tree::SplaySet;
+    ///
+    /// let mut a: SplaySet<_> = vec![1, 2, 3].into_iter().collect();
+    /// let b: SplaySet<_> = vec![2, 3, 4].into_iter().collect();
+    ///
+    /// a.symmetric_difference_with(b);
+    /// assert_eq!(a.into_iter().collect::<Vec<_>>(), [1, 4]);
+    /// ```
+    pub fn symmetric_difference_with(&mut self, other: Self) {
+        self.tree.merge_with(other.tree, |merged| match merged {
+            Merged::Lft(x) | Merged::Rgt(x) => Some(x),
+            Merged::Both(..) => None,
+        });
     }
 
     /// Returns `true` if the set has no elements in common with `other`.
@@ -500,43 +836,373 @@ where
     /// assert_eq!(set.iter().cloned().collect::<Vec<_>>(),
     ///            ["bar", "baz", "foo"]);
     /// ```
-    pub fn as_vec_like_mut(&mut self) -> VecLikeMut<T> {
+    pub fn as_vec_like_mut(&mut self) -> VecLikeMut<T, A, C, I> {
         VecLikeMut::new(&mut self.tree)
     }
+
+    /// Checks the internal structure of the set, and returns an error describing the corruption if any.
+    ///
+    /// This verifies that the values are in strictly ascending order, and that every node in the arena
+    /// is reachable from the root exactly once (i.e., the links have no cycles).
+    /// It takes linear time, and is intended for debugging.
+    ///
+    /// If the `check-invariants` feature is enabled, this is run after every mutating operation
+    /// in debug builds, which panic on a corruption.
+    ///
+    /// # Examples
+    /// ```
+    /// use splay_tree::SplaySet;
+    ///
+    /// let mut set: SplaySet<_> = (0..100).collect();
+    /// set.remove(&50);
+    /// set.intersect_with(&(0..100).filter(|x| x % 3 == 0).collect());
+    /// assert_eq!(set.check_invariants(), Ok(()));
+    /// ```
+    pub fn check_invariants(&self) -> Result<(), InvariantError> {
+        self.tree.check_invariants()
+    }
+}
+impl<T, A, C, I> SplaySet<T, A, C, I>
+where
+    C: Compare<T>,
+    A: Counted<T, ()>,
+    I: Index,
+{
+    /// Returns the number of elements in the set which are less than `value`,
+    /// or `None` if the set does not contain `value`.
+    ///
+    /// # Examples
+    /// ```
+    /// use splay_tree::SplaySet;
+    /// use splay_tree::augment::Count;
+    ///
+    /// let mut set: SplaySet<_, Count> = SplaySet::default();
+    /// set.insert("foo");
+    /// set.insert("bar");
+    ///
+    /// assert_eq!(set.rank("bar"), Some(0));
+    /// assert_eq!(set.rank("foo"), Some(1));
+    /// assert_eq!(set.rank("baz"), None);
+    /// ```
+    pub fn rank<Q: ?Sized>(&mut self, value: &Q) -> Option<usize>
+    where
+        T: Borrow<Q>,
+        C: Compare<Q>,
+    {
+        self.tree.rank(value)
+    }
+
+    /// Gets the `index`-th smallest element in the set (zero-based).
+    ///
+    /// # Examples
+    /// ```
+    /// use splay_tree::SplaySet;
+    /// use splay_tree::augment::Count;
+    ///
+    /// let mut set: SplaySet<_, Count> = SplaySet::default();
+    /// set.insert("foo");
+    /// set.insert("bar");
+    ///
+    /// assert_eq!(set.select(0), Some(&"bar"));
+    /// assert_eq!(set.select(1), Some(&"foo"));
+    /// assert_eq!(set.select(2), None);
+    /// ```
+    pub fn select(&mut self, index: usize) -> Option<&T> {
+        match self.tree.select(index) {
+            Some(i) => Some(&self.tree.node_ref(i).key),
+            None => None,
+        }
+    }
+
+    /// Returns the number of elements contained in `range`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if range `start > end`.
+    /// Panics if range `start == end` and both bounds are `Excluded`.
+    ///
+    /// # Examples
+    /// ```
+    /// use splay_tree::SplaySet;
+    /// use splay_tree::augment::Count;
+    ///
+    /// let mut set: SplaySet<_, Count> = (0..10).map(|i| i * 10).collect();
+    /// assert_eq!(set.count_range(15..45), 3);
+    /// assert_eq!(set.count_range(..=50), 6);
+    /// assert_eq!(set.count_range(..), 10);
+    /// ```
+    pub fn count_range<Q: ?Sized, R>(&mut self, range: R) -> usize
+    where
+        T: Borrow<Q>,
+        C: Compare<Q>,
+        R: RangeBounds<Q>,
+    {
+        self.tree.count_range(range)
+    }
+}
+impl<T, A, C, I> SplaySet<T, A, C, I>
+where
+    C: Compare<T>,
+    I: Index,
+{
+    /// Returns true if the set contains a value, without splaying the tree.
+    ///
+    /// Unlike `contains`, this function only requires `&self`,
+    /// but the cost is proportional to the current depth of the value
+    /// rather than `O(log n)` amortized.
+    ///
+    /// # Examples
+    /// ```
+    /// use splay_tree::SplaySet;
+    ///
+    /// let mut set = SplaySet::new();
+    /// set.insert("foo");
+    ///
+    /// let set = &set;
+    /// assert!(set.peek_contains("foo"));
+    /// assert!(!set.peek_contains("bar"));
+    /// ```
+    pub fn peek_contains<Q: ?Sized>(&self, value: &Q) -> bool
+    where
+        T: Borrow<Q>,
+        C: Compare<Q>,
+    {
+        self.tree.find_ref(value).is_some()
+    }
+
+    /// Returns a reference to the value in the set, if any, that is equal to the given value,
+    /// without splaying the tree.
+    ///
+    /// # Examples
+    /// ```
+    /// use splay_tree::SplaySet;
+    ///
+    /// let mut set = SplaySet::new();
+    /// set.insert("foo");
+    ///
+    /// let set = &set;
+    /// assert_eq!(set.peek_get("foo"), Some(&"foo"));
+    /// assert_eq!(set.peek_get("bar"), None);
+    /// ```
+    pub fn peek_get<Q: ?Sized>(&self, value: &Q) -> Option<&T>
+    where
+        T: Borrow<Q>,
+        C: Compare<Q>,
+    {
+        self.tree.find_ref(value).map(|node| &node.key)
+    }
+
+    /// Finds a minimum element which
+    /// satisfies "greater than or equal to `value`" condition in the set,
+    /// without splaying the tree.
+    ///
+    /// # Examples
+    /// ```
+    /// use splay_tree::SplaySet;
+    ///
+    /// let set: SplaySet<_> = vec![1, 3].into_iter().collect();
+    ///
+    /// assert_eq!(set.peek_lower_bound(&0), Some(&1));
+    /// assert_eq!(set.peek_lower_bound(&1), Some(&1));
+    /// assert_eq!(set.peek_lower_bound(&4), None);
+    /// ```
+    pub fn peek_lower_bound<Q: ?Sized>(&self, value: &Q) -> Option<&T>
+    where
+        T: Borrow<Q>,
+        C: Compare<Q>,
+    {
+        self.tree.lower_bound_ref(value).map(|node| &node.key)
+    }
+
+    /// Finds a minimum element which satisfies "greater than `value`" condition in the set,
+    /// without splaying the tree.
+    ///
+    /// # Examples
+    /// ```
+    /// use splay_tree::SplaySet;
+    ///
+    /// let set: SplaySet<_> = vec![1, 3].into_iter().collect();
+    ///
+    /// assert_eq!(set.peek_upper_bound(&0), Some(&1));
+    /// assert_eq!(set.peek_upper_bound(&1), Some(&3));
+    /// assert_eq!(set.peek_upper_bound(&4), None);
+    /// ```
+    pub fn peek_upper_bound<Q: ?Sized>(&self, value: &Q) -> Option<&T>
+    where
+        T: Borrow<Q>,
+        C: Compare<Q>,
+    {
+        self.tree.upper_bound_ref(value).map(|node| &node.key)
+    }
 }
-impl<T> SplaySet<T> {
+impl<T, A, C, I> SplaySet<T, A, C, I>
+where
+    I: Index,
+{
     /// Returns the number of elements in the set.
     ///
     /// # Examples
     /// ```
     /// use splay_tree::SplaySet;
     ///
-    /// let mut set = SplaySet::new();
-    /// set.insert("foo");
-    /// set.insert("bar");
-    /// assert_eq!(set.len(), 2);
+    /// let mut set = SplaySet::new();
+    /// set.insert("foo");
+    /// set.insert("bar");
+    /// assert_eq!(set.len(), 2);
+    /// ```
+    pub fn len(&self) -> usize {
+        self.tree.len()
+    }
+
+    /// Returns true if the set contains no elements.
+    ///
+    /// # Examples
+    /// ```
+    /// use splay_tree::SplaySet;
+    ///
+    /// let mut set = SplaySet::new();
+    /// assert!(set.is_empty());
+    ///
+    /// set.insert("foo");
+    /// assert!(!set.is_empty());
+    ///
+    /// set.clear();
+    /// assert!(set.is_empty());
+    /// ```
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the number of values the set can hold without reallocating.
+    ///
+    /// # Examples
+    /// ```
+    /// use splay_tree::SplaySet;
+    ///
+    /// let set: SplaySet<u8> = SplaySet::with_capacity(10);
+    /// assert!(set.capacity() >= 10);
+    /// ```
+    pub fn capacity(&self) -> usize {
+        self.tree.capacity()
+    }
+
+    /// Reserves capacity for at least `additional` more values to be inserted in the set.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the new capacity exceeds the limit of the index type
+    /// or overflows `usize`.
+    ///
+    /// # Examples
+    /// ```
+    /// use splay_tree::SplaySet;
+    ///
+    /// let mut set = SplaySet::new();
+    /// set.insert("foo");
+    /// set.reserve(10);
+    /// assert!(set.capacity() >= 11);
+    /// ```
+    pub fn reserve(&mut self, additional: usize) {
+        self.tree.reserve(additional);
+    }
+
+    /// Tries to reserve capacity for at least `additional` more values to be inserted in the set.
+    ///
+    /// Unlike `reserve`, this returns an error instead of panicking or aborting
+    /// if the capacity exceeds the limit of the index type or the allocator fails.
+    ///
+    /// # Examples
+    /// ```
+    /// use splay_tree::SplaySet;
+    /// use splay_tree::compare::Natural;
+    /// use splay_tree::error::TryReserveError;
+    ///
+    /// let mut set: SplaySet<u8, (), Natural, u16> = SplaySet::default();
+    /// assert_eq!(set.try_reserve(10), Ok(()));
+    /// assert!(set.capacity() >= 10);
+    /// assert_eq!(set.try_reserve(65536), Err(TryReserveError::CapacityOverflow));
+    /// ```
+    pub fn try_reserve(&mut self, additional: usize) -> Result<(), TryReserveError> {
+        self.tree.try_reserve(additional)
+    }
+
+    /// Shrinks the capacity of the set as much as possible.
+    ///
+    /// # Examples
+    /// ```
+    /// use splay_tree::SplaySet;
+    ///
+    /// let mut set = SplaySet::with_capacity(100);
+    /// set.insert("foo");
+    /// set.shrink_to_fit();
+    /// assert!(set.capacity() < 100);
+    /// ```
+    pub fn shrink_to_fit(&mut self) {
+        self.tree.shrink_to_fit();
+    }
+
+    /// Returns the policy which decides how the lookups restructure the set.
+    ///
+    /// # Examples
+    /// ```
+    /// use splay_tree::SplaySet;
+    /// use splay_tree::policy::SplayPolicy;
+    ///
+    /// let set: SplaySet<u8> = SplaySet::new();
+    /// assert_eq!(set.splay_policy(), SplayPolicy::Full);
+    /// ```
+    pub fn splay_policy(&self) -> SplayPolicy {
+        self.tree.policy()
+    }
+
+    /// Sets the policy which decides how the lookups restructure the set.
+    ///
+    /// The policy applies to `contains` and `get`.
+    /// See [`policy`](../policy/index.html) for details.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the probability of `SplayPolicy::Probability` is not in the range from 0 to 1.
+    ///
+    /// # Examples
+    /// ```
+    /// use splay_tree::SplaySet;
+    /// use splay_tree::policy::SplayPolicy;
+    ///
+    /// let mut set = (0..10).collect::<SplaySet<_>>();
+    /// set.set_splay_policy(SplayPolicy::Probability(0.25));
+    /// assert!(set.contains(&3));
+    /// assert_eq!(set.get(&9), Some(&9));
+    /// assert_eq!(set.get(&10), None);
+    /// ```
+    pub fn set_splay_policy(&mut self, policy: SplayPolicy) {
+        self.tree.set_policy(policy);
+    }
+
+    /// Returns the minimum element in the set, without splaying the tree.
+    ///
+    /// # Examples
+    /// ```
+    /// use splay_tree::SplaySet;
+    ///
+    /// let set: SplaySet<_> = vec![3, 1, 2].into_iter().collect();
+    /// assert_eq!(set.first(), Some(&1));
     /// ```
-    pub fn len(&self) -> usize {
-        self.tree.len()
+    pub fn first(&self) -> Option<&T> {
+        self.tree.lftmost_ref().map(|node| &node.key)
     }
 
-    /// Returns true if the set contains no elements.
+    /// Returns the maximum element in the set, without splaying the tree.
     ///
     /// # Examples
     /// ```
     /// use splay_tree::SplaySet;
     ///
-    /// let mut set = SplaySet::new();
-    /// assert!(set.is_empty());
-    ///
-    /// set.insert("foo");
-    /// assert!(!set.is_empty());
-    ///
-    /// set.clear();
-    /// assert!(set.is_empty());
+    /// let set: SplaySet<_> = vec![3, 1, 2].into_iter().collect();
+    /// assert_eq!(set.last(), Some(&3));
     /// ```
-    pub fn is_empty(&self) -> bool {
-        self.len() == 0
+    pub fn last(&self) -> Option<&T> {
+        self.tree.rgtmost_ref().map(|node| &node.key)
     }
 
     /// Gets an iterator over the SplaySet's contents, in sorted order.
@@ -552,7 +1218,7 @@ impl<T> SplaySet<T> {
     ///
     /// assert_eq!(set.iter().collect::<Vec<_>>(), [&"bar", &"baz", &"foo"]);
     /// ```
-    pub fn iter(&self) -> Iter<T> {
+    pub fn iter(&self) -> Iter<T, A, I> {
         Iter::new(self)
     }
 
@@ -580,7 +1246,7 @@ impl<T> SplaySet<T> {
     /// assert_eq!(set.iter().cloned().collect::<Vec<_>>(),
     ///            ["bar", "foo"]);
     /// ```
-    pub fn as_vec_like(&self) -> VecLike<T> {
+    pub fn as_vec_like(&self) -> VecLike<T, A, C, I> {
         VecLike::new(&self.tree)
     }
 }
@@ -626,74 +1292,199 @@ impl<T> SplaySet<T> {
 
 
 
-impl<T> Default for SplaySet<T>
+impl<T, A, C, I> std::hash::Hash for SplaySet<T, A, C, I>
+where
+    T: std::hash::Hash,
+    I: Index,
+{
+    fn hash<H>(&self, state: &mut H)
+    where
+        H: std::hash::Hasher,
+    {
+        self.tree.hash(state);
+    }
+}
+impl<T, A, C, I> PartialEq for SplaySet<T, A, C, I>
+where
+    T: PartialEq,
+    I: Index,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.tree == other.tree
+    }
+}
+impl<T, A, C, I> Eq for SplaySet<T, A, C, I>
+where
+    T: Eq,
+    I: Index,
+{
+}
+impl<T, A, C, I> PartialOrd for SplaySet<T, A, C, I>
+where
+    T: PartialOrd,
+    I: Index,
+{
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        self.tree.partial_cmp(&other.tree)
+    }
+}
+impl<T, A, C, I> Ord for SplaySet<T, A, C, I>
 where
     T: Ord,
+    I: Index,
+{
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.tree.cmp(&other.tree)
+    }
+}
+impl<T, A, C, I> Default for SplaySet<T, A, C, I>
+where
+    C: Compare<T> + Default,
+    A: Augment<T, ()>,
+    I: Index,
 {
     fn default() -> Self {
-        SplaySet::new()
+        SplaySet {
+            tree: tree_core::Tree::new(),
+        }
     }
 }
-impl<T> std::iter::FromIterator<T> for SplaySet<T>
+impl<T, A, C, I> std::iter::FromIterator<T> for SplaySet<T, A, C, I>
 where
-    T: Ord,
+    C: Compare<T> + Default,
+    A: Augment<T, ()>,
+    I: Index,
 {
-    fn from_iter<I>(iter: I) -> Self
+    fn from_iter<It>(iter: It) -> Self
     where
-        I: IntoIterator<Item = T>,
+        It: IntoIterator<Item = T>,
     {
-        let mut set = SplaySet::new();
-        for x in iter {
-            set.insert(x);
-        }
+        let mut set = SplaySet::default();
+        set.extend(iter);
         set
     }
 }
-impl<T> IntoIterator for SplaySet<T> {
+#[cfg(feature = "serde")]
+impl<T, A, C, I> ::serde::Serialize for SplaySet<T, A, C, I>
+where
+    T: ::serde::Serialize,
+    C: Compare<T>,
+    A: Augment<T, ()>,
+    I: Index,
+{
+    /// Serializes the set as a sequence of the values in ascending order,
+    /// in the same representation as `BTreeSet`.
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: ::serde::Serializer,
+    {
+        use serde::ser::SerializeSeq;
+        let mut seq = serializer.serialize_seq(Some(self.len()))?;
+        for (x, _) in self.tree.iter() {
+            seq.serialize_element(x)?;
+        }
+        seq.end()
+    }
+}
+#[cfg(feature = "serde")]
+impl<'de, T, A, C, I> ::serde::Deserialize<'de> for SplaySet<T, A, C, I>
+where
+    T: ::serde::Deserialize<'de>,
+    C: Compare<T> + Default,
+    A: Augment<T, ()>,
+    I: Index,
+{
+    /// Deserializes a sequence of the values, and links them into a perfectly balanced tree.
+    ///
+    /// The comparator is made by `Default`.
+    /// Fails if the values are not sorted in strictly ascending order by the comparator,
+    /// or the number of the values exceeds the limit of the index type.
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: ::serde::Deserializer<'de>,
+    {
+        struct Visitor<T, A, C, I>(std::marker::PhantomData<SplaySet<T, A, C, I>>);
+        impl<'de, T, A, C, I> ::serde::de::Visitor<'de> for Visitor<T, A, C, I>
+        where
+            T: ::serde::Deserialize<'de>,
+            C: Compare<T> + Default,
+            A: Augment<T, ()>,
+            I: Index,
+        {
+            type Value = SplaySet<T, A, C, I>;
+
+            fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                f.write_str("a sequence")
+            }
+            fn visit_seq<M>(self, mut access: M) -> Result<Self::Value, M::Error>
+            where
+                M: ::serde::de::SeqAccess<'de>,
+            {
+                let tree = tree_core::Tree::deserialize_sorted(C::default(), || {
+                    access.next_element().map(|x| x.map(|x| (x, ())))
+                })?;
+                Ok(SplaySet { tree })
+            }
+        }
+        deserializer.deserialize_seq(Visitor(std::marker::PhantomData))
+    }
+}
+impl<T, A, C, I> IntoIterator for SplaySet<T, A, C, I>
+where
+    A: Augment<T, ()>,
+    I: Index,
+{
     type Item = T;
-    type IntoIter = IntoIter<T>;
+    type IntoIter = IntoIter<T, A, I>;
     fn into_iter(self) -> Self::IntoIter {
         IntoIter(self.tree.into_iter())
     }
 }
-impl<'a, T> IntoIterator for &'a SplaySet<T> {
+impl<'a, T, A, C, I> IntoIterator for &'a SplaySet<T, A, C, I>
+where
+    I: Index,
+{
     type Item = &'a T;
-    type IntoIter = Iter<'a, T>;
+    type IntoIter = Iter<'a, T, A, I>;
     fn into_iter(self) -> Self::IntoIter {
         Iter::new(self)
     }
 }
-impl<T> Extend<T> for SplaySet<T>
+impl<T, A, C, I> Extend<T> for SplaySet<T, A, C, I>
 where
-    T: Ord,
+    C: Compare<T>,
+    A: Augment<T, ()>,
+    I: Index,
 {
-    fn extend<I>(&mut self, iter: I)
+    fn extend<It>(&mut self, iter: It)
     where
-        I: IntoIterator<Item = T>,
+        It: IntoIterator<Item = T>,
     {
-        for x in iter {
-            self.insert(x);
-        }
+        self.tree.extend(iter.into_iter().map(|x| (x, ())));
     }
 }
-impl<'a, T> Extend<&'a T> for SplaySet<T>
+impl<'a, T, A, C, I> Extend<&'a T> for SplaySet<T, A, C, I>
 where
-    T: Copy + 'a + Ord,
+    T: Copy + 'a,
+    A: Augment<T, ()>,
+    C: Compare<T>,
+    I: Index,
 {
-    fn extend<I>(&mut self, iter: I)
+    fn extend<It>(&mut self, iter: It)
     where
-        I: IntoIterator<Item = &'a T>,
+        It: IntoIterator<Item = &'a T>,
     {
-        for x in iter {
-            self.insert(*x);
-        }
+        self.tree.extend(iter.into_iter().map(|x| (*x, ())));
     }
 }
-impl<'a, 'b, T> ops::Sub<&'b SplaySet<T>> for &'a SplaySet<T>
+impl<'a, 'b, T, A, C, I> ops::Sub<&'b SplaySet<T, A, C, I>> for &'a SplaySet<T, A, C, I>
 where
-    T: Ord + Clone,
+    T: Clone,
+    C: Compare<T> + Clone,
+    A: Augment<T, ()>,
+    I: Index,
 {
-    type Output = SplaySet<T>;
+    type Output = SplaySet<T, A, C, I>;
 
     /// Returns the difference of `self` and `rhs` as a new `SplaySet<T>`.
     ///
@@ -707,15 +1498,22 @@ where
     /// assert_eq!((&a - &b).into_iter().collect::<Vec<_>>(),
     ///            [1, 2]);
     /// ```
-    fn sub(self, rhs: &SplaySet<T>) -> SplaySet<T> {
-        self.difference(rhs).cloned().collect()
+    fn sub(self, rhs: &SplaySet<T, A, C, I>) -> SplaySet<T, A, C, I> {
+        let mut set = SplaySet {
+            tree: self.tree.empty_like(),
+        };
+        set.extend(self.difference(rhs).cloned());
+        set
     }
 }
-impl<'a, 'b, T> ops::BitXor<&'b SplaySet<T>> for &'a SplaySet<T>
+impl<'a, 'b, T, A, C, I> ops::BitXor<&'b SplaySet<T, A, C, I>> for &'a SplaySet<T, A, C, I>
 where
-    T: Ord + Clone,
+    T: Clone,
+    C: Compare<T> + Clone,
+    A: Augment<T, ()>,
+    I: Index,
 {
-    type Output = SplaySet<T>;
+    type Output = SplaySet<T, A, C, I>;
 
     /// Returns the symmetric difference of `self` and `rhs` as a new `SplaySet<T>`.
     ///
@@ -729,15 +1527,22 @@ where
     /// assert_eq!((&a ^ &b).into_iter().collect::<Vec<_>>(),
     ///            [1, 2, 4, 5]);
     /// ```
-    fn bitxor(self, rhs: &SplaySet<T>) -> SplaySet<T> {
-        self.symmetric_difference(rhs).cloned().collect()
+    fn bitxor(self, rhs: &SplaySet<T, A, C, I>) -> SplaySet<T, A, C, I> {
+        let mut set = SplaySet {
+            tree: self.tree.empty_like(),
+        };
+        set.extend(self.symmetric_difference(rhs).cloned());
+        set
     }
 }
-impl<'a, 'b, T> ops::BitAnd<&'b SplaySet<T>> for &'a SplaySet<T>
+impl<'a, 'b, T, A, C, I> ops::BitAnd<&'b SplaySet<T, A, C, I>> for &'a SplaySet<T, A, C, I>
 where
-    T: Ord + Clone,
+    T: Clone,
+    C: Compare<T> + Clone,
+    A: Augment<T, ()>,
+    I: Index,
 {
-    type Output = SplaySet<T>;
+    type Output = SplaySet<T, A, C, I>;
 
     /// Returns the intersection of `self` and `rhs` as a new `SplaySet<T>`.
     ///
@@ -751,15 +1556,22 @@ where
     /// assert_eq!((&a & &b).into_iter().collect::<Vec<_>>(),
     ///            [3]);
     /// ```
-    fn bitand(self, rhs: &SplaySet<T>) -> SplaySet<T> {
-        self.intersection(rhs).cloned().collect()
+    fn bitand(self, rhs: &SplaySet<T, A, C, I>) -> SplaySet<T, A, C, I> {
+        let mut set = SplaySet {
+            tree: self.tree.empty_like(),
+        };
+        set.extend(self.intersection(rhs).cloned());
+        set
     }
 }
-impl<'a, 'b, T> ops::BitOr<&'b SplaySet<T>> for &'a SplaySet<T>
+impl<'a, 'b, T, A, C, I> ops::BitOr<&'b SplaySet<T, A, C, I>> for &'a SplaySet<T, A, C, I>
 where
-    T: Ord + Clone,
+    T: Clone,
+    C: Compare<T> + Clone,
+    A: Augment<T, ()>,
+    I: Index,
 {
-    type Output = SplaySet<T>;
+    type Output = SplaySet<T, A, C, I>;
 
     /// Returns the union of `self` and `rhs` as a new `SplaySet<T>`.
     ///
@@ -773,33 +1585,267 @@ where
     /// assert_eq!((&a | &b).into_iter().collect::<Vec<_>>(),
     ///            [1, 2, 3, 4, 5]);
     /// ```
-    fn bitor(self, rhs: &SplaySet<T>) -> SplaySet<T> {
-        self.union(rhs).cloned().collect()
+    fn bitor(self, rhs: &SplaySet<T, A, C, I>) -> SplaySet<T, A, C, I> {
+        let mut set = SplaySet {
+            tree: self.tree.empty_like(),
+        };
+        set.extend(self.union(rhs).cloned());
+        set
     }
 }
 
 /// An Iterator over a SplaySet items.
-pub struct Iter<'a, T: 'a>(iter::Iter<'a, T, ()>);
-impl<'a, T: 'a> Iter<'a, T> {
-    fn new(set: &'a SplaySet<T>) -> Self {
+pub struct Iter<'a, T: 'a, A: 'a = (), I: 'a = u32>(iter::Iter<'a, T, (), A, I>);
+impl<'a, T: 'a, A: 'a, I: 'a> Iter<'a, T, A, I>
+where
+    I: Index,
+{
+    fn new<C>(set: &'a SplaySet<T, A, C, I>) -> Self {
         Iter(set.tree.iter())
     }
 }
-impl<'a, T: 'a> Iterator for Iter<'a, T> {
+impl<'a, T: 'a, A: 'a, I: 'a> Iterator for Iter<'a, T, A, I>
+where
+    I: Index,
+{
+    type Item = &'a T;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(|(e, _)| e)
+    }
+}
+impl<'a, T: 'a, A: 'a, I: 'a> DoubleEndedIterator for Iter<'a, T, A, I>
+where
+    I: Index,
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.0.next_back().map(|(e, _)| e)
+    }
+}
+
+/// An iterator over a sub-range of SplaySet items.
+pub struct Range<'a, T: 'a, A: 'a = (), I: 'a = u32>(iter::Iter<'a, T, (), A, I>);
+impl<'a, T: 'a, A: 'a, I: 'a> Iterator for Range<'a, T, A, I>
+where
+    I: Index,
+{
     type Item = &'a T;
     fn next(&mut self) -> Option<Self::Item> {
         self.0.next().map(|(e, _)| e)
     }
 }
+impl<'a, T: 'a, A: 'a, I: 'a> DoubleEndedIterator for Range<'a, T, A, I>
+where
+    I: Index,
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.0.next_back().map(|(e, _)| e)
+    }
+}
+
+impl<T, A, C, I> ops::Sub<SplaySet<T, A, C, I>> for SplaySet<T, A, C, I>
+where
+    C: Compare<T>,
+    A: Augment<T, ()>,
+    I: Index,
+{
+    type Output = SplaySet<T, A, C, I>;
+
+    /// Returns the difference of `self` and `rhs`, consuming both sets in linear time.
+    ///
+    /// # Examples
+    /// ```
+    /// use splay_tree::SplaySet;
+    ///
+    /// let a: SplaySet<_> = vec![1, 2, 3].into_iter().collect();
+    /// let b: SplaySet<_> = vec![3, 4, 5].into_iter().collect();
+    ///
+    /// assert_eq!((a - b).into_iter().collect::<Vec<_>>(),
+    ///            [1, 2]);
+    /// ```
+    fn sub(mut self, rhs: SplaySet<T, A, C, I>) -> SplaySet<T, A, C, I> {
+        self.difference_with(&rhs);
+        self
+    }
+}
+impl<T, A, C, I> ops::BitXor<SplaySet<T, A, C, I>> for SplaySet<T, A, C, I>
+where
+    C: Compare<T>,
+    A: Augment<T, ()>,
+    I: Index,
+{
+    type Output = SplaySet<T, A, C, I>;
+
+    /// Returns the symmetric difference of `self` and `rhs`, consuming both sets in linear time.
+    ///
+    /// # Examples
+    /// ```
+    /// use splay_tree::SplaySet;
+    ///
+    /// let a: SplaySet<_> = vec![1, 2, 3].into_iter().collect();
+    /// let b: SplaySet<_> = vec![3, 4, 5].into_iter().collect();
+    ///
+    /// assert_eq!((a ^ b).into_iter().collect::<Vec<_>>(),
+    ///            [1, 2, 4, 5]);
+    /// ```
+    fn bitxor(mut self, rhs: SplaySet<T, A, C, I>) -> SplaySet<T, A, C, I> {
+        self.symmetric_difference_with(rhs);
+        self
+    }
+}
+impl<T, A, C, I> ops::BitAnd<SplaySet<T, A, C, I>> for SplaySet<T, A, C, I>
+where
+    C: Compare<T>,
+    A: Augment<T, ()>,
+    I: Index,
+{
+    type Output = SplaySet<T, A, C, I>;
+
+    /// Returns the intersection of `self` and `rhs`, consuming both sets in linear time.
+    ///
+    /// # Examples
+    /// ```
+    /// use splay_tree::SplaySet;
+    ///
+    /// let a: SplaySet<_> = vec![1, 2, 3].into_iter().collect();
+    /// let b: SplaySet<_> = vec![3, 4, 5].into_iter().collect();
+    ///
+    /// assert_eq!((a & b).into_iter().collect::<Vec<_>>(),
+    ///            [3]);
+    /// ```
+    fn bitand(mut self, rhs: SplaySet<T, A, C, I>) -> SplaySet<T, A, C, I> {
+        self.intersect_with(&rhs);
+        self
+    }
+}
+impl<T, A, C, I> ops::BitOr<SplaySet<T, A, C, I>> for SplaySet<T, A, C, I>
+where
+    C: Compare<T>,
+    A: Augment<T, ()>,
+    I: Index,
+{
+    type Output = SplaySet<T, A, C, I>;
 
-impl<T> std::ops::Index<usize> for SplaySet<T> {
+    /// Returns the union of `self` and `rhs`, consuming both sets in linear time.
+    ///
+    /// # Examples
+    /// ```
+    /// use splay_tree::SplaySet;
+    ///
+    /// let a: SplaySet<_> = vec![1, 2, 3].into_iter().collect();
+    /// let b: SplaySet<_> = vec![3, 4, 5].into_iter().collect();
+    ///
+    /// assert_eq!((a | b).into_iter().collect::<Vec<_>>(),
+    ///            [1, 2, 3, 4, 5]);
+    /// ```
+    fn bitor(mut self, rhs: SplaySet<T, A, C, I>) -> SplaySet<T, A, C, I> {
+        self.union_with(rhs);
+        self
+    }
+}
+impl<'a, T, A, C, I> ops::SubAssign<&'a SplaySet<T, A, C, I>> for SplaySet<T, A, C, I>
+where
+    C: Compare<T>,
+    A: Augment<T, ()>,
+    I: Index,
+{
+    /// Updates `self` to the difference of `self` and `rhs`, in linear time.
+    ///
+    /// # Examples
+    /// ```
+    /// use splay_tree::SplaySet;
+    ///
+    /// let mut a: SplaySet<_> = vec![1, 2, 3].into_iter().collect();
+    /// let b: SplaySet<_> = vec![3, 4, 5].into_iter().collect();
+    ///
+    /// a -= &b;
+    /// assert_eq!(a.into_iter().collect::<Vec<_>>(), [1, 2]);
+    /// ```
+    fn sub_assign(&mut self, rhs: &'a SplaySet<T, A, C, I>) {
+        self.difference_with(rhs);
+    }
+}
+impl<T, A, C, I> ops::BitXorAssign<SplaySet<T, A, C, I>> for SplaySet<T, A, C, I>
+where
+    C: Compare<T>,
+    A: Augment<T, ()>,
+    I: Index,
+{
+    /// Updates `self` to the symmetric difference of `self` and `rhs`, in linear time.
+    ///
+    /// # Examples
+    /// ```
+    /// use splay_tree::SplaySet;
+    ///
+    /// let mut a: SplaySet<_> = vec![1, 2, 3].into_iter().collect();
+    /// let b: SplaySet<_> = vec![3, 4, 5].into_iter().collect();
+    ///
+    /// a ^= b;
+    /// assert_eq!(a.into_iter().collect::<Vec<_>>(), [1, 2, 4, 5]);
+    /// ```
+    fn bitxor_assign(&mut self, rhs: SplaySet<T, A, C, I>) {
+        self.symmetric_difference_with(rhs);
+    }
+}
+impl<'a, T, A, C, I> ops::BitAndAssign<&'a SplaySet<T, A, C, I>> for SplaySet<T, A, C, I>
+where
+    C: Compare<T>,
+    A: Augment<T, ()>,
+    I: Index,
+{
+    /// Updates `self` to the intersection of `self` and `rhs`, in linear time.
+    ///
+    /// # Examples
+    /// ```
+    /// use splay_tree::SplaySet;
+    ///
+    /// let mut a: SplaySet<_> = vec![1, 2, 3].into_iter().collect();
+    /// let b: SplaySet<_> = vec![3, 4, 5].into_iter().collect();
+    ///
+    /// a &= &b;
+    /// assert_eq!(a.into_iter().collect::<Vec<_>>(), [3]);
+    /// ```
+    fn bitand_assign(&mut self, rhs: &'a SplaySet<T, A, C, I>) {
+        self.intersect_with(rhs);
+    }
+}
+impl<T, A, C, I> ops::BitOrAssign<SplaySet<T, A, C, I>> for SplaySet<T, A, C, I>
+where
+    C: Compare<T>,
+    A: Augment<T, ()>,
+    I: Index,
+{
+    /// Updates `self` to the union of `self` and `rhs`, in linear time.
+    ///
+    /// # Examples
+    /// ```
+    /// use splay_tree::SplaySet;
+    ///
+    /// let mut a: SplaySet<_> = vec![1, 2, 3].into_iter().collect();
+    /// let b: SplaySet<_> = vec![3, 4, 5].into_iter().collect();
+    ///
+    /// a |= b;
+    /// assert_eq!(a.into_iter().collect::<Vec<_>>(), [1, 2, 3, 4, 5]);
+    /// ```
+    fn bitor_assign(&mut self, rhs: SplaySet<T, A, C, I>) {
+        self.union_with(rhs);
+    }
+}
+/// Indexes the elements in sorted order.
+///
+/// This needs an augmentation which counts the elements (e.g., `Count`),
+/// so the element is found in `O(log n)` amortized time.
+/// The elements of a set without such an augmentation can be walked by `iter().nth(index)` instead.
+impl<T, A, C, I> std::ops::Index<usize> for SplaySet<T, A, C, I>
+where
+    A: Counted<T, ()>,
+    I: Index,
+{
     type Output = T;
 
     fn index(&self, index: usize) -> &Self::Output {
-        if index < self.tree.len() {
-            &self.tree.node_ref(index as tree_core::NodeIndex).key
-        } else {
-            panic!("Index {} out of bounds! Len: {}", index, self.len());
+        match self.tree.select_ref(index) {
+            Some(node) => &node.key,
+            None => panic!("Index {} out of bounds! Len: {}", index, self.len()),
         }
     }
 }
@@ -883,36 +1929,108 @@ impl<'a, T: Ord + 'a> Iterator for BetterIter<'a, T> {
 // }
 
 /// An owning iterator over a SplaySet's items.
-pub struct IntoIter<T>(iter::IntoIter<T, ()>);
-impl<T> Iterator for IntoIter<T> {
+pub struct IntoIter<T, A = (), I = u32>(iter::IntoIter<T, (), A, I>)
+where
+    I: Index;
+impl<T, A, I> Iterator for IntoIter<T, A, I>
+where
+    I: Index,
+{
     type Item = T;
     fn next(&mut self) -> Option<Self::Item> {
         self.0.next().map(|(e, _)| e)
     }
 }
+impl<T, A, I> DoubleEndedIterator for IntoIter<T, A, I>
+where
+    I: Index,
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.0.next_back().map(|(e, _)| e)
+    }
+}
 
-fn item_cmp<T>(a: Option<&T>, b: Option<&T>) -> Option<cmp::Ordering>
+/// An iterator which removes the elements of a SplaySet matching a predicate.
+///
+/// This is created by the `extract_if` method on `SplaySet`.
+pub struct ExtractIf<'a, T: 'a, A: 'a, C: 'a, I: 'a, F>
 where
-    T: Ord,
+    A: Augment<T, ()>,
+    I: Index,
+    F: FnMut(&T) -> bool,
+{
+    inner: tree_core::Extract<'a, T, (), A, C, I>,
+    pred: F,
+}
+impl<'a, T: 'a, A: 'a, C: 'a, I: 'a, F> Iterator for ExtractIf<'a, T, A, C, I, F>
+where
+    A: Augment<T, ()>,
+    F: FnMut(&T) -> bool,
+    I: Index,
+{
+    type Item = T;
+    fn next(&mut self) -> Option<Self::Item> {
+        let pred = &mut self.pred;
+        self.inner.next_by(|e, _| pred(e)).map(|(e, _)| e)
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+/// A draining iterator over a SplaySet's items.
+///
+/// The set is emptied as soon as this is created, so this owns the items like `IntoIter`.
+pub struct Drain<T, A = (), I = u32>(iter::IntoIter<T, (), A, I>)
+where
+    I: Index;
+impl<T, A, I> Iterator for Drain<T, A, I>
+where
+    I: Index,
+{
+    type Item = T;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(|(e, _)| e)
+    }
+}
+impl<T, A, I> DoubleEndedIterator for Drain<T, A, I>
+where
+    I: Index,
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.0.next_back().map(|(e, _)| e)
+    }
+}
+
+fn item_cmp<T, C>(cmp: &C, a: Option<&&T>, b: Option<&&T>) -> Option<cmp::Ordering>
+where
+    C: Compare<T>,
 {
     match (a, b) {
         (None, None) => None,
         (Some(_), None) => Some(cmp::Ordering::Less),
         (None, Some(_)) => Some(cmp::Ordering::Greater),
-        (Some(a), Some(b)) => Some(a.cmp(b)),
+        (Some(a), Some(b)) => Some(cmp.compare(a, b)),
     }
 }
 
 /// A lazy iterator producing elements in the set difference (in-order).
-pub struct Difference<'a, T: 'a>(Peekable<Iter<'a, T>>, Peekable<Iter<'a, T>>);
-impl<'a, T: 'a> Iterator for Difference<'a, T>
+pub struct Difference<'a, T: 'a, A: 'a = (), C: 'a = Natural, I: 'a = u32>(
+    Peekable<Iter<'a, T, A, I>>,
+    Peekable<Iter<'a, T, A, I>>,
+    &'a C,
+)
 where
-    T: Ord,
+    I: Index;
+impl<'a, T: 'a, A: 'a, C: 'a, I: 'a> Iterator for Difference<'a, T, A, C, I>
+where
+    C: Compare<T>,
+    I: Index,
 {
     type Item = &'a T;
     fn next(&mut self) -> Option<Self::Item> {
         loop {
-            match item_cmp(self.0.peek(), self.1.peek()) {
+            match item_cmp(self.2, self.0.peek(), self.1.peek()) {
                 None => return None,
                 Some(cmp::Ordering::Less) => return self.0.next(),
                 Some(cmp::Ordering::Greater) => {
@@ -928,15 +2046,22 @@ where
 }
 
 /// A lazy iterator producing elements in the set symmetric difference (in-order).
-pub struct SymmetricDifference<'a, T: 'a>(Peekable<Iter<'a, T>>, Peekable<Iter<'a, T>>);
-impl<'a, T: 'a> Iterator for SymmetricDifference<'a, T>
+pub struct SymmetricDifference<'a, T: 'a, A: 'a = (), C: 'a = Natural, I: 'a = u32>(
+    Peekable<Iter<'a, T, A, I>>,
+    Peekable<Iter<'a, T, A, I>>,
+    &'a C,
+)
 where
-    T: Ord,
+    I: Index;
+impl<'a, T: 'a, A: 'a, C: 'a, I: 'a> Iterator for SymmetricDifference<'a, T, A, C, I>
+where
+    C: Compare<T>,
+    I: Index,
 {
     type Item = &'a T;
     fn next(&mut self) -> Option<Self::Item> {
         loop {
-            match item_cmp(self.0.peek(), self.1.peek()) {
+            match item_cmp(self.2, self.0.peek(), self.1.peek()) {
                 None => return None,
                 Some(cmp::Ordering::Less) => return self.0.next(),
                 Some(cmp::Ordering::Greater) => return self.1.next(),
@@ -950,15 +2075,22 @@ where
 }
 
 /// A lazy iterator producing elements in the set intersection (in-order).
-pub struct Intersection<'a, T: 'a>(Peekable<Iter<'a, T>>, Peekable<Iter<'a, T>>);
-impl<'a, T: 'a> Iterator for Intersection<'a, T>
+pub struct Intersection<'a, T: 'a, A: 'a = (), C: 'a = Natural, I: 'a = u32>(
+    Peekable<Iter<'a, T, A, I>>,
+    Peekable<Iter<'a, T, A, I>>,
+    &'a C,
+)
 where
-    T: Ord,
+    I: Index;
+impl<'a, T: 'a, A: 'a, C: 'a, I: 'a> Iterator for Intersection<'a, T, A, C, I>
+where
+    C: Compare<T>,
+    I: Index,
 {
     type Item = &'a T;
     fn next(&mut self) -> Option<Self::Item> {
         loop {
-            match item_cmp(self.0.peek(), self.1.peek()) {
+            match item_cmp(self.2, self.0.peek(), self.1.peek()) {
                 None => return None,
                 Some(cmp::Ordering::Less) => {
                     self.0.next();
@@ -976,15 +2108,22 @@ where
 }
 
 /// A lazy iterator producing elements in the set union (in-order).
-pub struct Union<'a, T: 'a>(Peekable<Iter<'a, T>>, Peekable<Iter<'a, T>>);
-impl<'a, T: 'a> Iterator for Union<'a, T>
+pub struct Union<'a, T: 'a, A: 'a = (), C: 'a = Natural, I: 'a = u32>(
+    Peekable<Iter<'a, T, A, I>>,
+    Peekable<Iter<'a, T, A, I>>,
+    &'a C,
+)
 where
-    T: Ord,
+    I: Index;
+impl<'a, T: 'a, A: 'a, C: 'a, I: 'a> Iterator for Union<'a, T, A, C, I>
+where
+    C: Compare<T>,
+    I: Index,
 {
     type Item = &'a T;
     fn next(&mut self) -> Option<Self::Item> {
         loop {
-            match item_cmp(self.0.peek(), self.1.peek()) {
+            match item_cmp(self.2, self.0.peek(), self.1.peek()) {
                 None => return None,
                 Some(cmp::Ordering::Less) => return self.0.next(),
                 Some(cmp::Ordering::Greater) => return self.1.next(),
@@ -999,11 +2138,14 @@ where
 
 /// A vector like view of a set.
 #[derive(Debug, Clone)]
-pub struct VecLike<'a, T: 'a> {
-    inner: vec_like::VecLike<'a, T, ()>,
+pub struct VecLike<'a, T: 'a, A: 'a = (), C: 'a = Natural, I: 'a = u32> {
+    inner: vec_like::VecLike<'a, T, (), A, C, I>,
 }
-impl<'a, T: 'a> VecLike<'a, T> {
-    fn new(tree: &'a tree_core::Tree<T, ()>) -> Self {
+impl<'a, T: 'a, A: 'a, C: 'a, I: 'a> VecLike<'a, T, A, C, I>
+where
+    I: Index,
+{
+    fn new(tree: &'a tree_core::Tree<T, (), A, C, I>) -> Self {
         VecLike {
             inner: vec_like::VecLike::new(tree),
         }
@@ -1083,7 +2225,7 @@ impl<'a, T: 'a> VecLike<'a, T> {
     /// let vec = set.as_vec_like();
     /// assert_eq!(vec.iter().cloned().collect::<Vec<_>>(), ["foo", "bar", "baz"]);
     /// ```
-    pub fn iter(&self) -> VecLikeIter<'a, T> {
+    pub fn iter(&self) -> VecLikeIter<'a, T, A, I> {
         VecLikeIter(self.inner.iter())
     }
 
@@ -1121,12 +2263,14 @@ impl<'a, T: 'a> VecLike<'a, T> {
 
 /// A vector like mutable view of a set.
 #[derive(Debug)]
-pub struct VecLikeMut<'a, T: 'a> {
-    inner: vec_like::VecLikeMut<'a, T, ()>,
+pub struct VecLikeMut<'a, T: 'a, A: 'a = (), C: 'a = Natural, I: 'a = u32> {
+    inner: vec_like::VecLikeMut<'a, T, (), A, C, I>,
 }
-impl<'a, T: 'a> VecLikeMut<'a, T>
+impl<'a, T: 'a, A: 'a, C: 'a, I: 'a> VecLikeMut<'a, T, A, C, I>
 where
-    T: Ord,
+    C: Compare<T>,
+    A: Augment<T, ()>,
+    I: Index,
 {
     /// Appends a new element to the back of the vector like set.
     ///
@@ -1206,13 +2350,16 @@ where
     pub fn find_index<Q: ?Sized>(&mut self, value: &Q) -> Option<usize>
     where
         T: Borrow<Q>,
-        Q: Ord,
+        C: Compare<Q>,
     {
         self.inner.find_index(value)
     }
 }
-impl<'a, T: 'a> VecLikeMut<'a, T> {
-    fn new(tree: &'a mut tree_core::Tree<T, ()>) -> Self {
+impl<'a, T: 'a, A: 'a, C: 'a, I: 'a> VecLikeMut<'a, T, A, C, I>
+where
+    I: Index,
+{
+    fn new(tree: &'a mut tree_core::Tree<T, (), A, C, I>) -> Self {
         VecLikeMut {
             inner: vec_like::VecLikeMut::new(tree),
         }
@@ -1297,7 +2444,7 @@ impl<'a, T: 'a> VecLikeMut<'a, T> {
     /// let vec = set.as_vec_like_mut();
     /// assert_eq!(vec.iter().cloned().collect::<Vec<_>>(), ["foo", "bar", "baz"]);
     /// ```
-    pub fn iter(&self) -> VecLikeIter<T> {
+    pub fn iter(&self) -> VecLikeIter<T, A, I> {
         VecLikeIter(self.inner.iter())
     }
 
@@ -1341,8 +2488,11 @@ impl<'a, T: 'a> VecLikeMut<'a, T> {
 
 /// An iterator over a VecLike's elements
 #[derive(Clone)]
-pub struct VecLikeIter<'a, T: 'a>(vec_like::Iter<'a, T, ()>);
-impl<'a, T: 'a> Iterator for VecLikeIter<'a, T> {
+pub struct VecLikeIter<'a, T: 'a, A: 'a = (), I: 'a = u32>(vec_like::Iter<'a, T, (), A, I>);
+impl<'a, T: 'a, A: 'a, I: 'a> Iterator for VecLikeIter<'a, T, A, I>
+where
+    I: Index,
+{
     type Item = &'a T;
     fn next(&mut self) -> Option<Self::Item> {
         self.0.next().map(|(v, _)| v)
@@ -1,140 +1,199 @@
 //! In-place top-down splay tree implementation
-use std::u32;
 use std::mem;
 use std::cmp;
 use std::hash;
 use std::slice;
 use std::borrow::Borrow;
 use std::cmp::Ordering;
+use std::ops::{Bound, RangeBounds};
 use std::vec::Vec;
 use iter;
+use augment::{Augment, Counted, Folded, Monoid, Tagged};
+use compare::{Compare, Natural};
+use index::Index;
+use error::{InvariantError, TryReserveError};
+use policy::SplayPolicy;
 
-pub type NodeIndex = u32;
-const NULL_NODE: NodeIndex = u32::MAX;
+pub const FULL: &str = "The number of entries exceeds the limit of the index type";
+
+// The (nonzero) initial state of the xorshift generator of `SplayPolicy::Probability`.
+const SEED: u64 = 0x9E37_79B9_7F4A_7C15;
+
+/// An entry of a linear merge of two trees.
+pub enum Merged<T> {
+    /// The key is only in the left tree.
+    Lft(T),
+    /// The key is only in the right tree.
+    Rgt(T),
+    /// The key is in both trees.
+    Both(T, T),
+}
 
 #[derive(Debug, Clone)]
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-pub struct Node<K, V> {
-    lft: NodeIndex,
-    rgt: NodeIndex,
+pub struct Node<K, V, A = (), I = u32> {
+    lft: I,
+    rgt: I,
     pub key: K,
     pub val: V,
+    pub aug: A,
 }
-impl<K, V> Node<K, V> {
-    pub fn new(key: K, value: V, lft: NodeIndex, rgt: NodeIndex) -> Self {
+impl<K, V, A, I> Node<K, V, A, I>
+where
+    A: Augment<K, V>,
+    I: Index,
+{
+    pub fn new(key: K, value: V, lft: I, rgt: I) -> Self {
         Node {
+            aug: A::new(&key, &value),
             key: key,
             val: value,
             lft: lft,
             rgt: rgt,
         }
     }
-    pub fn rgt(&self) -> Option<NodeIndex> {
-        if self.rgt != NULL_NODE {
+}
+impl<K, V, A, I> Node<K, V, A, I>
+where
+    I: Index,
+{
+    pub fn rgt(&self) -> Option<I> {
+        if self.rgt != I::NULL {
             Some(self.rgt)
         } else {
             None
         }
     }
-    pub fn lft(&self) -> Option<NodeIndex> {
-        if self.lft != NULL_NODE {
+    pub fn lft(&self) -> Option<I> {
+        if self.lft != I::NULL {
             Some(self.lft)
         } else {
             None
         }
     }
 }
-impl<K, V> Into<(K, V)> for Node<K, V> {
+impl<K, V, A, I> Into<(K, V)> for Node<K, V, A, I> {
     fn into(self) -> (K, V) {
         (self.key, self.val)
     }
 }
-impl<'a, K, V> Into<(&'a K, &'a V)> for &'a Node<K, V> {
+impl<'a, K, V, A, I> Into<(&'a K, &'a V)> for &'a Node<K, V, A, I> {
     fn into(self) -> (&'a K, &'a V) {
         (&self.key, &self.val)
     }
 }
-impl<'a, K, V> Into<(&'a K, &'a mut V)> for &'a mut Node<K, V> {
+impl<'a, K, V, A, I> Into<(&'a K, &'a mut V)> for &'a mut Node<K, V, A, I> {
     fn into(self) -> (&'a K, &'a mut V) {
         (&self.key, &mut self.val)
     }
 }
 
 #[derive(Debug, Clone)]
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-pub struct Tree<K, V> {
-    root: NodeIndex,
-    nodes: Vec<Node<K, V>>,
+pub struct Tree<K, V, A = (), C = Natural, I = u32> {
+    root: I,
+    nodes: Vec<Node<K, V, A, I>>,
+
+    // The comparator of the keys.
+    cmp: C,
+
+    // `true` if the augmentations may be out of date,
+    // since the values were mutably borrowed through the iterators.
+    stale: bool,
+
+    // `true` if some of the nodes may have lazy updates which are not pushed down yet.
+    pending: bool,
+
+    // The policy of the lookups, and the state of the generator of its random decisions.
+    policy: SplayPolicy,
+    seed: u64,
 }
-impl<K, V> Tree<K, V>
+impl<K, V, A, C, I> Tree<K, V, A, C, I>
 where
-    K: Ord,
+    C: Compare<K>,
+    A: Augment<K, V>,
+    I: Index,
 {
-    pub fn new() -> Self {
-        Tree {
-            root: 0,
-            nodes: Vec::new(),
-        }
-    }
     pub fn contains_key<Q: ?Sized>(&mut self, key: &Q) -> bool
     where
         K: Borrow<Q>,
-        Q: Ord,
+        C: Compare<Q>,
     {
-        self.root().map_or(false, |root| {
-            let (root, order) = self.splay(root, key);
-            self.root = root;
-            order == Ordering::Equal
-        })
+        let found = self.splay(key).map_or(false, |(_, order)| order == Ordering::Equal);
+        self.debug_check();
+        found
+    }
+    /// Looks up `key` in the manner decided by the policy, and returns the node if it is found.
+    ///
+    /// Unlike `contains_key`, the node is not necessarily at the root.
+    pub fn lookup<Q: ?Sized>(&mut self, key: &Q) -> Option<I>
+    where
+        K: Borrow<Q>,
+        C: Compare<Q>,
+    {
+        let policy = self.policy;
+        let found = self.splay_by(policy, |c, k| c.compare(key, k.borrow()));
+        self.debug_check();
+        match found {
+            Some((i, Ordering::Equal)) => Some(i),
+            _ => None,
+        }
+    }
+    /// Looks up the leftmost node in the manner decided by the policy.
+    pub fn lookup_lftmost(&mut self) -> Option<(&K, &V)> {
+        let policy = self.policy;
+        let (i, _) = self.splay_by(policy, |_, _| Ordering::Less)?;
+        self.debug_check();
+        Some(self.node_ref(i).into())
     }
-    pub fn find_lower_bound<Q: ?Sized>(&mut self, key: &Q) -> Option<&K>
+    /// Looks up the rightmost node in the manner decided by the policy.
+    pub fn lookup_rgtmost(&mut self) -> Option<(&K, &V)> {
+        let policy = self.policy;
+        let (i, _) = self.splay_by(policy, |_, _| Ordering::Greater)?;
+        self.debug_check();
+        Some(self.node_ref(i).into())
+    }
+    pub fn find_lower_bound<Q: ?Sized>(&mut self, key: &Q) -> Option<&mut Node<K, V, A, I>>
     where
         K: Borrow<Q>,
-        Q: Ord,
+        C: Compare<Q>,
     {
-        self.find_bound(|k| key.cmp(k.borrow()))
+        self.find_bound(|c, k| c.compare(key, k.borrow()))
     }
-    pub fn find_upper_bound<Q: ?Sized>(&mut self, key: &Q) -> Option<&K>
+    pub fn find_upper_bound<Q: ?Sized>(&mut self, key: &Q) -> Option<&mut Node<K, V, A, I>>
     where
         K: Borrow<Q>,
-        Q: Ord,
+        C: Compare<Q>,
     {
-        self.find_bound(|k| match key.cmp(k.borrow()) {
+        self.find_bound(|c, k| match c.compare(key, k.borrow()) {
             Ordering::Equal => Ordering::Greater,
             other => other,
         })
     }
-    pub fn find_less<Q: ?Sized>(&mut self, key: &Q) -> Option<&K>
+    pub fn find_less<Q: ?Sized>(&mut self, key: &Q) -> Option<&mut Node<K, V, A, I>>
     where
         K: Borrow<Q>,
-        Q: Ord,
-    {
-        let found = std::cell::RefCell::new(false);
-        self.find_bound_below(|k| {
-            match key.cmp(k.borrow()) {
-                Ordering::Equal => Ordering::Less,
-                other => other,
-            }
-
-
-                        // match key.cmp(k.borrow()) {
-            // Ordering::Less => Ordering::Greater,
-            // Ordering::Equal => {
-            //     if *found.borrow() == true {
-            //         Ordering::Equal
-            //     } else {
-            //         *found.borrow_mut() = true;
-            //         Ordering::Equal
-            //     }
-            // }
-            // Ordering::Greater => Ordering::Less
+        C: Compare<Q>,
+    {
+        self.find_bound_below(|c, k| match c.compare(key, k.borrow()) {
+            Ordering::Equal => Ordering::Less,
+            other => other,
         })
     }
+    pub fn find_less_or_equal<Q: ?Sized>(&mut self, key: &Q) -> Option<&mut Node<K, V, A, I>>
+    where
+        K: Borrow<Q>,
+        C: Compare<Q>,
+    {
+        self.find_bound_below(|c, k| c.compare(key, k.borrow()))
+    }
     pub fn get<Q: ?Sized>(&mut self, key: &Q) -> Option<&mut V>
     where
         K: Borrow<Q>,
-        Q: Ord,
+        C: Compare<Q>,
     {
+        if A::STATELESS {
+            // No augmentation depends on the value, so it can be mutated at any node.
+            return self.lookup(key).map(move |i| &mut self.node_mut(i).val);
+        }
         if self.contains_key(key) {
             Some(&mut self.root_mut().val)
         } else {
@@ -142,36 +201,198 @@ where
         }
     }
     pub fn insert(&mut self, key: K, value: V) -> Option<V> {
-        if let Some(root) = self.root() {
-            let (root, order) = self.splay(root, &key);
-            self.root = root;
+        let old = if let Some((root, order)) = self.splay(&key) {
             match order {
                 Ordering::Equal => {
                     let old = mem::replace(&mut self.root_mut().val, value);
+                    self.update(root);
                     Some(old)
                 }
                 Ordering::Less => {
-                    let lft = mem::replace(&mut self.root_mut().lft, NULL_NODE);
+                    let lft = mem::replace(&mut self.root_mut().lft, I::NULL);
                     let rgt = self.root;
+                    self.update(rgt);
                     self.push_root(Node::new(key, value, lft, rgt));
                     None
                 }
                 Ordering::Greater => {
-                    let rgt = mem::replace(&mut self.root_mut().rgt, NULL_NODE);
+                    let rgt = mem::replace(&mut self.root_mut().rgt, I::NULL);
                     let lft = self.root;
+                    self.update(lft);
                     self.push_root(Node::new(key, value, lft, rgt));
                     None
                 }
             }
         } else {
-            self.push_root(Node::new(key, value, NULL_NODE, NULL_NODE));
+            self.push_root(Node::new(key, value, I::NULL, I::NULL));
             None
+        };
+        self.debug_check();
+        old
+    }
+    /// Inserts a key-value pair, reserving the slot for a new node fallibly beforehand.
+    pub fn try_insert(&mut self, key: K, value: V) -> Result<Option<V>, TryReserveError> {
+        if !self.contains_key(&key) {
+            self.try_reserve(1)?;
+        }
+        Ok(self.insert(key, value))
+    }
+    /// Inserts the entries one by one, in the same manner as `insert`.
+    ///
+    /// The runs of strictly ascending entries greater than the existing keys
+    /// are put at the end of the arena in one pass, and linked as balanced subtrees
+    /// instead of being inserted one by one.
+    pub fn extend<T>(&mut self, entries: T)
+    where
+        T: IntoIterator<Item = (K, V)>,
+    {
+        let mut entries = entries.into_iter();
+        let mut max = self.fresh_root().map(|root| {
+            self.root = self.splay_rgtmost(root);
+            self.root
+        });
+        let mut next = entries.next();
+        while let Some((key, value)) = next.take() {
+            let greater = match max {
+                Some(max) => self.cmp.compare(&self.node_ref(max).key, &key) == Ordering::Less,
+                None => true,
+            };
+            if greater {
+                let (last, rest) = self.append_ascending(max, (key, value), &mut entries);
+                max = Some(last);
+                next = rest;
+            } else {
+                // Inserting a non-maximum key does not move the maximum node.
+                self.insert(key, value);
+                next = entries.next();
+            }
+        }
+        self.debug_check();
+    }
+    /// Appends `first` and the following strictly ascending entries of `rest`
+    /// as a balanced subtree, which becomes the right child of the maximum node `max`.
+    ///
+    /// Returns the new maximum node, and the first entry which did not fit in the run.
+    fn append_ascending<T>(
+        &mut self,
+        max: Option<I>,
+        first: (K, V),
+        rest: &mut T,
+    ) -> (I, Option<(K, V)>)
+    where
+        T: Iterator<Item = (K, V)>,
+    {
+        let run = Run {
+            offset: self.len(),
+            max,
+            tree: self,
+        };
+        let mut next = Some(first);
+        while let Some((key, value)) = next.take() {
+            let tree = &mut *run.tree;
+            if tree.len() > run.offset {
+                let last = &tree.nodes[tree.len() - 1].key;
+                if tree.cmp.compare(last, &key) != Ordering::Less {
+                    next = Some((key, value));
+                    break;
+                }
+            }
+            tree.new_slot();
+            tree.nodes.push(Node::new(key, value, I::NULL, I::NULL));
+            next = rest.next();
+        }
+        (I::from_usize(run.tree.len() - 1), next)
+    }
+    /// Replaces the entries of the tree with `entries`,
+    /// which must be sorted in strictly ascending order.
+    pub fn rebuild_from_sorted<T>(&mut self, entries: T)
+    where
+        T: IntoIterator<Item = (K, V)>,
+    {
+        self.rebuild_sorted(entries);
+        debug_assert!(
+            self.nodes
+                .windows(2)
+                .all(|w| self.cmp.compare(&w[0].key, &w[1].key) == Ordering::Less),
+            "The entries are not sorted in strictly ascending order"
+        );
+        self.debug_check();
+    }
+    /// Merges the entries of `self` and `other` by a linear merge of their in-order sequences,
+    /// and links the result as a balanced tree.
+    ///
+    /// `f` is called for each key in ascending order, and returns the entry to keep (if any).
+    /// The two trees must be ordered in the same way.
+    ///
+    /// If `f` or the comparator panics, the entries which are not merged yet are dropped.
+    pub fn merge_with<F>(&mut self, other: Self, mut f: F)
+    where
+        F: FnMut(Merged<(K, V)>) -> Option<(K, V)>,
+    {
+        let mut lft = self.drain().peekable();
+        let mut rgt = other.into_iter().peekable();
+        {
+            let run = Run {
+                offset: 0,
+                max: None,
+                tree: self,
+            };
+            loop {
+                let order = match (lft.peek(), rgt.peek()) {
+                    (None, None) => break,
+                    (Some(_), None) => Ordering::Less,
+                    (None, Some(_)) => Ordering::Greater,
+                    (Some(l), Some(r)) => run.tree.cmp.compare(&l.0, &r.0),
+                };
+                let merged = match order {
+                    Ordering::Less => lft.next().map(Merged::Lft),
+                    Ordering::Greater => rgt.next().map(Merged::Rgt),
+                    Ordering::Equal => lft.next().and_then(|l| rgt.next().map(|r| Merged::Both(l, r))),
+                };
+                if let Some((key, value)) = merged.and_then(&mut f) {
+                    run.tree.new_slot();
+                    run.tree.nodes.push(Node::new(key, value, I::NULL, I::NULL));
+                }
+            }
+        }
+        self.debug_check();
+    }
+    /// Retains only the entries of which keys are contained in `keys` (if `contained` is `true`)
+    /// or not contained in `keys` (otherwise), and links them as a balanced tree.
+    ///
+    /// `keys` must be sorted in ascending order.
+    /// If the comparator panics, the tree is left empty.
+    pub fn retain_by_keys<'b, T>(&mut self, keys: T, contained: bool)
+    where
+        T: IntoIterator<Item = &'b K>,
+        K: 'b,
+    {
+        let mut keys = keys.into_iter().peekable();
+        let mut kept = Vec::new();
+        for (k, v) in self.drain() {
+            let mut found = false;
+            while let Some(other) = keys.peek() {
+                match self.cmp.compare(other, &k) {
+                    Ordering::Less => {
+                        keys.next();
+                    }
+                    order => {
+                        found = order == Ordering::Equal;
+                        break;
+                    }
+                }
+            }
+            if found == contained {
+                kept.push((k, v));
+            }
         }
+        self.rebuild_sorted(kept);
+        self.debug_check();
     }
     pub fn remove<Q: ?Sized>(&mut self, key: &Q) -> Option<V>
     where
         K: Borrow<Q>,
-        Q: Ord,
+        C: Compare<Q>,
     {
         if self.contains_key(key) {
             Some(self.non_empty_pop_root().1)
@@ -179,315 +400,1761 @@ where
             None
         }
     }
+    /// Removes the last node of the arena.
     pub fn pop_last(&mut self) -> Option<(K, V)> {
-        self.nodes
-            .last()
-            .map(|n| unsafe { &*(&n.key as *const _) })
-            .map(|key| {
-                self.contains_key(key);
-                self.non_empty_pop_root()
-            })
+        self.fresh_root()?;
+        let last = I::from_usize(self.len() - 1);
+        self.splay_node(last);
+        Some(self.non_empty_pop_root())
     }
     pub fn pop_root(&mut self) -> Option<(K, V)> {
-        self.root().map(|_| self.non_empty_pop_root())
+        self.fresh_root().map(|_| self.non_empty_pop_root())
     }
     pub fn get_lftmost(&mut self) -> Option<(&K, &V)> {
-        self.root().map(move |root| {
-            self.root = self.splay_lftmost(root);
-            self.root_ref().into()
-        })
+        let root = self.fresh_root()?;
+        self.root = self.splay_lftmost(root);
+        self.debug_check();
+        Some(self.root_ref().into())
     }
     pub fn take_lftmost(&mut self) -> Option<(K, V)> {
-        self.root().map(|root| {
+        self.fresh_root().map(|root| {
             self.root = self.splay_lftmost(root);
             self.non_empty_pop_root()
         })
     }
     pub fn get_rgtmost(&mut self) -> Option<(&K, &V)> {
-        self.root().map(move |root| {
-            self.root = self.splay_rgtmost(root);
-            self.root_ref().into()
-        })
+        let root = self.fresh_root()?;
+        self.root = self.splay_rgtmost(root);
+        self.debug_check();
+        Some(self.root_ref().into())
     }
     pub fn take_rgtmost(&mut self) -> Option<(K, V)> {
-        self.root().map(|root| {
+        self.fresh_root().map(|root| {
             self.root = self.splay_rgtmost(root);
             self.non_empty_pop_root()
         })
     }
-    fn push_root(&mut self, node: Node<K, V>) {
-        self.nodes.push(node);
-        self.root = self.nodes.len() as NodeIndex - 1;
-        assert!(self.root != NULL_NODE);
-    }
-    fn splay<Q: ?Sized>(&mut self, root: NodeIndex, key: &Q) -> (NodeIndex, Ordering)
+    /// Splits the tree at `key`, and returns the entries whose keys are
+    /// greater than or equal to `key`.
+    ///
+    /// Only the smaller part is moved into a new arena.
+    pub fn split_off<Q: ?Sized>(&mut self, key: &Q) -> Self
     where
         K: Borrow<Q>,
-        Q: Ord,
+        C: Compare<Q> + Clone,
     {
-        self.splay_by(root, |k| key.cmp(k.borrow()))
-    }
-    fn splay_lftmost(&mut self, root: NodeIndex) -> NodeIndex {
-        self.splay_by(root, |_| Ordering::Less).0
-    }
-    fn splay_rgtmost(&mut self, root: NodeIndex) -> NodeIndex {
-        self.splay_by(root, |_| Ordering::Greater).0
+        let (root, order) = match self.splay(key) {
+            None => return self.empty_like(),
+            Some(found) => found,
+        };
+        let (lft, rgt) = if order == Ordering::Greater {
+            (root, mem::replace(&mut self.node_mut(root).rgt, I::NULL))
+        } else {
+            (mem::replace(&mut self.node_mut(root).lft, I::NULL), root)
+        };
+        self.update(root);
+        let rest = self.split::<ByKey>(lft, rgt);
+        self.debug_check();
+        rest.debug_check();
+        rest
     }
-    fn splay_by<F>(&mut self, mut curr_idx: NodeIndex, cmp: F) -> (NodeIndex, Ordering)
+
+    /// Moves all entries of `other` into `self`.
+    ///
+    /// If the keys of the two trees do not overlap,
+    /// the trees are joined without comparing the rest of the keys.
+    /// Otherwise, the trees are merged in linear time
+    /// (and the values of `other` take precedence).
+    pub fn append(&mut self, other: &mut Self)
     where
-        F: Fn(&K) -> Ordering,
+        C: Clone,
     {
-        use std::mem::replace;
-        let mut lft_root_idx = NULL_NODE;
-        let mut rgt_root_idx = NULL_NODE;
-        let mut curr_mut = unsafe { self.aliasable_node_mut(curr_idx) };
-        let mut order = cmp(curr_mut.key.borrow());
-        {
-            let mut lft_rgtmost_idx = &mut lft_root_idx;
-            let mut rgt_lftmost_idx = &mut rgt_root_idx;
-            loop {
-                let mut child_idx;
-                let mut child_mut;
-                match order {
-                    Ordering::Less if curr_mut.lft != NULL_NODE => {
-                        // zig
-                        child_idx = replace(&mut curr_mut.lft, NULL_NODE);
-                        child_mut = unsafe { self.aliasable_node_mut(child_idx) };
-                        order = cmp(child_mut.key.borrow());
-                        if Ordering::Less == order && child_mut.lft != NULL_NODE {
-                            // zig-zig
-                            let grand_child_idx = replace(&mut child_mut.lft, NULL_NODE);
-                            curr_mut.lft = replace(&mut child_mut.rgt, curr_idx);
-                            curr_idx = replace(&mut child_idx, grand_child_idx);
-                            curr_mut = replace(&mut child_mut, unsafe {
-                                self.aliasable_node_mut(grand_child_idx)
-                            });
-                            order = cmp(child_mut.key.borrow());
-                        }
-                        *rgt_lftmost_idx = curr_idx;
-                        rgt_lftmost_idx = unsafe { &mut *(&mut curr_mut.lft as *mut _) };
-                    }
-                    Ordering::Greater if curr_mut.rgt != NULL_NODE => {
-                        // zag
-                        child_idx = replace(&mut curr_mut.rgt, NULL_NODE);
-                        child_mut = unsafe { self.aliasable_node_mut(child_idx) };
-                        order = cmp(child_mut.key.borrow());
-                        if Ordering::Greater == order && child_mut.rgt != NULL_NODE {
-                            // zag-zag
-                            let grand_child_idx = replace(&mut child_mut.rgt, NULL_NODE);
-                            curr_mut.rgt = replace(&mut child_mut.lft, curr_idx);
-                            curr_idx = replace(&mut child_idx, grand_child_idx);
-                            curr_mut = replace(&mut child_mut, unsafe {
-                                self.aliasable_node_mut(grand_child_idx)
-                            });
-                            order = cmp(child_mut.key.borrow());
-                        }
-                        *lft_rgtmost_idx = curr_idx;
-                        lft_rgtmost_idx = unsafe { &mut *(&mut curr_mut.rgt as *mut _) };
-                    }
-                    _ => break,
-                }
-                curr_mut = child_mut;
-                curr_idx = child_idx;
+        let empty = other.empty_like();
+        let mut other = mem::replace(other, empty);
+        let (self_root, other_root) = match (self.fresh_root(), other.fresh_root()) {
+            (_, None) => return,
+            (None, _) => {
+                self.swap_nodes(&mut other);
+                return;
             }
-            *lft_rgtmost_idx = replace(&mut curr_mut.lft, NULL_NODE);
-            *rgt_lftmost_idx = replace(&mut curr_mut.rgt, NULL_NODE);
+            (Some(a), Some(b)) => (a, b),
+        };
+        self.root = self.splay_rgtmost(self_root);
+        other.root = other.splay_lftmost(other_root);
+        if self.cmp.compare(&self.root_ref().key, &other.root_ref().key) == Ordering::Less {
+            self.join::<ByKey>(other, true);
+            self.debug_check();
+            return;
         }
-        curr_mut.lft = lft_root_idx;
-        curr_mut.rgt = rgt_root_idx;
-        (curr_idx, order)
+        self.root = self.splay_lftmost(self.root);
+        other.root = other.splay_rgtmost(other.root);
+        if self.cmp.compare(&other.root_ref().key, &self.root_ref().key) == Ordering::Less {
+            self.join::<ByKey>(other, false);
+            self.debug_check();
+            return;
+        }
+        self.merge_with(other, |merged| match merged {
+            Merged::Lft(e) | Merged::Rgt(e) => Some(e),
+            Merged::Both((k, _), (_, v)) => Some((k, v)),
+        });
     }
-    fn non_empty_pop_root(&mut self) -> (K, V) {
-        let new_root = match *self.root_ref() {
-            Node {
-                lft: NULL_NODE,
-                rgt: NULL_NODE,
-                ..
-            } => NULL_NODE,
-            Node {
-                lft,
-                rgt: NULL_NODE,
-                ..
-            } => lft,
-            Node {
-                lft: NULL_NODE,
-                rgt,
-                ..
-            } => rgt,
-            Node { lft, rgt, .. } if self.node_ref(rgt).lft == NULL_NODE => {
-                self.node_mut(rgt).lft = lft;
-                rgt
-            }
-            Node { lft, mut rgt, .. } => {
-                let lft_rgt = mem::replace(&mut self.node_mut(lft).rgt, NULL_NODE);
-                if lft_rgt != NULL_NODE {
-                    rgt = self.splay_lftmost(rgt);
-                    self.node_mut(rgt).lft = lft_rgt;
-                }
-                self.node_mut(lft).rgt = rgt;
-                lft
-            }
+    /// Splays the minimum node which satisfies "greater than or equal to `key`" to the root.
+    ///
+    /// Returns `false` if there is no such node.
+    pub fn splay_lower_bound<Q: ?Sized>(&mut self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        C: Compare<Q>,
+    {
+        self.find_bound_index(|c, k| c.compare(key, k.borrow())).is_some()
+    }
+    /// Splays the successor of the root to the root.
+    ///
+    /// Returns `false` (without changing the root) if the root is the rightmost node.
+    pub fn splay_next(&mut self) -> bool {
+        let root = match self.fresh_root() {
+            None => return false,
+            Some(root) => root,
         };
-        if self.len() as NodeIndex - 1 != self.root {
-            let key = &self.node_ref(self.len() as NodeIndex - 1).key as *const _;
-            let _ = self.splay(new_root, unsafe { &*key });
-            let last = self.nodes.pop().unwrap();
-            mem::replace(self.root_mut(), last).into()
-        } else {
-            self.root = new_root;
-            self.nodes.pop().unwrap().into()
+        self.push(root);
+        let rgt = self.root_ref().rgt;
+        if rgt == I::NULL {
+            return false;
+        }
+        // The successor has no left child after the splaying,
+        // so the old root can be rotated down to there.
+        let next = self.splay_lftmost(rgt);
+        self.node_mut(root).rgt = I::NULL;
+        self.update(root);
+        self.node_mut(next).lft = root;
+        self.update(next);
+        self.root = next;
+        self.debug_check();
+        true
+    }
+    /// Splays the predecessor of the root to the root.
+    ///
+    /// Returns `false` (without changing the root) if the root is the leftmost node.
+    pub fn splay_prev(&mut self) -> bool {
+        let root = match self.fresh_root() {
+            None => return false,
+            Some(root) => root,
+        };
+        self.push(root);
+        let lft = self.root_ref().lft;
+        if lft == I::NULL {
+            return false;
+        }
+        let prev = self.splay_rgtmost(lft);
+        self.node_mut(root).lft = I::NULL;
+        self.update(root);
+        self.node_mut(prev).rgt = root;
+        self.update(prev);
+        self.root = prev;
+        self.debug_check();
+        true
+    }
+    /// Removes the root, and splays its successor to the root.
+    ///
+    /// Returns the removed entry, and `false` if the root was the rightmost node.
+    pub fn pop_root_splay_next(&mut self) -> ((K, V), bool) {
+        let root = self.root;
+        self.push(root);
+        let mut next = self.root_ref().rgt;
+        if next != I::NULL {
+            next = self.splay_lftmost(next);
+            self.root_mut().rgt = next;
+        }
+        let entry = self.non_empty_pop_root();
+        if next == I::NULL {
+            return (entry, false);
         }
+        if next.to_usize() == self.len() {
+            // The successor was the last node of the arena, so it was moved into the hole.
+            next = root;
+        }
+        self.splay_node(next);
+        self.debug_check();
+        (entry, true)
+    }
+    /// Splays the node `i` to the root.
+    fn splay_node(&mut self, i: I) {
+        let root = self.root;
+        let (root, _) = self.splay_by_node(root, |tree, j| ByKey::locate(tree, i, j));
+        debug_assert_eq!(root, i);
+        self.root = root;
+    }
+    fn push_root(&mut self, node: Node<K, V, A, I>) {
+        self.root = self.new_slot();
+        self.nodes.push(node);
+        let root = self.root;
+        self.update(root);
     }
-    fn find_bound<F>(&mut self, cmp: F) -> Option<&K>
+    fn splay<Q: ?Sized>(&mut self, key: &Q) -> Option<(I, Ordering)>
     where
-        F: Fn(&K) -> Ordering,
+        K: Borrow<Q>,
+        C: Compare<Q>,
     {
-        self.root().and_then(move |root| {
-            let (root, order) = self.splay_by(root, cmp);
-            self.root = root;
-            if let Ordering::Greater = order {
-                let mut root_rgt = self.root_ref().rgt;
-                if root_rgt != NULL_NODE {
-                    root_rgt = self.splay_lftmost(root_rgt);
-                    self.root_mut().rgt = root_rgt;
-                    Some(&self.node_ref(root_rgt).key)
-                } else {
-                    None
-                }
-            } else {
-                Some(&self.root_ref().key)
-            }
-        })
+        self.splay_by(SplayPolicy::Full, |c, k| c.compare(key, k.borrow()))
     }
-
-    fn find_bound_below<F>(&mut self, cmp: F) -> Option<&K>
+    /// Searches for the node located by `cmp` from the root,
+    /// and restructures the tree according to `policy`.
+    ///
+    /// Returns the last node on the search path, and the order of the target to it.
+    /// The node is the root afterwards if `policy` is `SplayPolicy::Full`,
+    /// but it can be anywhere on the (shortened) search path otherwise.
+    fn splay_by<F>(&mut self, policy: SplayPolicy, cmp: F) -> Option<(I, Ordering)>
     where
-        F: Fn(&K) -> Ordering,
+        F: Fn(&C, &K) -> Ordering,
     {
-        self.root().and_then(move |root| {
-            let (root, order) = self.splay_by(root, cmp);
-            self.root = root;
-            if let Ordering::Less = order {
-                let mut root_lft = self.root_ref().lft;
-                if root_lft != NULL_NODE {
-                    root_lft = self.splay_rgtmost(root_lft);
-                    self.root_mut().lft = root_lft;
-                    Some(&self.node_ref(root_lft).key)
-                } else {
-                    None
+        let root = self.fresh_root()?;
+        let full = match policy {
+            SplayPolicy::Full => true,
+            SplayPolicy::Probability(p) => self.draw() < p,
+            _ => false,
+        };
+        if full {
+            let found = self.splay_by_node(root, |tree, i| cmp(&tree.cmp, &tree.node_ref(i).key));
+            self.root = found.0;
+            return Some(found);
+        }
+        let mut path = Vec::new();
+        let found = match policy {
+            SplayPolicy::Semi => {
+                let found = self.descend_by(&cmp, Some(&mut path));
+                self.semi_splay(&mut path);
+                found
+            }
+            SplayPolicy::Threshold(depth) => {
+                let found = self.descend_by(&cmp, Some(&mut path));
+                if path.len() > depth + 1 {
+                    self.splay_path(&path);
                 }
-            } else {
-                Some(&self.root_ref().key)
+                found
             }
-        })
+            _ => self.descend_by(&cmp, None),
+        };
+        Some(found)
     }
-}
-impl<K, V> Tree<K, V> {
-    pub fn root(&self) -> Option<NodeIndex> {
-        if self.nodes.is_empty() {
-            None
-        } else {
-            Some(self.root)
+    /// Searches for the node located by `cmp` from the root without restructuring the tree
+    /// (but the pending updates on the search path are pushed down).
+    ///
+    /// The nodes on the search path are recorded into `path` if it is given.
+    fn descend_by<F>(&mut self, cmp: &F, mut path: Option<&mut Vec<I>>) -> (I, Ordering)
+    where
+        F: Fn(&C, &K) -> Ordering,
+    {
+        let mut curr = self.root;
+        loop {
+            self.push(curr);
+            if let Some(ref mut path) = path {
+                path.push(curr);
+            }
+            let node = self.node_ref(curr);
+            let order = cmp(&self.cmp, &node.key);
+            let next = match order {
+                Ordering::Less => node.lft,
+                Ordering::Greater => node.rgt,
+                Ordering::Equal => I::NULL,
+            };
+            if next == I::NULL {
+                return (curr, order);
+            }
+            curr = next;
         }
     }
-    pub fn root_ref(&self) -> &Node<K, V> {
-        let root = self.root;
-        self.node_ref(root)
-    }
-    pub fn root_mut(&mut self) -> &mut Node<K, V> {
-        let root = self.root;
-        self.node_mut(root)
-    }
-    pub fn node_ref(&self, i: NodeIndex) -> &Node<K, V> {
-        unsafe { self.nodes.get_unchecked(i as usize) }
+    /// Semi-splays the last node of `path` (the search path from the root) bottom-up.
+    fn semi_splay(&mut self, path: &mut Vec<I>) {
+        while path.len() >= 3 {
+            let node = path.pop().unwrap();
+            let parent = path.pop().unwrap();
+            let grand_parent = path.pop().unwrap();
+            let zig_zig =
+                (self.node_ref(parent).lft == node) == (self.node_ref(grand_parent).lft == parent);
+            let top = if zig_zig {
+                self.rotate(parent, grand_parent);
+                parent
+            } else {
+                self.rotate(node, parent);
+                self.replace_child(grand_parent, parent, node);
+                self.rotate(node, grand_parent);
+                node
+            };
+            let above = path.last().cloned().unwrap_or(I::NULL);
+            self.replace_child(above, grand_parent, top);
+            path.push(top);
+        }
+        if path.len() == 2 {
+            self.rotate(path[1], path[0]);
+            self.root = path[1];
+        }
     }
-    pub fn node_mut(&mut self, i: NodeIndex) -> &mut Node<K, V> {
-        unsafe { self.nodes.get_unchecked_mut(i as usize) }
+    /// Splays the last node of `path` (the search path from the root) bottom-up to the root.
+    fn splay_path(&mut self, path: &[I]) {
+        let node = path[path.len() - 1];
+        let mut above = path.len() - 1;
+        while above >= 2 {
+            let parent = path[above - 1];
+            let grand_parent = path[above - 2];
+            if (self.node_ref(parent).lft == node) == (self.node_ref(grand_parent).lft == parent) {
+                self.rotate(parent, grand_parent);
+                self.rotate(node, parent);
+            } else {
+                self.rotate(node, parent);
+                self.replace_child(grand_parent, parent, node);
+                self.rotate(node, grand_parent);
+            }
+            above -= 2;
+            if above > 0 {
+                self.replace_child(path[above - 1], grand_parent, node);
+            }
+        }
+        if above == 1 {
+            self.rotate(node, path[0]);
+        }
+        self.root = node;
     }
-    unsafe fn aliasable_node_mut<'a, 'b>(&'a mut self, i: NodeIndex) -> &'b mut Node<K, V> {
-        &mut *(self.node_mut(i) as *mut _)
+    /// Rotates the node `child` up over its parent `parent`.
+    ///
+    /// The link to `parent` from its own parent is left as is.
+    fn rotate(&mut self, child: I, parent: I) {
+        if self.node_ref(parent).lft == child {
+            let inner = mem::replace(&mut self.node_mut(child).rgt, parent);
+            self.node_mut(parent).lft = inner;
+        } else {
+            let inner = mem::replace(&mut self.node_mut(child).lft, parent);
+            self.node_mut(parent).rgt = inner;
+        }
+        self.update(parent);
+        self.update(child);
     }
-    pub fn len(&self) -> usize {
-        self.nodes.len()
+    /// Replaces the link to `old` from `parent` (or `self.root` if it is null) with `new`.
+    fn replace_child(&mut self, parent: I, old: I, new: I) {
+        if parent == I::NULL {
+            self.root = new;
+        } else if self.node_ref(parent).lft == old {
+            self.node_mut(parent).lft = new;
+        } else {
+            self.node_mut(parent).rgt = new;
+        }
     }
-    #[allow(dead_code)]
-    pub fn capacity(&self) -> usize {
-        self.nodes.capacity()
+    /// Draws a number in the range `[0, 1)` for `SplayPolicy::Probability`.
+    fn draw(&mut self) -> f64 {
+        self.seed ^= self.seed << 13;
+        self.seed ^= self.seed >> 7;
+        self.seed ^= self.seed << 17;
+        (self.seed >> 11) as f64 / (1u64 << 53) as f64
     }
-    pub fn iter(&self) -> iter::Iter<K, V> {
-        iter::InOrderIter::new(self.root(), &self.nodes)
+    fn non_empty_pop_root(&mut self) -> (K, V) {
+        let entry = self.pop_root_with::<ByKey>();
+        self.debug_check();
+        entry
     }
-    pub fn iter_mut(&mut self) -> iter::IterMut<K, V> {
-        iter::InOrderIter::new(self.root(), &mut self.nodes)
+    /// Splays the minimum node which `cmp` does not regard as `Ordering::Greater` to the root,
+    /// and returns it.
+    ///
+    /// Since the node is the root, its value can be mutated without staling the augmentations.
+    fn find_bound<F>(&mut self, cmp: F) -> Option<&mut Node<K, V, A, I>>
+    where
+        F: Fn(&C, &K) -> Ordering,
+    {
+        self.find_bound_index(cmp).map(move |i| self.node_mut(i))
     }
-    pub fn into_iter(self) -> iter::IntoIter<K, V> {
-        iter::InOrderIter::new(self.root(), iter::OwnedNodes(self.nodes))
+    fn find_bound_index<F>(&mut self, cmp: F) -> Option<I>
+    where
+        F: Fn(&C, &K) -> Ordering,
+    {
+        let (_, order) = self.splay_by(SplayPolicy::Full, cmp)?;
+        self.debug_check();
+        if order == Ordering::Greater && !self.splay_next() {
+            None
+        } else {
+            Some(self.root)
+        }
     }
-    pub fn nodes_iter(&self) -> slice::Iter<Node<K, V>> {
-        self.nodes.iter()
+
+    /// Locates the first and the last nodes contained in `range`.
+    ///
+    /// The first node is splayed to the root
+    /// and the last node is splayed into the right subtree of the first one,
+    /// so the result can be passed to `iter::InOrderIter::range` as is.
+    pub fn range<Q: ?Sized, R>(&mut self, range: R) -> Option<(I, I)>
+    where
+        K: Borrow<Q>,
+        C: Compare<Q>,
+        R: RangeBounds<Q>,
+    {
+        check_range(&self.cmp, &range);
+        let start = range.start_bound();
+        let first = self.find_bound_index(|c, k| start_order(c, start, k.borrow()))?;
+        let end = range.end_bound();
+        let first_rgt = self.node_ref(first).rgt;
+        if first_rgt != I::NULL {
+            let (rgt, order) = self.splay_by_node(first_rgt, |tree, i| {
+                end_order(&tree.cmp, end, tree.node_ref(i).key.borrow())
+            });
+            self.node_mut(first).rgt = rgt;
+            self.debug_check();
+            if order != Ordering::Less {
+                return Some((first, rgt));
+            }
+            let rgt_lft = self.node_ref(rgt).lft;
+            if rgt_lft != I::NULL {
+                let rgt_lft = self.splay_rgtmost(rgt_lft);
+                self.node_mut(rgt).lft = rgt_lft;
+                self.debug_check();
+                return Some((first, rgt_lft));
+            }
+        }
+        if end_order(&self.cmp, end, self.node_ref(first).key.borrow()) != Ordering::Less {
+            Some((first, first))
+        } else {
+            None
+        }
     }
-    pub fn nodes_iter_mut(&mut self) -> slice::IterMut<Node<K, V>> {
-        self.nodes.iter_mut()
+
+    /// Splays the maximum node which `cmp` does not regard as `Ordering::Less` to the root,
+    /// and returns it.
+    fn find_bound_below<F>(&mut self, cmp: F) -> Option<&mut Node<K, V, A, I>>
+    where
+        F: Fn(&C, &K) -> Ordering,
+    {
+        let (_, order) = self.splay_by(SplayPolicy::Full, cmp)?;
+        self.debug_check();
+        if order == Ordering::Less && !self.splay_prev() {
+            None
+        } else {
+            Some(self.root_mut())
+        }
     }
 }
-impl<K, V> hash::Hash for Tree<K, V>
+impl<K, V, A, C, I> Tree<K, V, A, C, I>
 where
-    K: hash::Hash,
-    V: hash::Hash,
+    C: Compare<K>,
+    A: Counted<K, V>,
+    I: Index,
 {
-    fn hash<H>(&self, state: &mut H)
+    /// Returns the number of entries whose keys are less than `key`,
+    /// if the tree contains `key`.
+    pub fn rank<Q: ?Sized>(&mut self, key: &Q) -> Option<usize>
     where
-        H: hash::Hasher,
+        K: Borrow<Q>,
+        C: Compare<Q>,
     {
-        for e in self.iter() {
-            e.hash(state);
+        if self.contains_key(key) {
+            Some(self.count(self.root_ref().lft))
+        } else {
+            None
         }
     }
-}
-impl<K, V> PartialEq for Tree<K, V>
-where
-    K: PartialEq,
-    V: PartialEq,
-{
-    fn eq(&self, other: &Self) -> bool {
-        self.len() == other.len() && self.iter().zip(other.iter()).all(|(a, b)| a.eq(&b))
+
+    /// Returns the number of entries contained in `range`.
+    pub fn count_range<Q: ?Sized, R>(&mut self, range: R) -> usize
+    where
+        K: Borrow<Q>,
+        C: Compare<Q>,
+        R: RangeBounds<Q>,
+    {
+        check_range(&self.cmp, &range);
+        let start = range.start_bound();
+        let end = range.end_bound();
+        let below_start = self.count_below(|c, k| start_order(c, start, k.borrow()));
+        let below_end = self.count_below(|c, k| match end_order(c, end, k.borrow()) {
+            Ordering::Less => Ordering::Less,
+            _ => Ordering::Greater,
+        });
+        below_end - below_start
+    }
+
+    /// Returns the number of entries which `cmp` regards as `Ordering::Greater`.
+    fn count_below<F>(&mut self, cmp: F) -> usize
+    where
+        F: Fn(&C, &K) -> Ordering,
+    {
+        self.splay_by(SplayPolicy::Full, cmp).map_or(0, |(_, order)| {
+            self.debug_check();
+            let below = self.count(self.root_ref().lft);
+            if order == Ordering::Greater {
+                below + 1
+            } else {
+                below
+            }
+        })
     }
 }
-impl<K, V> Eq for Tree<K, V>
+impl<K, V, A, C, I> Tree<K, V, A, C, I>
 where
-    K: Eq,
-    V: Eq,
+    A: Counted<K, V>,
+    I: Index,
 {
+    /// Splays the `index`-th smallest entry to the root.
+    pub fn select(&mut self, index: usize) -> Option<I> {
+        let root = match self.fresh_root() {
+            Some(root) if index < self.len() => root,
+            _ => return None,
+        };
+        self.root = self.splay_nth(root, index);
+        self.debug_check_links();
+        Some(self.root)
+    }
+
+    /// Splays the `index`-th smallest entry of the subtree rooted at `root`.
+    fn splay_nth(&mut self, root: I, index: usize) -> I {
+        let mut index = index;
+        self.splay_by_node(root, |tree, i| {
+            let lft_count = tree.count(tree.node_ref(i).lft);
+            if index < lft_count {
+                Ordering::Less
+            } else if index == lft_count {
+                Ordering::Equal
+            } else {
+                index -= lft_count + 1;
+                Ordering::Greater
+            }
+        }).0
+    }
+
+    /// Finds the `index`-th smallest entry without restructuring the tree.
+    pub fn select_ref(&self, index: usize) -> Option<&Node<K, V, A, I>> {
+        let mut index = index;
+        let mut curr = self.root()?;
+        while curr != I::NULL {
+            let node = self.node_ref(curr);
+            let lft_count = self.count(node.lft);
+            if index < lft_count {
+                curr = node.lft;
+            } else if index == lft_count {
+                return Some(node);
+            } else {
+                index -= lft_count + 1;
+                curr = node.rgt;
+            }
+        }
+        None
+    }
+
+    fn count(&self, i: I) -> usize {
+        if i == I::NULL {
+            0
+        } else {
+            self.node_ref(i).aug.count()
+        }
+    }
 }
-impl<K, V> PartialOrd for Tree<K, V>
+impl<K, V, A, C, I> Tree<K, V, A, C, I>
 where
-    K: PartialOrd,
-    V: PartialOrd,
+    C: Compare<K>,
+    A: Folded<K, V>,
+    I: Index,
 {
-    fn partial_cmp(&self, other: &Self) -> Option<cmp::Ordering> {
-        let mut i0 = self.iter();
-        let mut i1 = other.iter();
-        loop {
-            match (i0.next(), i1.next()) {
-                (None, None) => return Some(cmp::Ordering::Equal),
-                (None, _) => return Some(cmp::Ordering::Less),
-                (_, None) => return Some(cmp::Ordering::Greater),
-                (Some(e0), Some(e1)) => match e0.partial_cmp(&e1) {
-                    Some(cmp::Ordering::Equal) => {}
-                    not_equal => return not_equal,
-                },
+    /// Folds the entries contained in `range`.
+    pub fn fold_range<Q: ?Sized, R>(&mut self, range: R) -> A::Monoid
+    where
+        K: Borrow<Q>,
+        C: Compare<Q>,
+        R: RangeBounds<Q>,
+    {
+        let (first, last) = match self.range(range) {
+            None => return A::Monoid::identity(),
+            Some(bounds) => bounds,
+        };
+        let mut acc = self.lift(first);
+        if first != last {
+            // All of the nodes between `first` and `last` are in the left subtree of `first.rgt`.
+            let rgt = self.node_ref(first).rgt;
+            let rgt_lft = self.node_ref(rgt).lft;
+            if rgt_lft != I::NULL {
+                acc = acc.combine(self.node_ref(rgt_lft).aug.folded());
+            }
+            if last == rgt {
+                acc = acc.combine(&self.lift(rgt));
             }
         }
+        acc
+    }
+
+    fn lift(&self, i: I) -> A::Monoid {
+        let node = self.node_ref(i);
+        A::Monoid::lift(&node.key, &node.val)
     }
 }
-impl<K, V> Ord for Tree<K, V>
+impl<K, V, A, C, I> Tree<K, V, A, C, I>
 where
-    K: Ord,
-    V: Ord,
+    C: Compare<K>,
+    A: Tagged<K, V>,
+    I: Index,
 {
-    fn cmp(&self, other: &Self) -> cmp::Ordering {
-        let mut i0 = self.iter();
-        let mut i1 = other.iter();
+    /// Applies `tag` to the entries contained in `range`.
+    ///
+    /// The nodes between the first and the last ones form a subtree,
+    /// so the update of them is postponed.
+    pub fn update_range<Q: ?Sized, R>(&mut self, range: R, tag: &A::Tag)
+    where
+        K: Borrow<Q>,
+        C: Compare<Q>,
+        R: RangeBounds<Q>,
+    {
+        let (first, last) = match self.range(range) {
+            None => return,
+            Some(bounds) => bounds,
+        };
+        if first != last {
+            let rgt = self.node_ref(first).rgt;
+            let rgt_lft = self.node_ref(rgt).lft;
+            if rgt_lft != I::NULL {
+                self.tag(rgt_lft, tag);
+            }
+            if last == rgt {
+                self.apply(rgt, tag);
+            } else {
+                self.update(rgt);
+            }
+        }
+        self.apply(first, tag);
+        let root = self.root;
+        if root != first {
+            self.update(root);
+        }
+        self.debug_check();
+    }
+}
+impl<V, A, I> Tree<I, V, A, Natural, I>
+where
+    A: Positional<V, I>,
+    I: Index,
+{
+    /// Finds the value at `index` without restructuring the tree.
+    ///
+    /// The pending reversals on the way are taken into account instead of being pushed down.
+    pub fn nth_ref(&self, index: usize) -> Option<&Node<I, V, A, I>> {
+        let mut index = index;
+        let mut curr = self.root()?;
+        let mut swapped = false;
+        while curr != I::NULL {
+            let node = self.node_ref(curr);
+            swapped ^= node.aug.is_reversed();
+            let (lft, rgt) = if swapped {
+                (node.rgt, node.lft)
+            } else {
+                (node.lft, node.rgt)
+            };
+            let lft_count = self.count(lft);
+            if index < lft_count {
+                curr = lft;
+            } else if index == lft_count {
+                return Some(node);
+            } else {
+                index -= lft_count + 1;
+                curr = rgt;
+            }
+        }
+        None
+    }
+
+    /// Iterates over the values in order without pushing the pending reversals down.
+    pub fn positional_iter(&self) -> iter::PositionalIter<'_, V, A, I> {
+        let nodes = iter::PositionalNodes(&self.nodes);
+        iter::InOrderIter::new(self.root().map(I::to_usize), nodes)
+    }
+
+    /// Inserts `value` at `index` (or at the end if `index` is out of bounds).
+    pub fn insert_at(&mut self, index: usize, value: V) {
+        let slot = self.new_slot();
+        if index < self.len() {
+            let rgt = self.select(index).expect("Never fails");
+            let lft = mem::replace(&mut self.node_mut(rgt).lft, I::NULL);
+            self.update(rgt);
+            self.push_root(Node::new(slot, value, lft, rgt));
+        } else if let Some(root) = self.fresh_root() {
+            let lft = self.splay_rgtmost(root);
+            self.push_root(Node::new(slot, value, lft, I::NULL));
+        } else {
+            self.push_root(Node::new(slot, value, I::NULL, I::NULL));
+        }
+        self.debug_check_links();
+    }
+
+    /// Removes the value at `index`.
+    pub fn remove_at(&mut self, index: usize) -> Option<V> {
+        let value = self.select(index)
+            .map(|_| self.pop_root_with::<BySlot>().1);
+        self.debug_check_links();
+        value
+    }
+
+    /// Splits the sequence at `index`, and returns the values at and after `index`.
+    pub fn split_off_at(&mut self, index: usize) -> Self {
+        match self.select(index) {
+            None => Tree::new(),
+            Some(rgt) => {
+                let lft = mem::replace(&mut self.node_mut(rgt).lft, I::NULL);
+                self.update(rgt);
+                let rest = self.split::<BySlot>(lft, rgt);
+                self.debug_check_links();
+                rest.debug_check_links();
+                rest
+            }
+        }
+    }
+
+    /// Replaces the values of the tree with `values`, which are linked into a perfectly balanced shape.
+    #[cfg(feature = "serde")]
+    pub fn rebuild_from_values<T>(&mut self, values: T)
+    where
+        T: IntoIterator<Item = V>,
+    {
+        let entries = values
+            .into_iter()
+            .enumerate()
+            .map(|(i, v)| (I::from_usize(i), v));
+        self.rebuild_sorted(entries);
+    }
+
+    /// Moves all values of `other` to the end of `self`.
+    pub fn concat(&mut self, other: &mut Self) {
+        let mut other = mem::replace(other, Tree::new());
+        match (self.fresh_root(), other.fresh_root()) {
+            (_, None) => {}
+            (None, _) => *self = other,
+            _ => self.join::<BySlot>(other, true),
+        }
+        self.debug_check_links();
+    }
+
+    /// Reverses the values at `[start, end)`.
+    pub fn reverse_at(&mut self, start: usize, end: usize) {
+        if let Some((subtree, parent)) = self.isolate(start, end) {
+            self.node_mut(subtree).aug.reverse();
+            self.pending = true;
+            self.update_above(parent);
+        }
+        self.debug_check_links();
+    }
+
+    /// Applies `tag` to the values at `[start, end)`.
+    pub fn update_at(&mut self, start: usize, end: usize, tag: &A::Tag)
+    where
+        A: Tagged<I, V>,
+    {
+        if let Some((subtree, parent)) = self.isolate(start, end) {
+            self.tag(subtree, tag);
+            self.update_above(parent);
+        }
+        self.debug_check_links();
+    }
+
+    /// Gathers the values at `[start, end)` into a subtree,
+    /// and returns the root of it and its parent.
+    ///
+    /// The parent is the root of the tree or the right child of it
+    /// (or `I::NULL` if the subtree is the whole tree).
+    fn isolate(&mut self, start: usize, end: usize) -> Option<(I, I)> {
+        let len = self.len();
+        if start >= end || end > len {
+            return None;
+        }
+        let root = self.fresh_root().expect("Never fails");
+        if start == 0 {
+            if end == len {
+                return Some((root, I::NULL));
+            }
+            self.root = self.splay_nth(root, end);
+            return Some((self.root_ref().lft, self.root));
+        }
+        self.root = self.splay_nth(root, start - 1);
+        let root_rgt = self.root_ref().rgt;
+        if end == len {
+            return Some((root_rgt, self.root));
+        }
+        let rgt = self.splay_nth(root_rgt, end - start);
+        self.root_mut().rgt = rgt;
+        Some((self.node_ref(rgt).lft, rgt))
+    }
+
+    /// Updates the ancestors of a subtree made by `isolate`.
+    fn update_above(&mut self, parent: I) {
+        if parent != I::NULL {
+            self.update(parent);
+            let root = self.root;
+            if parent != root {
+                self.update(root);
+            }
+        }
+    }
+}
+impl<K, V, A, C, I> Tree<K, V, A, C, I>
+where
+    A: Augment<K, V>,
+    I: Index,
+{
+    /// Removes the root, and fills the hole in the arena with the last node of it.
+    fn pop_root_with<L: Locate<K, V, A, C, I>>(&mut self) -> (K, V) {
+        if !L::SLOTTED {
+            // The comparator may panic, so it is not called after the tree is restructured.
+            let last = I::from_usize(self.len() - 1);
+            self.swap_with_root::<L>(last);
+        }
+        let (lft, rgt) = (self.root_ref().lft, self.root_ref().rgt);
+        self.push(lft);
+        self.push(rgt);
+        let new_root = if lft == I::NULL {
+            rgt
+        } else if rgt == I::NULL {
+            lft
+        } else if self.node_ref(rgt).lft == I::NULL {
+            self.node_mut(rgt).lft = lft;
+            self.update(rgt);
+            rgt
+        } else {
+            let mut rgt = rgt;
+            let lft_rgt = mem::replace(&mut self.node_mut(lft).rgt, I::NULL);
+            if lft_rgt != I::NULL {
+                rgt = self.splay_lftmost(rgt);
+                self.node_mut(rgt).lft = lft_rgt;
+                self.update(rgt);
+            }
+            self.node_mut(lft).rgt = rgt;
+            self.update(lft);
+            lft
+        };
+        let hole = mem::replace(&mut self.root, new_root);
+        let last = I::from_usize(self.len() - 1);
+        if last != hole {
+            self.relocate::<L>(last, hole);
+        }
+        self.nodes.pop().unwrap().into()
+    }
+
+    /// Swaps the root and the node `i` in the arena.
+    ///
+    /// `i` is splayed to a child of the root beforehand, so that only the link to it needs to be updated.
+    /// The splaying keeps the tree valid even if `L::locate` panics.
+    fn swap_with_root<L: Locate<K, V, A, C, I>>(&mut self, i: I) {
+        let root = self.root;
+        if i == root {
+            return;
+        }
+        if L::locate(self, i, root) == Ordering::Less {
+            let lft = self.root_ref().lft;
+            let (lft, _) = self.splay_by_node(lft, |tree, j| L::locate(tree, i, j));
+            debug_assert_eq!(lft, i);
+            self.root_mut().lft = lft;
+        } else {
+            let rgt = self.root_ref().rgt;
+            let (rgt, _) = self.splay_by_node(rgt, |tree, j| L::locate(tree, i, j));
+            debug_assert_eq!(rgt, i);
+            self.root_mut().rgt = rgt;
+        }
+        self.nodes.swap(root.to_usize(), i.to_usize());
+        let node = self.node_mut(i);
+        if node.lft == i {
+            node.lft = root;
+        } else {
+            node.rgt = root;
+        }
+        self.root = i;
+    }
+
+    /// Moves the node `from` into the unused slot `to`.
+    ///
+    /// The node is splayed to the root beforehand, so no other links need to be updated.
+    fn relocate<L: Locate<K, V, A, C, I>>(&mut self, from: I, to: I) {
+        let root = self.root;
+        let (root, _) = self.splay_by_node(root, |tree, i| L::locate(tree, from, i));
+        debug_assert_eq!(root, from);
+        self.nodes.swap(from.to_usize(), to.to_usize());
+        self.root = to;
+        L::moved(self.node_mut(to), to);
+        self.update(to);
+    }
+    /// Splits the tree into the two subtrees `lft` and `rgt`, and returns the latter.
+    ///
+    /// Only the smaller one is moved into a new arena.
+    fn split<L: Locate<K, V, A, C, I>>(&mut self, lft: I, rgt: I) -> Self
+    where
+        C: Clone,
+    {
+        let mut limit = 1;
+        loop {
+            if let Some(subtree) = self.preorder(rgt, limit) {
+                self.root = lft;
+                return self.detach::<L>(subtree, true);
+            }
+            if let Some(subtree) = self.preorder(lft, limit) {
+                self.root = rgt;
+                let mut detached = self.detach::<L>(subtree, false);
+                mem::swap(self, &mut detached);
+                return detached;
+            }
+            limit *= 2;
+        }
+    }
+    /// Swaps the nodes of the two trees, but keeps their comparators and policies in place.
+    fn swap_nodes(&mut self, other: &mut Self) {
+        mem::swap(self, other);
+        mem::swap(&mut self.cmp, &mut other.cmp);
+        mem::swap(&mut self.policy, &mut other.policy);
+        mem::swap(&mut self.seed, &mut other.seed);
+    }
+    /// Joins `other` to the right (if `other_is_greater` is `true`) or left side of `self`.
+    ///
+    /// The nodes of the smaller tree are moved into the arena of the larger one.
+    fn join<L: Locate<K, V, A, C, I>>(&mut self, mut other: Self, mut other_is_greater: bool) {
+        if other.len() > self.len() {
+            self.swap_nodes(&mut other);
+            other_is_greater = !other_is_greater;
+        }
+        let offset = self.len();
+        assert!(self.len() + other.len() <= I::NULL.to_usize(), "{}", FULL);
+        self.pending |= other.pending;
+        let shift = |i: I| I::from_usize(i.to_usize() + offset);
+        let other_root = shift(other.root);
+        self.nodes.extend(other.nodes.into_iter().map(|mut n| {
+            if n.lft != I::NULL {
+                n.lft = shift(n.lft);
+            }
+            if n.rgt != I::NULL {
+                n.rgt = shift(n.rgt);
+            }
+            n
+        }));
+        if L::SLOTTED {
+            for i in offset..self.len() {
+                let i = I::from_usize(i);
+                L::moved(self.node_mut(i), i);
+            }
+            self.update_subtree(other_root);
+        }
+        let root = self.root;
+        if other_is_greater {
+            self.root = self.splay_rgtmost(root);
+            self.root_mut().rgt = other_root;
+        } else {
+            self.root = self.splay_lftmost(root);
+            self.root_mut().lft = other_root;
+        }
+        let root = self.root;
+        self.update(root);
+    }
+
+    /// Moves the nodes of `subtree` (which is made by `preorder`) into a new tree.
+    ///
+    /// `self.root` must be the root of the rest of the nodes.
+    /// The holes left in the arena are filled by the nodes at the end of it.
+    fn detach<L: Locate<K, V, A, C, I>>(
+        &mut self,
+        subtree: Vec<(I, I, I)>,
+        greater: bool,
+    ) -> Self
+    where
+        C: Clone,
+    {
+        // Detached nodes are marked by the links to themselves.
+        for &(i, _, _) in &subtree {
+            let node = self.node_mut(i);
+            node.lft = i;
+            node.rgt = i;
+        }
+
+        let new_len = self.len() - subtree.len();
+        let subtree = {
+            let mut detaching = Detaching {
+                tree: self,
+                subtree,
+                greater,
+                done: false,
+            };
+            let mut last = detaching.tree.len();
+            for k in 0..detaching.subtree.len() {
+                let hole = detaching.subtree[k].0;
+                if hole.to_usize() >= new_len {
+                    continue;
+                }
+                // Skips the detached nodes at the end of the arena.
+                let from = loop {
+                    last -= 1;
+                    let i = I::from_usize(last);
+                    if detaching.tree.node_ref(i).lft != i {
+                        break i;
+                    }
+                };
+                detaching.tree.relocate::<L>(from, hole);
+                detaching.subtree[k].0 = from;
+            }
+            detaching.done = true;
+            mem::take(&mut detaching.subtree)
+        };
+
+        // Rearranges the detached nodes in preorder.
+        let mut nodes = self.nodes.split_off(new_len);
+        let mut positions = vec![0; nodes.len()];
+        for (i, &(old, _, _)) in subtree.iter().enumerate() {
+            positions[old.to_usize() - new_len] = i;
+        }
+        for i in 0..nodes.len() {
+            while positions[i] != i {
+                let j = positions[i];
+                nodes.swap(i, j);
+                positions.swap(i, j);
+            }
+        }
+        for (i, (node, &(_, lft, rgt))) in nodes.iter_mut().zip(&subtree).enumerate() {
+            node.lft = lft;
+            node.rgt = rgt;
+            L::moved(node, I::from_usize(i));
+        }
+        let mut tree = Tree {
+            root: I::from_usize(0),
+            nodes,
+            cmp: self.cmp.clone(),
+            stale: false,
+            pending: self.pending,
+            policy: self.policy,
+            seed: SEED,
+        };
+        if L::SLOTTED {
+            tree.update_all();
+        }
+        tree
+    }
+    /// Returns the root, after bringing the augmentations up to date.
+    ///
+    /// Note that the root is the only node whose value can be mutably borrowed other than
+    /// through the iterators, and it is updated anyway when the tree is splayed from the root.
+    fn fresh_root(&mut self) -> Option<I> {
+        if mem::replace(&mut self.stale, false) && !A::STATELESS {
+            self.update_all();
+        }
+        self.root()
+    }
+
+    fn update_all(&mut self) {
+        if let Some(root) = self.root() {
+            self.update_subtree(root);
+        }
+        self.pending = false;
+    }
+
+    /// Replaces the link to the node `old` (from its parent or `self.root`) with `new`.
+    ///
+    /// This is only used on unwinding, so the parent is searched for from the root.
+    fn replace_link(&mut self, old: I, new: I) {
+        if self.root == old {
+            self.root = new;
+            return;
+        }
+        let mut stack = self.root().into_iter().collect::<Vec<_>>();
+        while let Some(i) = stack.pop() {
+            let node = self.node_mut(i);
+            if node.lft == old {
+                node.lft = new;
+                return;
+            }
+            if node.rgt == old {
+                node.rgt = new;
+                return;
+            }
+            stack.extend(node.lft().into_iter().chain(node.rgt()));
+        }
+    }
+
+    /// Pushes all of the pending updates down to the leaves.
+    pub fn flush(&mut self) {
+        if mem::replace(&mut self.pending, false) {
+            if let Some(root) = self.root() {
+                self.push_subtree(root);
+            }
+        }
+        self.debug_check_links();
+    }
+
+    /// Pushes the pending updates in the subtree rooted at `root` down to the leaves.
+    fn push_subtree(&mut self, root: I) {
+        if !A::LAZY {
+            return;
+        }
+        let mut stack = vec![root];
+        while let Some(i) = stack.pop() {
+            self.push(i);
+            let (lft, rgt) = (self.node_ref(i).lft, self.node_ref(i).rgt);
+            if lft != I::NULL {
+                stack.push(lft);
+            }
+            if rgt != I::NULL {
+                stack.push(rgt);
+            }
+        }
+    }
+
+    /// Pushes the pending updates in the range made by `range` down to the leaves.
+    fn push_range(&mut self, bounds: Option<(I, I)>) {
+        if !self.pending {
+            return;
+        }
+        if let Some((first, last)) = bounds {
+            if first != last {
+                // `first` and `first.rgt` are already pushed by splaying.
+                let rgt = self.node_ref(first).rgt;
+                let rgt_lft = self.node_ref(rgt).lft;
+                if rgt_lft != I::NULL {
+                    self.push_subtree(rgt_lft);
+                }
+            }
+        }
+    }
+
+    pub fn iter_mut(&mut self) -> iter::IterMut<K, V, A, I> {
+        self.flush();
+        self.stale = true;
+        iter::InOrderIter::new(self.root().map(I::to_usize), &mut self.nodes)
+    }
+    pub fn iter_range(&mut self, bounds: Option<(I, I)>) -> iter::Iter<'_, K, V, A, I> {
+        self.push_range(bounds);
+        match bounds {
+            Some((first, last)) => iter::InOrderIter::range(first.to_usize(), last.to_usize(), &self.nodes),
+            None => iter::InOrderIter::empty(&self.nodes),
+        }
+    }
+    pub fn iter_range_mut(
+        &mut self,
+        bounds: Option<(I, I)>,
+    ) -> iter::IterMut<'_, K, V, A, I> {
+        self.push_range(bounds);
+        self.stale = true;
+        match bounds {
+            Some((first, last)) => iter::InOrderIter::range(first.to_usize(), last.to_usize(), &mut self.nodes),
+            None => iter::InOrderIter::empty(&mut self.nodes),
+        }
+    }
+    pub fn into_iter(mut self) -> iter::IntoIter<K, V, A, I> {
+        self.flush();
+        iter::InOrderIter::new(self.root().map(I::to_usize), iter::OwnedNodes::new(self.nodes))
+    }
+    /// Moves all of the entries out of the tree, leaving it empty.
+    pub fn drain(&mut self) -> iter::IntoIter<K, V, A, I> {
+        self.flush();
+        let root = self.root();
+        let nodes = mem::take(&mut self.nodes);
+        self.clear();
+        iter::InOrderIter::new(root.map(I::to_usize), iter::OwnedNodes::new(nodes))
+    }
+    /// Starts removing the entries selected by `Extract::next_by`, in ascending order.
+    pub fn extract(&mut self) -> Extract<'_, K, V, A, C, I> {
+        let entries = self.drain();
+        Extract {
+            tree: self,
+            entries,
+            kept: Vec::new(),
+        }
+    }
+    /// Replaces the entries of the tree with `entries` (sorted in ascending order),
+    /// which are linked into a perfectly balanced shape.
+    pub fn rebuild_sorted<T>(&mut self, entries: T)
+    where
+        T: IntoIterator<Item = (K, V)>,
+    {
+        // The nodes are collected beforehand, so the tree is left empty if `entries` panics.
+        self.clear();
+        let nodes = entries
+            .into_iter()
+            .map(|(k, v)| Node::new(k, v, I::NULL, I::NULL))
+            .collect::<Vec<_>>();
+        assert!(nodes.len() <= I::NULL.to_usize(), "{}", FULL);
+        self.nodes = nodes;
+        let len = self.len();
+        self.root = self.link_balanced(0, len);
+        self.debug_check_links();
+    }
+    /// Links the nodes in the arena slots `lo..hi` into a balanced subtree, and returns its root.
+    fn link_balanced(&mut self, lo: usize, hi: usize) -> I {
+        if lo == hi {
+            return I::NULL;
+        }
+        let mid = lo + (hi - lo) / 2;
+        let lft = self.link_balanced(lo, mid);
+        let rgt = self.link_balanced(mid + 1, hi);
+        let mid = I::from_usize(mid);
+        self.node_mut(mid).lft = lft;
+        self.node_mut(mid).rgt = rgt;
+        self.update(mid);
+        mid
+    }
+    pub fn nodes_iter_mut(&mut self) -> slice::IterMut<Node<K, V, A, I>> {
+        self.flush();
+        self.stale = true;
+        self.nodes.iter_mut()
+    }
+
+    /// Recomputes the augmentations of the subtree rooted at `root` in post-order.
+    fn update_subtree(&mut self, root: I) {
+        let mut stack = vec![(root, false)];
+        while let Some((i, visited)) = stack.pop() {
+            if visited {
+                self.update(i);
+                continue;
+            }
+            stack.push((i, true));
+            self.push(i);
+            let (lft, rgt) = (self.node_ref(i).lft, self.node_ref(i).rgt);
+            if lft != I::NULL {
+                stack.push((lft, false));
+            }
+            if rgt != I::NULL {
+                stack.push((rgt, false));
+            }
+        }
+    }
+
+    fn splay_lftmost(&mut self, root: I) -> I {
+        self.splay_by_node(root, |_, _| Ordering::Less).0
+    }
+    fn splay_rgtmost(&mut self, root: I) -> I {
+        self.splay_by_node(root, |_, _| Ordering::Greater).0
+    }
+
+    /// Top-down splaying.
+    ///
+    /// `cmp` is called exactly once for each node on the search path (in order),
+    /// so it may keep track of the position like order statistic lookups do.
+    ///
+    /// If `cmp` panics, the tree is reassembled (with the last visited node as the root of `root`)
+    /// while unwinding, so it is left valid.
+    fn splay_by_node<F>(&mut self, root: I, cmp: F) -> (I, Ordering)
+    where
+        F: FnMut(&Self, I) -> Ordering,
+    {
+        let mut splay = Splay {
+            tree: self,
+            top: root,
+            curr: root,
+            lft_root: I::NULL,
+            lft_rgtmost: I::NULL,
+            rgt_root: I::NULL,
+            rgt_lftmost: I::NULL,
+            done: false,
+        };
+        let order = splay.run(cmp);
+        splay.done = true;
+        (splay.curr, order)
+    }
+
+    /// Updates the nodes on the right spine from `bottom` up to `top`.
+    ///
+    /// The links are temporarily reversed while walking down,
+    /// so that the spine can be walked up again without extra memory.
+    fn update_rgt_spine(&mut self, top: I, bottom: I) {
+        if A::STATELESS {
+            return;
+        }
+        let mut parent = I::NULL;
+        let mut curr = top;
+        while curr != bottom {
+            let next = mem::replace(&mut self.node_mut(curr).rgt, parent);
+            parent = mem::replace(&mut curr, next);
+        }
+        self.update(bottom);
+        while parent != I::NULL {
+            let next = mem::replace(&mut self.node_mut(parent).rgt, curr);
+            curr = mem::replace(&mut parent, next);
+            self.update(curr);
+        }
+    }
+
+    /// Updates the nodes on the left spine from `bottom` up to `top`.
+    fn update_lft_spine(&mut self, top: I, bottom: I) {
+        if A::STATELESS {
+            return;
+        }
+        let mut parent = I::NULL;
+        let mut curr = top;
+        while curr != bottom {
+            let next = mem::replace(&mut self.node_mut(curr).lft, parent);
+            parent = mem::replace(&mut curr, next);
+        }
+        self.update(bottom);
+        while parent != I::NULL {
+            let next = mem::replace(&mut self.node_mut(parent).lft, curr);
+            curr = mem::replace(&mut parent, next);
+            self.update(curr);
+        }
+    }
+
+    /// Pushes the pending update of the node `i` down to its children.
+    ///
+    /// This has to be done before the children of `i` are visited.
+    fn push(&mut self, i: I) {
+        if !A::LAZY || i == I::NULL {
+            return;
+        }
+        let nodes = self.nodes.as_mut_ptr();
+        unsafe {
+            // The children are always different nodes from `i`.
+            let node = &mut *nodes.add(i.to_usize());
+            let lft = if node.lft != I::NULL {
+                let child = &mut *nodes.add(node.lft.to_usize());
+                Some((&child.key, &mut child.val, &mut child.aug))
+            } else {
+                None
+            };
+            let rgt = if node.rgt != I::NULL {
+                let child = &mut *nodes.add(node.rgt.to_usize());
+                Some((&child.key, &mut child.val, &mut child.aug))
+            } else {
+                None
+            };
+            if node.aug.push_down(lft, rgt) {
+                mem::swap(&mut node.lft, &mut node.rgt);
+            }
+        }
+    }
+
+    /// Applies `tag` to the subtree rooted at `i`.
+    fn tag(&mut self, i: I, tag: &A::Tag)
+    where
+        A: Tagged<K, V>,
+    {
+        let node = self.node_mut(i);
+        node.aug.tag(&node.key, &mut node.val, tag);
+        self.pending = true;
+    }
+
+    /// Applies `tag` to the node `i` alone.
+    fn apply(&mut self, i: I, tag: &A::Tag)
+    where
+        A: Tagged<K, V>,
+    {
+        {
+            let node = self.node_mut(i);
+            A::apply(tag, &node.key, &mut node.val);
+        }
+        self.update(i);
+    }
+
+    /// Recomputes the augmentation of the node `i` from its children.
+    fn update(&mut self, i: I) {
+        if A::STATELESS {
+            return;
+        }
+        let nodes = self.nodes.as_mut_ptr();
+        unsafe {
+            // The children are always different nodes from `i`.
+            let node = &mut *nodes.add(i.to_usize());
+            let lft = if node.lft != I::NULL {
+                Some(&(*nodes.add(node.lft.to_usize())).aug)
+            } else {
+                None
+            };
+            let rgt = if node.rgt != I::NULL {
+                Some(&(*nodes.add(node.rgt.to_usize())).aug)
+            } else {
+                None
+            };
+            node.aug.update(&node.key, &node.val, lft, rgt);
+        }
+    }
+}
+/// Non-splaying lookups, which descend from the root without restructuring the tree.
+///
+/// Only the keys are guaranteed to be up to date,
+/// so the callers need `A: Eager` before exposing the values.
+impl<K, V, A, C, I> Tree<K, V, A, C, I>
+where
+    C: Compare<K>,
+    I: Index,
+{
+    pub fn find_ref<Q: ?Sized>(&self, key: &Q) -> Option<&Node<K, V, A, I>>
+    where
+        K: Borrow<Q>,
+        C: Compare<Q>,
+    {
+        self.lower_bound_ref(key)
+            .filter(|node| self.cmp.compare(key, node.key.borrow()) == Ordering::Equal)
+    }
+    pub fn lower_bound_ref<Q: ?Sized>(&self, key: &Q) -> Option<&Node<K, V, A, I>>
+    where
+        K: Borrow<Q>,
+        C: Compare<Q>,
+    {
+        self.descend_bound(|c, k| c.compare(key, k.borrow()))
+    }
+    pub fn upper_bound_ref<Q: ?Sized>(&self, key: &Q) -> Option<&Node<K, V, A, I>>
+    where
+        K: Borrow<Q>,
+        C: Compare<Q>,
+    {
+        self.descend_bound(|c, k| match c.compare(key, k.borrow()) {
+            Ordering::Equal => Ordering::Greater,
+            other => other,
+        })
+    }
+
+    /// Returns the leftmost node for which `cmp` does not return `Greater`.
+    fn descend_bound<F>(&self, cmp: F) -> Option<&Node<K, V, A, I>>
+    where
+        F: Fn(&C, &K) -> Ordering,
+    {
+        let mut bound = None;
+        let mut curr = self.root().unwrap_or(I::NULL);
+        while curr != I::NULL {
+            let node = self.node_ref(curr);
+            if cmp(&self.cmp, &node.key) == Ordering::Greater {
+                curr = node.rgt;
+            } else {
+                bound = Some(node);
+                curr = node.lft;
+            }
+        }
+        bound
+    }
+}
+impl<K, V, A, C, I> Tree<K, V, A, C, I>
+where
+    I: Index,
+{
+    pub fn new() -> Self
+    where
+        C: Default,
+    {
+        Tree::with_comparator(C::default())
+    }
+    pub fn with_comparator(cmp: C) -> Self {
+        Tree {
+            root: I::from_usize(0),
+            nodes: Vec::new(),
+            cmp,
+            stale: false,
+            pending: false,
+            policy: SplayPolicy::Full,
+            seed: SEED,
+        }
+    }
+    /// Makes a new empty tree which has the same comparator and policy as `self`.
+    pub fn empty_like(&self) -> Self
+    where
+        C: Clone,
+    {
+        let mut tree = Tree::with_comparator(self.cmp.clone());
+        tree.policy = self.policy;
+        tree
+    }
+    pub fn clear(&mut self) {
+        self.root = I::from_usize(0);
+        self.nodes = Vec::new();
+        self.stale = false;
+        self.pending = false;
+    }
+    pub fn comparator(&self) -> &C {
+        &self.cmp
+    }
+    pub fn policy(&self) -> SplayPolicy {
+        self.policy
+    }
+    pub fn set_policy(&mut self, policy: SplayPolicy) {
+        if let SplayPolicy::Probability(p) = policy {
+            assert!((0.0..=1.0).contains(&p), "The probability must be in the range from 0 to 1");
+        }
+        self.policy = policy;
+    }
+    pub fn root(&self) -> Option<I> {
+        if self.nodes.is_empty() {
+            None
+        } else {
+            Some(self.root)
+        }
+    }
+    pub fn root_ref(&self) -> &Node<K, V, A, I> {
+        let root = self.root;
+        self.node_ref(root)
+    }
+    pub fn root_mut(&mut self) -> &mut Node<K, V, A, I> {
+        let root = self.root;
+        self.node_mut(root)
+    }
+    pub fn node_ref(&self, i: I) -> &Node<K, V, A, I> {
+        unsafe { self.nodes.get_unchecked(i.to_usize()) }
+    }
+    pub fn node_mut(&mut self, i: I) -> &mut Node<K, V, A, I> {
+        unsafe { self.nodes.get_unchecked_mut(i.to_usize()) }
+    }
+    pub fn lftmost_ref(&self) -> Option<&Node<K, V, A, I>> {
+        self.root().map(|mut curr| {
+            while let Some(lft) = self.node_ref(curr).lft() {
+                curr = lft;
+            }
+            self.node_ref(curr)
+        })
+    }
+    pub fn rgtmost_ref(&self) -> Option<&Node<K, V, A, I>> {
+        self.root().map(|mut curr| {
+            while let Some(rgt) = self.node_ref(curr).rgt() {
+                curr = rgt;
+            }
+            self.node_ref(curr)
+        })
+    }
+    pub fn mark_stale(&mut self) {
+        self.stale = true;
+    }
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+    /// Checks that the keys are in strictly ascending order in addition to `check_links`.
+    pub fn check_invariants(&self) -> Result<(), InvariantError>
+    where
+        C: Compare<K>,
+    {
+        self.check_with(|a, b| self.cmp.compare(a, b) == Ordering::Less)
+    }
+    /// Checks that the root and the links point to the nodes in the arena,
+    /// and that every node is reachable from the root exactly once.
+    pub fn check_links(&self) -> Result<(), InvariantError> {
+        self.check_with(|_, _| true)
+    }
+    /// Traverses the tree in order, and calls `ordered` for each pair of adjacent keys.
+    ///
+    /// Each node is marked before descending into its children,
+    /// so the traversal terminates even if the links form a cycle.
+    fn check_with<F>(&self, mut ordered: F) -> Result<(), InvariantError>
+    where
+        F: FnMut(&K, &K) -> bool,
+    {
+        let len = self.len();
+        let mut visited = vec![false; len];
+        let mut stack = Vec::new();
+        let mut prev: Option<usize> = None;
+        let mut reachable = 0;
+        let mut curr = self.root().unwrap_or(I::NULL);
+        loop {
+            while curr != I::NULL {
+                let i = curr.to_usize();
+                if i >= len {
+                    return Err(InvariantError::LinkOutOfBounds { link: i, len });
+                }
+                if mem::replace(&mut visited[i], true) {
+                    return Err(InvariantError::Revisited { node: i });
+                }
+                stack.push(i);
+                curr = self.nodes[i].lft;
+            }
+            let i = match stack.pop() {
+                None => break,
+                Some(i) => i,
+            };
+            if let Some(prev) = prev {
+                if !ordered(&self.nodes[prev].key, &self.nodes[i].key) {
+                    return Err(InvariantError::Unordered { prev, node: i });
+                }
+            }
+            prev = Some(i);
+            reachable += 1;
+            curr = self.nodes[i].rgt;
+        }
+        if reachable != len {
+            return Err(InvariantError::Unreachable {
+                reachable,
+                len,
+            });
+        }
+        Ok(())
+    }
+    /// Runs `check_invariants` if the `check-invariants` feature is enabled (in debug builds),
+    /// and panics if the tree is corrupted.
+    #[inline]
+    fn debug_check(&self)
+    where
+        C: Compare<K>,
+    {
+        #[cfg(all(feature = "check-invariants", debug_assertions))]
+        {
+            if let Err(e) = self.check_invariants() {
+                panic!("{}", e);
+            }
+        }
+    }
+    /// Same as `debug_check`, but only runs `check_links`.
+    ///
+    /// This is used by the positional trees (whose keys are not ordered)
+    /// and by the operations which do not know the order of the keys.
+    #[inline]
+    fn debug_check_links(&self) {
+        #[cfg(all(feature = "check-invariants", debug_assertions))]
+        {
+            if let Err(e) = self.check_links() {
+                panic!("{}", e);
+            }
+        }
+    }
+    /// Returns the slot for a new node at the end of the arena.
+    fn new_slot(&self) -> I {
+        assert!(self.len() < I::NULL.to_usize(), "{}", FULL);
+        I::from_usize(self.len())
+    }
+    /// Lists the nodes of the subtree rooted at `root` in preorder.
+    ///
+    /// Each element is a tuple of the index of a node and the positions of its children in the list.
+    /// Returns `None` if the subtree has more than `limit` nodes.
+    fn preorder(
+        &self,
+        root: I,
+        limit: usize,
+    ) -> Option<Vec<(I, I, I)>> {
+        let mut list: Vec<(I, I, I)> = Vec::new();
+        let mut stack = Vec::new();
+        if root != I::NULL {
+            stack.push((root, I::NULL, true));
+        }
+        while let Some((i, parent, is_lft)) = stack.pop() {
+            if list.len() == limit {
+                return None;
+            }
+            let position = I::from_usize(list.len());
+            if parent != I::NULL {
+                if is_lft {
+                    list[parent.to_usize()].1 = position;
+                } else {
+                    list[parent.to_usize()].2 = position;
+                }
+            }
+            list.push((i, I::NULL, I::NULL));
+            let node = self.node_ref(i);
+            if node.rgt != I::NULL {
+                stack.push((node.rgt, position, false));
+            }
+            if node.lft != I::NULL {
+                stack.push((node.lft, position, true));
+            }
+        }
+        Some(list)
+    }
+    pub fn capacity(&self) -> usize {
+        self.nodes.capacity()
+    }
+    pub fn reserve(&mut self, additional: usize) {
+        assert!(self.fits(additional), "{}", FULL);
+        self.nodes.reserve(additional);
+    }
+    pub fn try_reserve(&mut self, additional: usize) -> Result<(), TryReserveError> {
+        if !self.fits(additional) {
+            return Err(TryReserveError::CapacityOverflow);
+        }
+        self.nodes
+            .try_reserve(additional)
+            .map_err(TryReserveError::AllocError)
+    }
+    pub fn shrink_to_fit(&mut self) {
+        self.nodes.shrink_to_fit();
+    }
+    /// Returns true if `additional` more nodes can be indexed by `I`.
+    fn fits(&self, additional: usize) -> bool {
+        additional <= I::NULL.to_usize() - self.len()
+    }
+    /// Iterates over the entries without pushing the lazy updates down,
+    /// so only the keys are guaranteed to be up to date.
+    pub fn iter(&self) -> iter::Iter<K, V, A, I> {
+        iter::InOrderIter::new(self.root().map(I::to_usize), &self.nodes)
+    }
+    pub fn nodes_iter(&self) -> slice::Iter<Node<K, V, A, I>> {
+        self.nodes.iter()
+    }
+}
+#[cfg(feature = "serde")]
+impl<K, V, A, C, I> Tree<K, V, A, C, I>
+where
+    C: Compare<K>,
+    A: Augment<K, V>,
+    I: Index,
+{
+    /// Makes a tree of the entries given by `next`, which are linked into a perfectly balanced shape.
+    ///
+    /// Fails if the keys are not sorted in strictly ascending order,
+    /// or the number of entries exceeds the limit of the index type.
+    pub fn deserialize_sorted<E, F>(cmp: C, mut next: F) -> Result<Self, E>
+    where
+        E: ::serde::de::Error,
+        F: FnMut() -> Result<Option<(K, V)>, E>,
+    {
+        let mut tree = Tree::with_comparator(cmp);
+        while let Some((key, value)) = next()? {
+            if let Some(last) = tree.nodes.last() {
+                if tree.cmp.compare(&last.key, &key) != Ordering::Less {
+                    return Err(E::custom(
+                        "The keys are not sorted in strictly ascending order",
+                    ));
+                }
+            }
+            if tree.len() == I::NULL.to_usize() {
+                return Err(E::custom(FULL));
+            }
+            tree.nodes.push(Node::new(key, value, I::NULL, I::NULL));
+        }
+        let len = tree.len();
+        tree.root = tree.link_balanced(0, len);
+        Ok(tree)
+    }
+}
+impl<K, V, A, C, I> hash::Hash for Tree<K, V, A, C, I>
+where
+    K: hash::Hash,
+    V: hash::Hash,
+    I: Index,
+{
+    fn hash<H>(&self, state: &mut H)
+    where
+        H: hash::Hasher,
+    {
+        for e in self.iter() {
+            e.hash(state);
+        }
+    }
+}
+impl<K, V, A, C, I> PartialEq for Tree<K, V, A, C, I>
+where
+    K: PartialEq,
+    V: PartialEq,
+    I: Index,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len() && self.iter().zip(other.iter()).all(|(a, b)| a.eq(&b))
+    }
+}
+impl<K, V, A, C, I> Eq for Tree<K, V, A, C, I>
+where
+    K: Eq,
+    V: Eq,
+    I: Index,
+{
+}
+impl<K, V, A, C, I> PartialOrd for Tree<K, V, A, C, I>
+where
+    K: PartialOrd,
+    V: PartialOrd,
+    I: Index,
+{
+    fn partial_cmp(&self, other: &Self) -> Option<cmp::Ordering> {
+        let mut i0 = self.iter();
+        let mut i1 = other.iter();
+        loop {
+            match (i0.next(), i1.next()) {
+                (None, None) => return Some(cmp::Ordering::Equal),
+                (None, _) => return Some(cmp::Ordering::Less),
+                (_, None) => return Some(cmp::Ordering::Greater),
+                (Some(e0), Some(e1)) => match e0.partial_cmp(&e1) {
+                    Some(cmp::Ordering::Equal) => {}
+                    not_equal => return not_equal,
+                },
+            }
+        }
+    }
+}
+impl<K, V, A, C, I> Ord for Tree<K, V, A, C, I>
+where
+    K: Ord,
+    V: Ord,
+    I: Index,
+{
+    fn cmp(&self, other: &Self) -> cmp::Ordering {
+        let mut i0 = self.iter();
+        let mut i1 = other.iter();
         loop {
             match (i0.next(), i1.next()) {
                 (None, None) => return cmp::Ordering::Equal,
@@ -501,3 +2168,382 @@ where
         }
     }
 }
+
+/// Augmentation of positional trees (i.e., sequences).
+///
+/// The key of each node of a positional tree is the slot of the node in the arena,
+/// and the order of the nodes is determined only by the shape of the tree.
+pub trait Positional<V, I>: Counted<I, V> {
+    /// Reverses the subtree lazily (i.e., `push_down` swaps the children afterwards).
+    fn reverse(&mut self);
+
+    /// Returns `true` if the subtree is reversed lazily, and not pushed down yet.
+    fn is_reversed(&self) -> bool;
+
+    /// Returns the largest slot in the subtree.
+    fn max_slot(&self) -> I;
+}
+
+/// Removes the entries of a tree in a single in-order pass.
+///
+/// The entries are moved out of the arena beforehand,
+/// and the kept ones are linked into a balanced tree again when this is dropped.
+/// So if this is leaked, the entries which have not been visited yet are lost too.
+pub struct Extract<'a, K: 'a, V: 'a, A: 'a, C: 'a, I: 'a = u32>
+where
+    A: Augment<K, V>,
+    I: Index,
+{
+    tree: &'a mut Tree<K, V, A, C, I>,
+    entries: iter::IntoIter<K, V, A, I>,
+    kept: Vec<(K, V)>,
+}
+impl<'a, K: 'a, V: 'a, A: 'a, C: 'a, I: 'a> Extract<'a, K, V, A, C, I>
+where
+    A: Augment<K, V>,
+    I: Index,
+{
+    /// Returns the next entry for which `pred` returns `true`, keeping the ones skipped over.
+    pub fn next_by<F>(&mut self, mut pred: F) -> Option<(K, V)>
+    where
+        F: FnMut(&K, &mut V) -> bool,
+    {
+        for (k, mut v) in &mut self.entries {
+            if pred(&k, &mut v) {
+                return Some((k, v));
+            }
+            self.kept.push((k, v));
+        }
+        None
+    }
+    pub fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, self.entries.size_hint().1)
+    }
+}
+impl<'a, K: 'a, V: 'a, A: 'a, C: 'a, I: 'a> Drop for Extract<'a, K, V, A, C, I>
+where
+    A: Augment<K, V>,
+    I: Index,
+{
+    fn drop(&mut self) {
+        let kept = mem::take(&mut self.kept);
+        let rest = mem::replace(
+            &mut self.entries,
+            iter::InOrderIter::empty(iter::OwnedNodes::new(Vec::new())),
+        );
+        self.tree.rebuild_sorted(kept.into_iter().chain(rest));
+    }
+}
+
+/// A top-down splaying in progress.
+///
+/// The nodes less than the target are linked into the left tree along its right spine
+/// (and vice versa), so only the spines need to be updated.
+/// The three trees are reassembled when this is dropped,
+/// so the tree is left valid even if the comparator panics in the middle of splaying.
+struct Splay<'a, K: 'a, V: 'a, A: 'a, C: 'a, I: 'a>
+where
+    A: Augment<K, V>,
+    I: Index,
+{
+    tree: &'a mut Tree<K, V, A, C, I>,
+    /// The root of the subtree being splayed.
+    top: I,
+    /// The root of the middle tree, which contains the target.
+    curr: I,
+    lft_root: I,
+    lft_rgtmost: I,
+    rgt_root: I,
+    rgt_lftmost: I,
+    /// `false` while the splaying may be interrupted by a panic.
+    done: bool,
+}
+impl<'a, K: 'a, V: 'a, A: 'a, C: 'a, I: 'a> Splay<'a, K, V, A, C, I>
+where
+    A: Augment<K, V>,
+    I: Index,
+{
+    /// Moves the nodes on the search path into the left and right trees,
+    /// until `cmp` finds the target (or a leaf) at `self.curr`.
+    ///
+    /// `self.curr` is the root of a valid subtree whenever `cmp` is called.
+    fn run<F>(&mut self, mut cmp: F) -> Ordering
+    where
+        F: FnMut(&Tree<K, V, A, C, I>, I) -> Ordering,
+    {
+        let tree = &mut *self.tree;
+        tree.push(self.curr);
+        let mut order = cmp(tree, self.curr);
+        loop {
+            let curr = self.curr;
+            let mut child;
+            match order {
+                Ordering::Less if tree.node_ref(curr).lft != I::NULL => {
+                    // zig
+                    child = tree.node_ref(curr).lft;
+                    tree.push(child);
+                    order = cmp(tree, child);
+                    if Ordering::Less == order && tree.node_ref(child).lft != I::NULL {
+                        // zig-zig
+                        let grand_child = tree.node_ref(child).lft;
+                        tree.node_mut(curr).lft = tree.node_ref(child).rgt;
+                        tree.update(curr);
+                        tree.node_mut(child).rgt = curr;
+                        self.curr = mem::replace(&mut child, grand_child);
+                        tree.push(child);
+                        order = cmp(tree, child);
+                    }
+                    if self.rgt_lftmost == I::NULL {
+                        self.rgt_root = self.curr;
+                    } else {
+                        tree.node_mut(self.rgt_lftmost).lft = self.curr;
+                    }
+                    self.rgt_lftmost = self.curr;
+                }
+                Ordering::Greater if tree.node_ref(curr).rgt != I::NULL => {
+                    // zag
+                    child = tree.node_ref(curr).rgt;
+                    tree.push(child);
+                    order = cmp(tree, child);
+                    if Ordering::Greater == order && tree.node_ref(child).rgt != I::NULL {
+                        // zag-zag
+                        let grand_child = tree.node_ref(child).rgt;
+                        tree.node_mut(curr).rgt = tree.node_ref(child).lft;
+                        tree.update(curr);
+                        tree.node_mut(child).lft = curr;
+                        self.curr = mem::replace(&mut child, grand_child);
+                        tree.push(child);
+                        order = cmp(tree, child);
+                    }
+                    if self.lft_rgtmost == I::NULL {
+                        self.lft_root = self.curr;
+                    } else {
+                        tree.node_mut(self.lft_rgtmost).rgt = self.curr;
+                    }
+                    self.lft_rgtmost = self.curr;
+                }
+                _ => return order,
+            }
+            self.curr = child;
+        }
+    }
+}
+impl<'a, K: 'a, V: 'a, A: 'a, C: 'a, I: 'a> Drop for Splay<'a, K, V, A, C, I>
+where
+    A: Augment<K, V>,
+    I: Index,
+{
+    fn drop(&mut self) {
+        let tree = &mut *self.tree;
+        let curr = self.curr;
+        let (curr_lft, curr_rgt) = (tree.node_ref(curr).lft, tree.node_ref(curr).rgt);
+        if self.lft_rgtmost == I::NULL {
+            self.lft_root = curr_lft;
+        } else {
+            tree.node_mut(self.lft_rgtmost).rgt = curr_lft;
+            tree.update_rgt_spine(self.lft_root, self.lft_rgtmost);
+        }
+        if self.rgt_lftmost == I::NULL {
+            self.rgt_root = curr_rgt;
+        } else {
+            tree.node_mut(self.rgt_lftmost).lft = curr_rgt;
+            tree.update_lft_spine(self.rgt_root, self.rgt_lftmost);
+        }
+        tree.node_mut(curr).lft = self.lft_root;
+        tree.node_mut(curr).rgt = self.rgt_root;
+        tree.update(curr);
+        if !self.done {
+            // The caller cannot link the new root of the subtree in place of the old one.
+            tree.replace_link(self.top, curr);
+        }
+    }
+}
+
+/// The nodes being detached from a tree by `Tree::detach`.
+///
+/// `subtree` is the list made by `Tree::preorder`, whose first elements are the current slots of the nodes.
+/// If the comparator panics while the other nodes are relocated,
+/// the detached nodes are linked again and joined to the rest of the tree when this is dropped.
+struct Detaching<'a, K: 'a, V: 'a, A: 'a, C: 'a, I: 'a>
+where
+    A: Augment<K, V>,
+    I: Index,
+{
+    tree: &'a mut Tree<K, V, A, C, I>,
+    subtree: Vec<(I, I, I)>,
+    /// `true` if the detached nodes are greater than the rest.
+    greater: bool,
+    done: bool,
+}
+impl<'a, K: 'a, V: 'a, A: 'a, C: 'a, I: 'a> Drop for Detaching<'a, K, V, A, C, I>
+where
+    A: Augment<K, V>,
+    I: Index,
+{
+    fn drop(&mut self) {
+        if self.done || self.subtree.is_empty() {
+            return;
+        }
+        let tree = &mut *self.tree;
+        let subtree = &self.subtree;
+        let slot = |position: I| {
+            if position == I::NULL {
+                I::NULL
+            } else {
+                subtree[position.to_usize()].0
+            }
+        };
+        for &(i, lft, rgt) in subtree {
+            let node = tree.node_mut(i);
+            node.lft = slot(lft);
+            node.rgt = slot(rgt);
+        }
+        let top = subtree[0].0;
+        let root = tree.root;
+        if root == I::NULL {
+            tree.root = top;
+        } else if self.greater {
+            tree.root = tree.splay_rgtmost(root);
+            tree.root_mut().rgt = top;
+        } else {
+            tree.root = tree.splay_lftmost(root);
+            tree.root_mut().lft = top;
+        }
+        let root = tree.root;
+        tree.update(root);
+    }
+}
+
+/// The nodes pushed at the end of the arena, which are not linked yet.
+///
+/// They are linked into a balanced subtree when this is dropped (even on unwinding),
+/// which becomes the right child of the maximum node `max` (or the whole tree if `max` is `None`).
+struct Run<'a, K: 'a, V: 'a, A: 'a, C: 'a, I: 'a>
+where
+    A: Augment<K, V>,
+    I: Index,
+{
+    tree: &'a mut Tree<K, V, A, C, I>,
+    max: Option<I>,
+    /// The slot of the first node of the run.
+    offset: usize,
+}
+impl<'a, K: 'a, V: 'a, A: 'a, C: 'a, I: 'a> Drop for Run<'a, K, V, A, C, I>
+where
+    A: Augment<K, V>,
+    I: Index,
+{
+    fn drop(&mut self) {
+        let tree = &mut *self.tree;
+        let len = tree.len();
+        let run = tree.link_balanced(self.offset, len);
+        match self.max {
+            None => tree.root = run,
+            Some(_) if run == I::NULL => {}
+            Some(max) => {
+                // The maximum node is found without the comparator.
+                let root = tree.root;
+                tree.root = tree.splay_rgtmost(root);
+                debug_assert_eq!(tree.root, max);
+                tree.root_mut().rgt = run;
+                tree.update(max);
+            }
+        }
+    }
+}
+
+/// The way to find a node without the links from the parents.
+trait Locate<K, V, A, C, I> {
+    /// `true` if the keys are the slots of the nodes.
+    const SLOTTED: bool = false;
+
+    /// Compares the node `target` with the node `i`, in the same manner as `splay_by_node`.
+    fn locate(tree: &Tree<K, V, A, C, I>, target: I, i: I) -> Ordering;
+
+    /// Called when `node` is moved into `slot`.
+    fn moved(_node: &mut Node<K, V, A, I>, _slot: I) {}
+}
+
+/// Finds the nodes by their keys.
+struct ByKey;
+impl<K, V, A, C: Compare<K>, I: Index> Locate<K, V, A, C, I> for ByKey {
+    fn locate(tree: &Tree<K, V, A, C, I>, target: I, i: I) -> Ordering {
+        tree.cmp
+            .compare(&tree.node_ref(target).key, &tree.node_ref(i).key)
+    }
+}
+
+/// Finds the nodes of positional trees by the largest slots in the subtrees.
+struct BySlot;
+impl<V, A: Positional<V, I>, C, I: Index> Locate<I, V, A, C, I> for BySlot {
+    const SLOTTED: bool = true;
+
+    fn locate(tree: &Tree<I, V, A, C, I>, target: I, i: I) -> Ordering {
+        let lft = tree.node_ref(i).lft;
+        if i == target {
+            Ordering::Equal
+        } else if lft != I::NULL && tree.node_ref(lft).aug.max_slot() == target {
+            Ordering::Less
+        } else {
+            Ordering::Greater
+        }
+    }
+    fn moved(node: &mut Node<I, V, A, I>, slot: I) {
+        node.key = slot;
+    }
+}
+
+fn check_range<Q: ?Sized, C, R>(cmp: &C, range: &R)
+where
+    C: Compare<Q>,
+    R: RangeBounds<Q>,
+{
+    match (range.start_bound(), range.end_bound()) {
+        (Bound::Excluded(s), Bound::Excluded(e)) if cmp.compare(s, e) == Ordering::Equal => {
+            panic!("range start and end are equal and excluded in SplayMap")
+        }
+        (Bound::Included(s), Bound::Included(e))
+        | (Bound::Included(s), Bound::Excluded(e))
+        | (Bound::Excluded(s), Bound::Included(e))
+        | (Bound::Excluded(s), Bound::Excluded(e))
+            if cmp.compare(s, e) == Ordering::Greater =>
+        {
+            panic!("range start is greater than range end in SplayMap")
+        }
+        _ => {}
+    }
+}
+
+/// Compares the start bound of a range with `key`.
+///
+/// Returns `Ordering::Greater` if `key` is before the start of the range.
+fn start_order<Q: ?Sized, C>(cmp: &C, start: Bound<&Q>, key: &Q) -> Ordering
+where
+    C: Compare<Q>,
+{
+    match start {
+        Bound::Included(s) => cmp.compare(s, key),
+        Bound::Excluded(s) => match cmp.compare(s, key) {
+            Ordering::Equal => Ordering::Greater,
+            other => other,
+        },
+        Bound::Unbounded => Ordering::Less,
+    }
+}
+
+/// Compares the end bound of a range with `key`.
+///
+/// Returns `Ordering::Less` if `key` is after the end of the range.
+fn end_order<Q: ?Sized, C>(cmp: &C, end: Bound<&Q>, key: &Q) -> Ordering
+where
+    C: Compare<Q>,
+{
+    match end {
+        Bound::Included(e) => cmp.compare(e, key),
+        Bound::Excluded(e) => match cmp.compare(e, key) {
+            Ordering::Equal => Ordering::Less,
+            other => other,
+        },
+        Bound::Unbounded => Ordering::Greater,
+    }
+}
@@ -0,0 +1,853 @@
+//! A sequence based on a splay tree.
+use std;
+use std::cmp;
+use std::hash;
+use std::mem;
+use std::ops::{self, Bound, RangeBounds};
+use tree_core;
+use augment::{Augment, Counted, Eager, Tagged};
+use iter;
+use compare::Natural;
+use index::Index;
+use error::InvariantError;
+
+/// A sequence based on a splay tree.
+///
+/// Unlike `SplayMap`, the elements are ordered by their positions instead of keys.
+/// It performs insertion and removal at arbitrary positions, look-up by position,
+/// splitting and concatenation in `O(log n)` amortized time.
+///
+/// # Examples
+/// ```
+/// use splay_tree::SplayVec;
+///
+/// let mut vec = SplayVec::new();
+///
+/// vec.push("foo");
+/// vec.push("bar");
+/// vec.insert(1, "baz");
+///
+/// assert_eq!(vec.get(1), Some(&"baz"));
+/// assert_eq!(vec.remove(0), "foo");
+/// assert_eq!(vec.get(0), Some(&"baz"));
+///
+/// assert_eq!(vec.into_iter().collect::<Vec<_>>(), ["baz", "bar"]);
+/// ```
+#[derive(Debug, Clone)]
+pub struct SplayVec<T, A = (), I = u32> {
+    tree: tree_core::Tree<I, T, Implicit<A, I>, Natural, I>,
+}
+impl<T> SplayVec<T> {
+    /// Makes a new empty `SplayVec`.
+    ///
+    /// # Examples
+    /// ```
+    /// use splay_tree::SplayVec;
+    ///
+    /// let mut vec = SplayVec::new();
+    /// vec.push(1);
+    /// assert_eq!(vec.len(), 1);
+    /// ```
+    pub fn new() -> Self {
+        SplayVec {
+            tree: tree_core::Tree::new(),
+        }
+    }
+}
+impl<T, A, I> SplayVec<T, A, I>
+where
+    A: Augment<I, T>,
+    I: Index,
+{
+    /// Clears the sequence, removing all elements.
+    ///
+    /// # Examples
+    /// ```
+    /// use splay_tree::SplayVec;
+    ///
+    /// let mut vec: SplayVec<_> = (0..3).collect();
+    /// vec.clear();
+    /// assert!(vec.is_empty());
+    /// ```
+    pub fn clear(&mut self) {
+        self.tree = tree_core::Tree::new();
+    }
+
+    /// Returns a reference to the element at `index`, or `None` if it is out of bounds.
+    ///
+    /// # NOTICE
+    /// Because `SplayVec` is a self-adjusting amortized data structure,
+    /// this function requires the `mut` qualifier.
+    ///
+    /// # Examples
+    /// ```
+    /// use splay_tree::SplayVec;
+    ///
+    /// let mut vec: SplayVec<_> = vec!["foo", "bar"].into_iter().collect();
+    /// assert_eq!(vec.get(1), Some(&"bar"));
+    /// assert_eq!(vec.get(2), None);
+    /// ```
+    pub fn get(&mut self, index: usize) -> Option<&T> {
+        match self.tree.select(index) {
+            Some(i) => Some(&self.tree.node_ref(i).val),
+            None => None,
+        }
+    }
+
+    /// Returns a mutable reference to the element at `index`, or `None` if it is out of bounds.
+    ///
+    /// # Examples
+    /// ```
+    /// use splay_tree::SplayVec;
+    ///
+    /// let mut vec: SplayVec<_> = vec![1, 2].into_iter().collect();
+    /// if let Some(x) = vec.get_mut(1) {
+    ///     *x = 10;
+    /// }
+    /// assert_eq!(vec.get(1), Some(&10));
+    /// ```
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut T> {
+        match self.tree.select(index) {
+            Some(i) => Some(&mut self.tree.node_mut(i).val),
+            None => None,
+        }
+    }
+
+    /// Inserts an element at `index`, shifting all elements after it to the right.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index > len`.
+    ///
+    /// # Examples
+    /// ```
+    /// use splay_tree::SplayVec;
+    ///
+    /// let mut vec: SplayVec<_> = vec![1, 2, 3].into_iter().collect();
+    /// vec.insert(1, 4);
+    /// assert_eq!(vec.iter().cloned().collect::<Vec<_>>(), [1, 4, 2, 3]);
+    /// vec.insert(4, 5);
+    /// assert_eq!(vec.iter().cloned().collect::<Vec<_>>(), [1, 4, 2, 3, 5]);
+    /// ```
+    pub fn insert(&mut self, index: usize, element: T) {
+        let len = self.len();
+        if index > len {
+            panic!(
+                "insertion index (is {}) should be <= len (is {})",
+                index, len
+            );
+        }
+        self.tree.insert_at(index, element);
+    }
+
+    /// Removes and returns the element at `index`, shifting all elements after it to the left.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds.
+    ///
+    /// # Examples
+    /// ```
+    /// use splay_tree::SplayVec;
+    ///
+    /// let mut vec: SplayVec<_> = vec![1, 2, 3].into_iter().collect();
+    /// assert_eq!(vec.remove(1), 2);
+    /// assert_eq!(vec.iter().cloned().collect::<Vec<_>>(), [1, 3]);
+    /// ```
+    pub fn remove(&mut self, index: usize) -> T {
+        let len = self.len();
+        match self.tree.remove_at(index) {
+            Some(element) => element,
+            None => panic!(
+                "removal index (is {}) should be < len (is {})",
+                index, len
+            ),
+        }
+    }
+
+    /// Appends an element to the back of the sequence.
+    ///
+    /// # Examples
+    /// ```
+    /// use splay_tree::SplayVec;
+    ///
+    /// let mut vec = SplayVec::new();
+    /// vec.push(1);
+    /// vec.push(2);
+    /// assert_eq!(vec.iter().cloned().collect::<Vec<_>>(), [1, 2]);
+    /// ```
+    pub fn push(&mut self, element: T) {
+        let len = self.len();
+        self.tree.insert_at(len, element);
+    }
+
+    /// Removes the last element from the sequence and returns it, or `None` if it is empty.
+    ///
+    /// # Examples
+    /// ```
+    /// use splay_tree::SplayVec;
+    ///
+    /// let mut vec: SplayVec<_> = vec![1, 2].into_iter().collect();
+    /// assert_eq!(vec.pop(), Some(2));
+    /// assert_eq!(vec.pop(), Some(1));
+    /// assert_eq!(vec.pop(), None);
+    /// ```
+    pub fn pop(&mut self) -> Option<T> {
+        let len = self.len();
+        self.tree.remove_at(len.wrapping_sub(1))
+    }
+
+    /// Splits the sequence into two at the given index.
+    ///
+    /// Returns a newly allocated sequence containing the elements in the range `[at, len)`.
+    /// After the call, the original sequence will be left containing the elements `[0, at)`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `at > len`.
+    ///
+    /// # Examples
+    /// ```
+    /// use splay_tree::SplayVec;
+    ///
+    /// let mut vec: SplayVec<_> = vec![1, 2, 3].into_iter().collect();
+    /// let vec2 = vec.split_off(1);
+    /// assert_eq!(vec.into_iter().collect::<Vec<_>>(), [1]);
+    /// assert_eq!(vec2.into_iter().collect::<Vec<_>>(), [2, 3]);
+    /// ```
+    pub fn split_off(&mut self, at: usize) -> Self {
+        let len = self.len();
+        if at > len {
+            panic!("`at` split index (is {}) should be <= len (is {})", at, len);
+        }
+        SplayVec {
+            tree: self.tree.split_off_at(at),
+        }
+    }
+
+    /// Moves all the elements of `other` into the back of `self`, leaving `other` empty.
+    ///
+    /// # Examples
+    /// ```
+    /// use splay_tree::SplayVec;
+    ///
+    /// let mut vec: SplayVec<_> = vec![1, 2, 3].into_iter().collect();
+    /// let mut vec2: SplayVec<_> = vec![4, 5, 6].into_iter().collect();
+    /// vec.append(&mut vec2);
+    /// assert_eq!(vec.iter().cloned().collect::<Vec<_>>(), [1, 2, 3, 4, 5, 6]);
+    /// assert!(vec2.is_empty());
+    /// ```
+    pub fn append(&mut self, other: &mut Self) {
+        self.tree.concat(&mut other.tree);
+    }
+
+    /// Gets a mutable iterator over the elements of the sequence.
+    ///
+    /// # Examples
+    /// ```
+    /// use splay_tree::SplayVec;
+    ///
+    /// let mut vec: SplayVec<_> = vec![1, 2].into_iter().collect();
+    /// for x in vec.iter_mut() {
+    ///     *x *= 10;
+    /// }
+    /// assert_eq!(vec.iter().cloned().collect::<Vec<_>>(), [10, 20]);
+    /// ```
+    pub fn iter_mut(&mut self) -> IterMut<'_, T, A, I> {
+        IterMut(self.tree.iter_mut())
+    }
+
+    /// Reverses the elements in `range`.
+    ///
+    /// The elements are reordered lazily in `O(log n)` amortized time.
+    /// Note that the augmentation of the reversed elements is not recomputed,
+    /// so it has to be symmetric (e.g., the fold of a commutative monoid).
+    ///
+    /// # Panics
+    ///
+    /// Panics if the start of the range is greater than the end of it
+    /// or if the end of the range is greater than the length of the sequence.
+    ///
+    /// # Examples
+    /// ```
+    /// use splay_tree::SplayVec;
+    ///
+    /// let mut vec: SplayVec<_> = (0..6).collect();
+    /// vec.reverse(1..4);
+    /// assert_eq!(vec.get(1), Some(&3));
+    ///
+    /// vec.reverse(..);
+    /// assert_eq!(vec.iter().cloned().collect::<Vec<_>>(), [5, 4, 1, 2, 3, 0]);
+    /// ```
+    pub fn reverse<R>(&mut self, range: R)
+    where
+        R: RangeBounds<usize>,
+    {
+        let (start, end) = self.bounds(&range);
+        self.tree.reverse_at(start, end);
+    }
+
+    /// Pushes all of the pending lazy updates down to the elements.
+    ///
+    /// Lazy updates (i.e., `reverse` and `update_range`) are applied to the elements
+    /// as they are visited by the operations which take `&mut self`.
+    /// The ones taking `&self` (e.g., `iter` and indexing) see through the pending reversals,
+    /// so this is only needed to bring the whole sequence up to date at once.
+    ///
+    /// # Examples
+    /// ```
+    /// use splay_tree::SplayVec;
+    ///
+    /// let mut vec: SplayVec<_> = (0..3).collect();
+    /// vec.reverse(..);
+    /// vec.flush();
+    /// assert_eq!(vec[0], 2);
+    /// ```
+    pub fn flush(&mut self) {
+        self.tree.flush();
+    }
+
+    /// Checks the internal structure of the sequence, and returns an error describing the corruption if any.
+    ///
+    /// This verifies that every node in the arena is reachable from the root exactly once
+    /// (i.e., the links have no cycles).
+    /// It takes linear time, and is intended for debugging.
+    ///
+    /// If the `check-invariants` feature is enabled, this is run after every mutating operation
+    /// in debug builds, which panic on a corruption.
+    ///
+    /// # Examples
+    /// ```
+    /// use splay_tree::SplayVec;
+    ///
+    /// let mut vec: SplayVec<_> = (0..100).collect();
+    /// vec.remove(50);
+    /// vec.reverse(10..90);
+    /// assert_eq!(vec.check_invariants(), Ok(()));
+    /// ```
+    pub fn check_invariants(&self) -> Result<(), InvariantError> {
+        self.tree.check_links()
+    }
+
+    fn bounds<R>(&self, range: &R) -> (usize, usize)
+    where
+        R: RangeBounds<usize>,
+    {
+        let start = match range.start_bound() {
+            Bound::Included(&s) => s,
+            Bound::Excluded(&s) => s + 1,
+            Bound::Unbounded => 0,
+        };
+        let end = match range.end_bound() {
+            Bound::Included(&e) => e + 1,
+            Bound::Excluded(&e) => e,
+            Bound::Unbounded => self.len(),
+        };
+        if start > end {
+            panic!("range starts at {} but ends at {}", start, end);
+        }
+        if end > self.len() {
+            panic!(
+                "range end index {} out of range for sequence of length {}",
+                end,
+                self.len()
+            );
+        }
+        (start, end)
+    }
+}
+impl<T, A, I> SplayVec<T, A, I>
+where
+    A: Tagged<I, T>,
+    I: Index,
+{
+    /// Applies `tag` to the elements in `range`.
+    ///
+    /// The elements are updated lazily in `O(log n)` amortized time (see `flush`).
+    ///
+    /// # Panics
+    ///
+    /// Panics if the start of the range is greater than the end of it
+    /// or if the end of the range is greater than the length of the sequence.
+    ///
+    /// # Examples
+    /// ```
+    /// use splay_tree::SplayVec;
+    /// use splay_tree::augment::{Lazy, Tag};
+    ///
+    /// #[derive(Clone)]
+    /// struct Mul(u32);
+    /// impl<K> Tag<K, u32> for Mul {
+    ///     fn apply(&self, _key: &K, value: &mut u32) {
+    ///         *value *= self.0;
+    ///     }
+    ///     fn compose(&mut self, later: &Self) {
+    ///         self.0 *= later.0;
+    ///     }
+    /// }
+    ///
+    /// let mut vec: SplayVec<_, Lazy<Mul>> = (1..6).collect();
+    /// vec.update_range(1..4, Mul(10));
+    /// vec.update_range(3.., Mul(2));
+    /// assert_eq!(vec.get(3), Some(&80));
+    ///
+    /// assert_eq!(vec.iter_mut().map(|x| *x).collect::<Vec<_>>(), [1, 20, 30, 80, 10]);
+    /// ```
+    pub fn update_range<R>(&mut self, range: R, tag: A::Tag)
+    where
+        R: RangeBounds<usize>,
+    {
+        let (start, end) = self.bounds(&range);
+        self.tree.update_at(start, end, &tag);
+    }
+}
+impl<T, A, I> SplayVec<T, A, I>
+where
+    I: Index,
+{
+    /// Returns the number of elements in the sequence.
+    ///
+    /// # Examples
+    /// ```
+    /// use splay_tree::SplayVec;
+    ///
+    /// let mut vec = SplayVec::new();
+    /// vec.push(1);
+    /// assert_eq!(vec.len(), 1);
+    /// ```
+    pub fn len(&self) -> usize {
+        self.tree.len()
+    }
+
+    /// Returns true if the sequence contains no elements.
+    ///
+    /// # Examples
+    /// ```
+    /// use splay_tree::SplayVec;
+    ///
+    /// let mut vec = SplayVec::new();
+    /// assert!(vec.is_empty());
+    ///
+    /// vec.push(1);
+    /// assert!(!vec.is_empty());
+    /// ```
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Gets an iterator over the elements of the sequence.
+    ///
+    /// # Examples
+    /// ```
+    /// use splay_tree::SplayVec;
+    ///
+    /// let mut vec = SplayVec::new();
+    /// vec.push(1);
+    /// vec.insert(0, 2);
+    /// assert_eq!(vec.iter().collect::<Vec<_>>(), [&2, &1]);
+    /// ```
+    pub fn iter(&self) -> Iter<'_, T, A, I>
+    where
+        A: Eager<I, T>,
+    {
+        Iter(self.tree.positional_iter())
+    }
+}
+impl<T, A, I> Default for SplayVec<T, A, I>
+where
+    A: Augment<I, T>,
+    I: Index,
+{
+    fn default() -> Self {
+        SplayVec {
+            tree: tree_core::Tree::new(),
+        }
+    }
+}
+impl<T, A, I> ops::Index<usize> for SplayVec<T, A, I>
+where
+    A: Eager<I, T>,
+    I: Index,
+{
+    type Output = T;
+
+    fn index(&self, index: usize) -> &Self::Output {
+        match self.tree.nth_ref(index) {
+            Some(node) => &node.val,
+            None => panic!("Index {} out of bounds! Len: {}", index, self.len()),
+        }
+    }
+}
+impl<T, A, I> ops::IndexMut<usize> for SplayVec<T, A, I>
+where
+    A: Eager<I, T>,
+    I: Index,
+{
+    fn index_mut(&mut self, index: usize) -> &mut Self::Output {
+        let len = self.len();
+        match self.get_mut(index) {
+            Some(element) => element,
+            None => panic!("Index {} out of bounds! Len: {}", index, len),
+        }
+    }
+}
+impl<T, A, I> hash::Hash for SplayVec<T, A, I>
+where
+    T: hash::Hash,
+    A: Eager<I, T>,
+    I: Index,
+{
+    fn hash<H>(&self, state: &mut H)
+    where
+        H: hash::Hasher,
+    {
+        for x in self.iter() {
+            x.hash(state);
+        }
+    }
+}
+impl<T, A, I> PartialEq for SplayVec<T, A, I>
+where
+    T: PartialEq,
+    A: Eager<I, T>,
+    I: Index,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len() && self.iter().eq(other.iter())
+    }
+}
+impl<T, A, I> Eq for SplayVec<T, A, I>
+where
+    T: Eq,
+    A: Eager<I, T>,
+    I: Index,
+{
+}
+impl<T, A, I> PartialOrd for SplayVec<T, A, I>
+where
+    T: PartialOrd,
+    A: Eager<I, T>,
+    I: Index,
+{
+    fn partial_cmp(&self, other: &Self) -> Option<cmp::Ordering> {
+        self.iter().partial_cmp(other.iter())
+    }
+}
+impl<T, A, I> Ord for SplayVec<T, A, I>
+where
+    T: Ord,
+    A: Eager<I, T>,
+    I: Index,
+{
+    fn cmp(&self, other: &Self) -> cmp::Ordering {
+        self.iter().cmp(other.iter())
+    }
+}
+impl<T, A, I> std::iter::FromIterator<T> for SplayVec<T, A, I>
+where
+    A: Augment<I, T>,
+    I: Index,
+{
+    fn from_iter<It>(iter: It) -> Self
+    where
+        It: IntoIterator<Item = T>,
+    {
+        let mut vec = SplayVec::default();
+        for x in iter {
+            vec.push(x);
+        }
+        vec
+    }
+}
+#[cfg(feature = "serde")]
+impl<T, A, I> ::serde::Serialize for SplayVec<T, A, I>
+where
+    T: ::serde::Serialize,
+    A: Eager<I, T>,
+    I: Index,
+{
+    /// Serializes the sequence in the same representation as `Vec`.
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: ::serde::Serializer,
+    {
+        use serde::ser::SerializeSeq;
+        let mut seq = serializer.serialize_seq(Some(self.len()))?;
+        for x in self.iter() {
+            seq.serialize_element(x)?;
+        }
+        seq.end()
+    }
+}
+#[cfg(feature = "serde")]
+impl<'de, T, A, I> ::serde::Deserialize<'de> for SplayVec<T, A, I>
+where
+    T: ::serde::Deserialize<'de>,
+    A: Augment<I, T>,
+    I: Index,
+{
+    /// Deserializes a sequence of the elements, and links them into a perfectly balanced tree.
+    ///
+    /// Fails if the number of the elements exceeds the limit of the index type.
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: ::serde::Deserializer<'de>,
+    {
+        struct Visitor<T, A, I>(std::marker::PhantomData<SplayVec<T, A, I>>);
+        impl<'de, T, A, I> ::serde::de::Visitor<'de> for Visitor<T, A, I>
+        where
+            T: ::serde::Deserialize<'de>,
+            A: Augment<I, T>,
+            I: Index,
+        {
+            type Value = SplayVec<T, A, I>;
+
+            fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                f.write_str("a sequence")
+            }
+            fn visit_seq<M>(self, mut access: M) -> Result<Self::Value, M::Error>
+            where
+                M: ::serde::de::SeqAccess<'de>,
+            {
+                use std::vec::Vec;
+                let mut items = Vec::with_capacity(cmp::min(access.size_hint().unwrap_or(0), 4096));
+                while let Some(item) = access.next_element()? {
+                    if items.len() == I::NULL.to_usize() {
+                        return Err(::serde::de::Error::custom(tree_core::FULL));
+                    }
+                    items.push(item);
+                }
+                let mut vec = SplayVec::default();
+                vec.tree.rebuild_from_values(items);
+                Ok(vec)
+            }
+        }
+        deserializer.deserialize_seq(Visitor(std::marker::PhantomData))
+    }
+}
+impl<T, A, I> IntoIterator for SplayVec<T, A, I>
+where
+    A: Augment<I, T>,
+    I: Index,
+{
+    type Item = T;
+    type IntoIter = IntoIter<T, A, I>;
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter(self.tree.into_iter())
+    }
+}
+impl<'a, T, A, I> IntoIterator for &'a SplayVec<T, A, I>
+where
+    A: Eager<I, T>,
+    I: Index,
+{
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T, A, I>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+impl<'a, T, A, I> IntoIterator for &'a mut SplayVec<T, A, I>
+where
+    A: Augment<I, T>,
+    I: Index,
+{
+    type Item = &'a mut T;
+    type IntoIter = IterMut<'a, T, A, I>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+impl<T, A, I> Extend<T> for SplayVec<T, A, I>
+where
+    A: Augment<I, T>,
+    I: Index,
+{
+    fn extend<It>(&mut self, iter: It)
+    where
+        It: IntoIterator<Item = T>,
+    {
+        for x in iter {
+            self.push(x);
+        }
+    }
+}
+impl<'a, T, A, I> Extend<&'a T> for SplayVec<T, A, I>
+where
+    T: Copy + 'a,
+    A: Augment<I, T>,
+    I: Index,
+{
+    fn extend<It>(&mut self, iter: It)
+    where
+        It: IntoIterator<Item = &'a T>,
+    {
+        for x in iter {
+            self.push(*x);
+        }
+    }
+}
+
+/// An iterator over a SplayVec's elements.
+pub struct Iter<'a, T: 'a, A: 'a = (), I: 'a = u32>(iter::PositionalIter<'a, T, Implicit<A, I>, I>);
+impl<'a, T: 'a, A: 'a, I: 'a> Iterator for Iter<'a, T, A, I>
+where
+    A: Augment<I, T>,
+    I: Index,
+{
+    type Item = &'a T;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(|(_, v)| v)
+    }
+}
+impl<'a, T: 'a, A: 'a, I: 'a> DoubleEndedIterator for Iter<'a, T, A, I>
+where
+    A: Augment<I, T>,
+    I: Index,
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.0.next_back().map(|(_, v)| v)
+    }
+}
+
+/// A mutable iterator over a SplayVec's elements.
+pub struct IterMut<'a, T: 'a, A: 'a = (), I: 'a = u32>(iter::IterMut<'a, I, T, Implicit<A, I>, I>);
+impl<'a, T: 'a, A: 'a, I: 'a> Iterator for IterMut<'a, T, A, I>
+where
+    I: Index,
+{
+    type Item = &'a mut T;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(|(_, v)| v)
+    }
+}
+impl<'a, T: 'a, A: 'a, I: 'a> DoubleEndedIterator for IterMut<'a, T, A, I>
+where
+    I: Index,
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.0.next_back().map(|(_, v)| v)
+    }
+}
+
+/// An owning iterator over a SplayVec's elements.
+pub struct IntoIter<T, A = (), I = u32>(iter::IntoIter<I, T, Implicit<A, I>, I>)
+where
+    I: Index;
+impl<T, A, I> Iterator for IntoIter<T, A, I>
+where
+    I: Index,
+{
+    type Item = T;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(|(_, v)| v)
+    }
+}
+impl<T, A, I> DoubleEndedIterator for IntoIter<T, A, I>
+where
+    I: Index,
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.0.next_back().map(|(_, v)| v)
+    }
+}
+
+/// Augmentation which makes a tree positional.
+///
+/// The key of each node is its slot in the arena.
+/// `A` is the augmentation given by the user.
+#[derive(Debug, Clone)]
+struct Implicit<A, I> {
+    count: usize,
+    max_slot: I,
+    reversed: bool,
+    augment: A,
+}
+impl<T, A, I> Augment<I, T> for Implicit<A, I>
+where
+    A: Augment<I, T>,
+    I: Index,
+{
+    const LAZY: bool = true;
+
+    fn new(slot: &I, element: &T) -> Self {
+        Implicit {
+            count: 1,
+            max_slot: *slot,
+            reversed: false,
+            augment: A::new(slot, element),
+        }
+    }
+    fn update(&mut self, slot: &I, element: &T, lft: Option<&Self>, rgt: Option<&Self>) {
+        self.count = 1;
+        self.max_slot = *slot;
+        for child in lft.iter().chain(rgt.iter()) {
+            self.count += child.count;
+            self.max_slot = cmp::max(self.max_slot, child.max_slot);
+        }
+        self.augment.update(
+            slot,
+            element,
+            lft.map(|x| &x.augment),
+            rgt.map(|x| &x.augment),
+        );
+    }
+    fn push_down(
+        &mut self,
+        mut lft: Option<(&I, &mut T, &mut Self)>,
+        mut rgt: Option<(&I, &mut T, &mut Self)>,
+    ) -> bool {
+        if A::LAZY {
+            self.augment.push_down(
+                lft.as_mut().map(|x| (x.0, &mut *x.1, &mut x.2.augment)),
+                rgt.as_mut().map(|x| (x.0, &mut *x.1, &mut x.2.augment)),
+            );
+        }
+        if mem::replace(&mut self.reversed, false) {
+            for (_, _, child) in lft.into_iter().chain(rgt) {
+                child.reversed = !child.reversed;
+            }
+            true
+        } else {
+            false
+        }
+    }
+}
+impl<T, A, I> Counted<I, T> for Implicit<A, I>
+where
+    A: Augment<I, T>,
+    I: Index,
+{
+    fn count(&self) -> usize {
+        self.count
+    }
+}
+impl<T, A, I> Tagged<I, T> for Implicit<A, I>
+where
+    A: Tagged<I, T>,
+    I: Index,
+{
+    type Tag = A::Tag;
+
+    fn apply(tag: &A::Tag, slot: &I, element: &mut T) {
+        A::apply(tag, slot, element);
+    }
+    fn tag(&mut self, slot: &I, element: &mut T, tag: &A::Tag) {
+        self.augment.tag(slot, element, tag);
+    }
+}
+impl<T, A, I> tree_core::Positional<T, I> for Implicit<A, I>
+where
+    A: Augment<I, T>,
+    I: Index,
+{
+    fn reverse(&mut self) {
+        self.reversed = !self.reversed;
+    }
+    fn is_reversed(&self) -> bool {
+        self.reversed
+    }
+    fn max_slot(&self) -> I {
+        self.max_slot
+    }
+}
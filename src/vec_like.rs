@@ -1,13 +1,19 @@
 use std::slice;
 use std::borrow::Borrow;
 use tree_core;
+use augment::Augment;
+use compare::{Compare, Natural};
+use index::Index;
 
 #[derive(Debug, Clone)]
-pub struct VecLike<'a, K: 'a, V: 'a> {
-    tree: &'a tree_core::Tree<K, V>,
+pub struct VecLike<'a, K: 'a, V: 'a, A: 'a = (), C: 'a = Natural, I: 'a = u32> {
+    tree: &'a tree_core::Tree<K, V, A, C, I>,
 }
-impl<'a, K: 'a, V: 'a> VecLike<'a, K, V> {
-    pub fn new(tree: &'a tree_core::Tree<K, V>) -> Self {
+impl<'a, K: 'a, V: 'a, A: 'a, C: 'a, I: 'a> VecLike<'a, K, V, A, C, I>
+where
+    I: Index,
+{
+    pub fn new(tree: &'a tree_core::Tree<K, V, A, C, I>) -> Self {
         VecLike { tree: tree }
     }
     pub fn len(&self) -> usize {
@@ -15,7 +21,7 @@ impl<'a, K: 'a, V: 'a> VecLike<'a, K, V> {
     }
     pub fn get(&self, index: usize) -> Option<(&'a K, &'a V)> {
         if index < self.tree.len() {
-            Some(self.tree.node_ref(index as tree_core::NodeIndex).into())
+            Some(self.tree.node_ref(I::from_usize(index)).into())
         } else {
             None
         }
@@ -27,19 +33,25 @@ impl<'a, K: 'a, V: 'a> VecLike<'a, K, V> {
         let last = self.tree.len().wrapping_sub(1);
         self.get(last)
     }
-    pub fn iter(&self) -> Iter<'a, K, V> {
+    pub fn iter(&self) -> Iter<'a, K, V, A, I> {
         Iter(self.tree.nodes_iter())
     }
 }
 
 #[derive(Debug)]
-pub struct VecLikeMut<'a, K: 'a, V: 'a> {
-    tree: &'a mut tree_core::Tree<K, V>,
+pub struct VecLikeMut<'a, K: 'a, V: 'a, A: 'a = (), C: 'a = Natural, I: 'a = u32> {
+    tree: &'a mut tree_core::Tree<K, V, A, C, I>,
 }
-impl<'a, K: 'a, V: 'a> VecLikeMut<'a, K, V>
+impl<'a, K: 'a, V: 'a, A: 'a, C: 'a, I: 'a> VecLikeMut<'a, K, V, A, C, I>
 where
-    K: Ord,
+    C: Compare<K>,
+    A: Augment<K, V>,
+    I: Index,
 {
+    #[allow(dead_code)]
+    pub fn iter_mut(&mut self) -> IterMut<K, V, A, I> {
+        IterMut(self.tree.nodes_iter_mut())
+    }
     pub fn push(&mut self, key: K, value: V) -> bool {
         if self.tree.contains_key(&key) {
             false
@@ -54,17 +66,20 @@ where
     pub fn find_index<Q: ?Sized>(&mut self, key: &Q) -> Option<usize>
     where
         K: Borrow<Q>,
-        Q: Ord,
+        C: Compare<Q>,
     {
         if self.tree.contains_key(key) {
-            self.tree.root().map(|i| i as usize)
+            self.tree.root().map(I::to_usize)
         } else {
             None
         }
     }
 }
-impl<'a, K: 'a, V: 'a> VecLikeMut<'a, K, V> {
-    pub fn new(tree: &'a mut tree_core::Tree<K, V>) -> Self {
+impl<'a, K: 'a, V: 'a, A: 'a, C: 'a, I: 'a> VecLikeMut<'a, K, V, A, C, I>
+where
+    I: Index,
+{
+    pub fn new(tree: &'a mut tree_core::Tree<K, V, A, C, I>) -> Self {
         VecLikeMut { tree: tree }
     }
     pub fn len(&self) -> usize {
@@ -72,7 +87,7 @@ impl<'a, K: 'a, V: 'a> VecLikeMut<'a, K, V> {
     }
     pub fn get(&self, index: usize) -> Option<(&K, &V)> {
         if index < self.tree.len() {
-            Some(self.tree.node_ref(index as tree_core::NodeIndex).into())
+            Some(self.tree.node_ref(I::from_usize(index)).into())
         } else {
             None
         }
@@ -80,15 +95,17 @@ impl<'a, K: 'a, V: 'a> VecLikeMut<'a, K, V> {
     #[allow(dead_code)]
     pub fn get_mut(&mut self, index: usize) -> Option<(&K, &mut V)> {
         if index < self.tree.len() {
-            Some(self.tree.node_mut(index as tree_core::NodeIndex).into())
+            self.tree.mark_stale();
+            Some(self.tree.node_mut(I::from_usize(index)).into())
         } else {
             None
         }
     }
 
-    pub fn get_node_mut(&mut self, index: usize) -> Option<&mut tree_core::Node<K, V>> {
+    pub fn get_node_mut(&mut self, index: usize) -> Option<&mut tree_core::Node<K, V, A, I>> {
         if index < self.tree.len() {
-            Some(self.tree.node_mut(index as tree_core::NodeIndex))
+            self.tree.mark_stale();
+            Some(self.tree.node_mut(I::from_usize(index)))
         } else {
             None
         }
@@ -110,26 +127,28 @@ impl<'a, K: 'a, V: 'a> VecLikeMut<'a, K, V> {
         let last = self.tree.len().wrapping_sub(1);
         self.get_mut(last)
     }
-    pub fn iter(&self) -> Iter<K, V> {
+    pub fn iter(&self) -> Iter<K, V, A, I> {
         Iter(self.tree.nodes_iter())
     }
-    #[allow(dead_code)]
-    pub fn iter_mut(&mut self) -> IterMut<K, V> {
-        IterMut(self.tree.nodes_iter_mut())
-    }
 }
 
 #[derive(Clone)]
-pub struct Iter<'a, K: 'a, V: 'a>(pub slice::Iter<'a, tree_core::Node<K, V>>);
-impl<'a, K: 'a, V: 'a> Iterator for Iter<'a, K, V> {
+pub struct Iter<'a, K: 'a, V: 'a, A: 'a = (), I: 'a = u32>(pub slice::Iter<'a, tree_core::Node<K, V, A, I>>);
+impl<'a, K: 'a, V: 'a, A: 'a, I: 'a> Iterator for Iter<'a, K, V, A, I>
+where
+    I: Index,
+{
     type Item = (&'a K, &'a V);
     fn next(&mut self) -> Option<Self::Item> {
         self.0.next().map(|n| n.into())
     }
 }
 
-pub struct IterMut<'a, K: 'a, V: 'a>(slice::IterMut<'a, tree_core::Node<K, V>>);
-impl<'a, K: 'a, V: 'a> Iterator for IterMut<'a, K, V> {
+pub struct IterMut<'a, K: 'a, V: 'a, A: 'a = (), I: 'a = u32>(slice::IterMut<'a, tree_core::Node<K, V, A, I>>);
+impl<'a, K: 'a, V: 'a, A: 'a, I: 'a> Iterator for IterMut<'a, K, V, A, I>
+where
+    I: Index,
+{
     type Item = (&'a K, &'a mut V);
     fn next(&mut self) -> Option<Self::Item> {
         self.0.next().map(|n| n.into())
@@ -4,7 +4,13 @@ extern crate serde_json;
 extern crate splay_tree;
 
 use std::hash;
+use std::cell::Cell;
+use std::cmp::Ordering;
 use std::collections::hash_map::DefaultHasher;
+use std::panic;
+use std::rc::Rc;
+use std::sync::Once;
+use splay_tree::compare::Compare;
 
 fn hash<T: hash::Hash>(x: &T) -> u64 {
     use std::hash::Hasher;
@@ -13,8 +19,49 @@ fn hash<T: hash::Hash>(x: &T) -> u64 {
     hasher.finish()
 }
 
+/// A comparator which panics at the `n`-th comparison after `arm(n)` is called.
+#[derive(Clone, Default)]
+struct Fuse(Rc<Cell<Option<usize>>>);
+impl Fuse {
+    fn arm(&self, n: usize) {
+        static QUIET: Once = Once::new();
+        QUIET.call_once(|| {
+            let hook = panic::take_hook();
+            panic::set_hook(Box::new(move |info| {
+                if info.payload().downcast_ref::<&str>() != Some(&"fuse") {
+                    hook(info);
+                }
+            }));
+        });
+        self.0.set(Some(n));
+    }
+
+    /// Disarms the fuse, and returns `true` if it has blown.
+    fn disarm(&self) -> bool {
+        self.0.replace(None).is_none()
+    }
+}
+impl<T: Ord> Compare<T> for Fuse {
+    fn compare(&self, a: &T, b: &T) -> Ordering {
+        match self.0.get() {
+            Some(0) => {
+                self.0.set(None);
+                panic!("fuse");
+            }
+            Some(n) => self.0.set(Some(n - 1)),
+            None => {}
+        }
+        a.cmp(b)
+    }
+}
+
 mod map {
+    use std::cmp::Ordering;
+    use std::ops::Bound;
     use splay_tree::SplayMap;
+    use splay_tree::compare::{Compare, Natural};
+    use splay_tree::augment::{Count, Counted, Fold, Folded, Lazy, Monoid, Tag};
+    use splay_tree::error::TryReserveError;
     use super::hash;
     #[cfg(feature = "serde")]
     use serde_json::{from_str, to_string};
@@ -80,6 +127,73 @@ mod map {
         );
     }
 
+    #[test]
+    fn into_iter_drops_leftovers_once() {
+        use std::num::NonZeroU32;
+        use std::rc::Rc;
+
+        // None of the key and value types has a valid all-zero bit pattern.
+        let counter = Rc::new(());
+        let names = ["a", "b", "c", "d", "e", "f", "g", "h"];
+        let map: SplayMap<_, _> = (0..8)
+            .map(|i| {
+                let key = Box::new(NonZeroU32::new(i + 1).unwrap());
+                (key, (names[i as usize], Rc::clone(&counter)))
+            })
+            .collect();
+        assert_eq!(Rc::strong_count(&counter), 9);
+
+        let mut iter = map.into_iter();
+        let (k, (name, _)) = iter.next().unwrap();
+        assert_eq!((k.get(), name), (1, "a"));
+        let (k, (name, _)) = iter.next_back().unwrap();
+        assert_eq!((k.get(), name), (8, "h"));
+        assert_eq!(Rc::strong_count(&counter), 7);
+        drop(iter);
+        assert_eq!(Rc::strong_count(&counter), 1);
+
+        let mut map: SplayMap<_, _> = (0..8).map(|i| (i, Rc::clone(&counter))).collect();
+        map.drain().take(3).count();
+        assert!(map.is_empty());
+        assert_eq!(Rc::strong_count(&counter), 1);
+    }
+
+    #[test]
+    fn double_ended_iterator() {
+        let mut map: SplayMap<_, _> = (0..100).map(|n| (n, n * 2)).collect();
+        map.get(&50); // changes the shape of the tree
+
+        let expected = (0..100).rev().map(|n| (n, n * 2)).collect::<Vec<_>>();
+        assert_eq!(
+            map.iter().rev().map(|(k, v)| (*k, *v)).collect::<Vec<_>>(),
+            expected
+        );
+        assert_eq!(map.keys().next_back(), Some(&99));
+        assert_eq!(map.values().next_back(), Some(&198));
+        for v in map.values_mut().rev().take(2) {
+            *v = 0;
+        }
+        assert_eq!(map.iter_mut().next_back(), Some((&99, &mut 0)));
+
+        // both ends meet in the middle
+        for n in 0..101 {
+            let mut iter = map.clone().into_iter();
+            let front = iter.by_ref().take(n).count();
+            let back = iter.rev().count();
+            assert_eq!(front + back, 100);
+        }
+        let mut iter = map.iter();
+        let mut visited = Vec::new();
+        while let Some((k, _)) = iter.next() {
+            visited.push(*k);
+            if let Some((k, _)) = iter.next_back() {
+                visited.push(*k);
+            }
+        }
+        visited.sort();
+        assert_eq!(visited, (0..100).collect::<Vec<_>>());
+    }
+
     #[test]
     fn find_lower_or_upper_bound_key() {
         // small map
@@ -105,6 +219,848 @@ mod map {
         assert_eq!(map.find_upper_bound_key(&999), None);
     }
 
+    #[test]
+    fn range() {
+        use rand::{self, Rng};
+        use std::collections::BTreeMap;
+        use std::ops::Bound::{self, Excluded, Included, Unbounded};
+
+        let mut input = (0..100).map(|n| n * 2).collect::<Vec<_>>();
+        rand::thread_rng().shuffle(&mut input);
+
+        let mut map: SplayMap<_, _> = input.iter().map(|&n| (n, n)).collect();
+        let btree: BTreeMap<_, _> = input.iter().map(|&n| (n, n)).collect();
+
+        let bound = |n: i32| -> Bound<i32> {
+            match n % 3 {
+                0 => Included(n / 3),
+                1 => Excluded(n / 3),
+                _ => Unbounded,
+            }
+        };
+        for _ in 0..1000 {
+            let mut a = rand::thread_rng().gen_range(-10, 620);
+            let mut b = rand::thread_rng().gen_range(-10, 620);
+            if a / 3 > b / 3 {
+                ::std::mem::swap(&mut a, &mut b);
+            }
+            let range = (bound(a), bound(b));
+            if let (Excluded(s), Excluded(e)) = range {
+                if s == e {
+                    continue;
+                }
+            }
+            let expected = btree.range(range).collect::<Vec<_>>();
+            assert_eq!(map.range(range).collect::<Vec<_>>(), expected);
+
+            let mut expected_rev = expected.clone();
+            expected_rev.reverse();
+            assert_eq!(map.range(range).rev().collect::<Vec<_>>(), expected_rev);
+
+            // consumes the range from both ends alternately
+            let mut both = Vec::new();
+            let mut iter = map.range(range);
+            let mut tail = Vec::new();
+            loop {
+                match iter.next() {
+                    None => break,
+                    Some(e) => both.push(e),
+                }
+                match iter.next_back() {
+                    None => break,
+                    Some(e) => tail.push(e),
+                }
+            }
+            both.extend(tail.into_iter().rev());
+            assert_eq!(both, expected);
+        }
+
+        assert_eq!(map.range(7..8).count(), 0);
+        assert_eq!(map.range(..).count(), 100);
+    }
+
+    #[test]
+    fn range_mut() {
+        let mut map: SplayMap<_, _> = (0..10).map(|n| (n, n)).collect();
+        for (_, v) in map.range_mut(3..7).rev() {
+            *v *= 10;
+        }
+        assert_eq!(
+            map.values().cloned().collect::<Vec<_>>(),
+            [0, 1, 2, 30, 40, 50, 60, 7, 8, 9]
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn range_start_greater_than_end() {
+        let mut map: SplayMap<_, _> = (0..10).map(|n| (n, n)).collect();
+        map.range(5..3);
+    }
+
+    #[test]
+    fn order_statistics() {
+        use rand::{self, Rng};
+        use std::ops::Bound::{Excluded, Included};
+
+        let mut rng = rand::thread_rng();
+        let mut map: SplayMap<_, _, Count> = SplayMap::default();
+        let mut sorted: Vec<i32> = Vec::new();
+        for _ in 0..2000 {
+            let k = rng.gen_range(0, 300);
+            if rng.gen() {
+                if map.insert(k, k * 10).is_none() {
+                    let i = sorted.binary_search(&k).unwrap_err();
+                    sorted.insert(i, k);
+                }
+            } else if map.remove(&k).is_some() {
+                let i = sorted.binary_search(&k).unwrap();
+                sorted.remove(i);
+            }
+
+            let k = rng.gen_range(0, 300);
+            assert_eq!(map.rank(&k), sorted.binary_search(&k).ok());
+
+            let i = rng.gen_range(0, sorted.len() + 2);
+            assert_eq!(
+                map.select(i).map(|(&k, &v)| (k, v)),
+                sorted.get(i).map(|&k| (k, k * 10))
+            );
+
+            let mut a = rng.gen_range(-10, 310);
+            let mut b = rng.gen_range(-10, 310);
+            if a > b {
+                ::std::mem::swap(&mut a, &mut b);
+            }
+            let expected = sorted.iter().filter(|&&k| a <= k && k < b).count();
+            assert_eq!(map.count_range(a..b), expected);
+            let expected = sorted.iter().filter(|&&k| a < k && k <= b).count();
+            assert_eq!(map.count_range((Excluded(a), Included(b))), expected);
+        }
+        assert_eq!(map.count_range(..), sorted.len());
+    }
+
+    #[test]
+    fn split_off_and_append() {
+        use rand::{self, Rng};
+        use std::collections::BTreeMap;
+
+        let mut rng = rand::thread_rng();
+        for _ in 0..100 {
+            let size = rng.gen_range(0, 200);
+            let mut input = (0..size).collect::<Vec<_>>();
+            rng.shuffle(&mut input);
+            let mut map: SplayMap<_, _, Count> = input.iter().map(|&n| (n, n)).collect();
+            let mut btree: BTreeMap<_, _> = input.iter().map(|&n| (n, n)).collect();
+
+            let key = rng.gen_range(-5, size + 5);
+            let mut other = map.split_off(&key);
+            let mut other_btree = btree.split_off(&key);
+            assert_eq!(map.iter().collect::<Vec<_>>(), btree.iter().collect::<Vec<_>>());
+            assert_eq!(
+                other.iter().collect::<Vec<_>>(),
+                other_btree.iter().collect::<Vec<_>>()
+            );
+            for i in 0..other.len() {
+                assert_eq!(other.select(i).map(|(&k, _)| k), Some(key.max(0) + i as i32));
+            }
+
+            // both trees are still usable after moving the nodes between the arenas
+            for &n in &input {
+                assert_eq!(map.get(&n).cloned(), btree.get(&n).cloned());
+                assert_eq!(other.get(&n).cloned(), other_btree.get(&n).cloned());
+            }
+            other.insert(key, -1);
+            other_btree.insert(key, -1);
+            map.remove(&(key - 1));
+            btree.remove(&(key - 1));
+
+            if rng.gen() {
+                map.append(&mut other);
+                btree.append(&mut other_btree);
+            } else {
+                other.append(&mut map);
+                other_btree.append(&mut btree);
+                ::std::mem::swap(&mut map, &mut other);
+                ::std::mem::swap(&mut btree, &mut other_btree);
+            }
+            assert!(other.is_empty());
+            assert_eq!(map.len(), btree.len());
+            assert_eq!(map.iter().collect::<Vec<_>>(), btree.iter().collect::<Vec<_>>());
+            assert_eq!(
+                map.iter().rev().collect::<Vec<_>>(),
+                btree.iter().rev().collect::<Vec<_>>()
+            );
+            for (i, (k, _)) in btree.iter().enumerate() {
+                assert_eq!(map.rank(k), Some(i));
+            }
+        }
+    }
+
+    #[test]
+    fn append_overlapping() {
+        let mut a: SplayMap<_, _> = vec![(1, "a"), (3, "b"), (5, "c")].into_iter().collect();
+        let mut b: SplayMap<_, _> = vec![(2, "d"), (3, "e"), (4, "f")].into_iter().collect();
+        a.append(&mut b);
+        assert!(b.is_empty());
+        assert_eq!(
+            a.into_iter().collect::<Vec<_>>(),
+            [(1, "a"), (2, "d"), (3, "e"), (4, "f"), (5, "c")]
+        );
+    }
+
+    #[test]
+    fn merge_with() {
+        use rand::{self, Rng};
+        use std::collections::BTreeMap;
+
+        let mut rng = rand::thread_rng();
+        for _ in 0..100 {
+            let xs = (0..rng.gen_range(0, 50))
+                .map(|_| (rng.gen_range(0, 60), rng.gen_range(0, 10)))
+                .collect::<Vec<_>>();
+            let ys = (0..rng.gen_range(0, 50))
+                .map(|_| (rng.gen_range(0, 60), rng.gen_range(0, 10)))
+                .collect::<Vec<_>>();
+            let mut a: SplayMap<i32, i32, Count> = xs.iter().cloned().collect();
+            let b: SplayMap<i32, i32, Count> = ys.iter().cloned().collect();
+            let mut expected: BTreeMap<_, _> = xs.into_iter().collect();
+            for (k, v) in ys.into_iter().collect::<BTreeMap<_, _>>() {
+                match expected.remove(&k) {
+                    Some(l) if l + v < 10 => {}
+                    Some(l) => {
+                        expected.insert(k, l + v);
+                    }
+                    None => {
+                        expected.insert(k, v);
+                    }
+                }
+            }
+
+            a.merge_with(b, |_, l, r| if l + r < 10 { None } else { Some(l + r) });
+            assert_eq!(
+                a.iter().map(|(k, v)| (*k, *v)).collect::<Vec<_>>(),
+                expected.iter().map(|(k, v)| (*k, *v)).collect::<Vec<_>>()
+            );
+            let mid = a.len() / 2;
+            assert_eq!(a.select(mid), expected.iter().nth(mid));
+            assert_eq!(a.rank(&30), expected.keys().position(|&k| k == 30));
+        }
+    }
+
+    #[test]
+    fn check_invariants() {
+        use rand::{self, Rng};
+
+        let mut rng = rand::thread_rng();
+        let mut map: SplayMap<i32, i32, Count> = SplayMap::default();
+        for _ in 0..2000 {
+            let k = rng.gen_range(0, 200);
+            match rng.gen_range(0, 7) {
+                0 | 1 => {
+                    map.insert(k, k);
+                }
+                2 => {
+                    map.remove(&k);
+                }
+                3 => {
+                    map.find_lower_bound_key(&k);
+                    map.find_less(&k);
+                }
+                4 => {
+                    map.take_smallest();
+                    map.take_largest();
+                }
+                5 => {
+                    let mut rest = map.split_off(&k);
+                    assert_eq!(rest.check_invariants(), Ok(()));
+                    map.append(&mut rest);
+                }
+                _ => {
+                    let len = map.range(k..k + 20).count();
+                    assert_eq!(map.count_range(k..k + 20), len);
+                }
+            }
+            assert_eq!(map.check_invariants(), Ok(()));
+        }
+    }
+
+    #[test]
+    fn panicking_comparator() {
+        use rand::{self, Rng};
+        use std::collections::BTreeSet;
+        use std::panic::{self, AssertUnwindSafe};
+        use super::Fuse;
+
+        let mut rng = rand::thread_rng();
+        for op in 0..7 {
+            for n in 0..30 {
+                let fuse = Fuse::default();
+                let mut keys = (0..100).map(|k| k * 2).collect::<Vec<i32>>();
+                rng.shuffle(&mut keys);
+                let mut map = SplayMap::with_comparator(fuse.clone());
+                map.extend(keys.into_iter().take(50).map(|k| (k, ())));
+                let k = rng.gen_range(0, 200);
+                let before = map.keys().cloned().collect::<BTreeSet<_>>();
+                let after = match op {
+                    0 => before.iter().cloned().chain(Some(k)).collect(),
+                    1 => before.iter().cloned().filter(|&x| x != k).collect(),
+                    4 => before.iter().cloned().filter(|&x| x < k).collect(),
+                    5 | 6 => before.iter().cloned().chain(k..k + 10).collect(),
+                    _ => before.clone(),
+                };
+
+                fuse.arm(n);
+                let result = panic::catch_unwind(AssertUnwindSafe(|| match op {
+                    0 => {
+                        map.insert(k, ());
+                    }
+                    1 => {
+                        map.remove(&k);
+                    }
+                    2 => {
+                        map.get(&k);
+                    }
+                    3 => {
+                        map.range(k..k + 20).count();
+                    }
+                    4 => {
+                        map.split_off(&k);
+                    }
+                    5 => map.extend((k..k + 10).map(|x| (x, ()))),
+                    _ => {
+                        let mut other = SplayMap::with_comparator(fuse.clone());
+                        other.extend((k..k + 10).map(|x| (x, ())));
+                        map.append(&mut other);
+                    }
+                }));
+                let blown = fuse.disarm();
+                assert_eq!(result.is_err(), blown);
+
+                assert_eq!(map.check_invariants(), Ok(()));
+                let keys = map.keys().cloned().collect::<BTreeSet<_>>();
+                assert_eq!(keys.len(), map.len());
+                if !blown {
+                    assert_eq!(keys, after);
+                } else if op != 6 {
+                    // The entries not merged yet are dropped if `append` panics.
+                    assert!(keys.is_superset(&before.intersection(&after).cloned().collect()));
+                    assert!(keys.is_subset(&before.union(&after).cloned().collect()));
+                }
+            }
+        }
+    }
+
+    #[derive(Debug, PartialEq)]
+    struct Sum(i64);
+    impl<K> Monoid<K, i64> for Sum {
+        fn identity() -> Self {
+            Sum(0)
+        }
+        fn lift(_key: &K, value: &i64) -> Self {
+            Sum(*value)
+        }
+        fn combine(&self, other: &Self) -> Self {
+            Sum(self.0 + other.0)
+        }
+    }
+
+    #[test]
+    fn fold_range() {
+        use rand::{self, Rng};
+        use std::collections::BTreeMap;
+        use std::ops::Bound::{Excluded, Included, Unbounded};
+
+        let mut rng = rand::thread_rng();
+        let mut map: SplayMap<_, _, (Count, Fold<Sum>)> = SplayMap::default();
+        let mut btree = BTreeMap::new();
+        for _ in 0..3000 {
+            let k = rng.gen_range(0, 200);
+            let v = rng.gen_range(-100, 100);
+            match rng.gen_range(0, 8) {
+                0 | 1 | 2 => {
+                    assert_eq!(map.insert(k, v), btree.insert(k, v));
+                }
+                3 => {
+                    assert_eq!(map.remove(&k), btree.remove(&k));
+                }
+                4 => {
+                    if let Some(x) = map.get_mut(&k) {
+                        *x = v;
+                    }
+                    if let Some(x) = btree.get_mut(&k) {
+                        *x = v;
+                    }
+                }
+                5 => {
+                    *map.entry(k).or_insert(0) += v;
+                    *btree.entry(k).or_insert(0) += v;
+                }
+                6 => {
+                    for x in map.range_mut(k..k + 10) {
+                        *x.1 += v;
+                    }
+                    for x in btree.range_mut(k..k + 10) {
+                        *x.1 += v;
+                    }
+                }
+                _ => {
+                    if let Some(x) = map.values_mut().next() {
+                        *x = v;
+                    }
+                    if let Some(x) = btree.values_mut().next() {
+                        *x = v;
+                    }
+                }
+            }
+
+            let a = rng.gen_range(-10, 210);
+            let b = rng.gen_range(a, 210);
+            let expected = btree.range(a..b).map(|(_, v)| v).sum();
+            assert_eq!(map.fold_range(a..b), Sum(expected));
+            let expected = btree.range((Excluded(a), Unbounded)).map(|(_, v)| v).sum();
+            assert_eq!(map.fold_range((Excluded(a), Unbounded)), Sum(expected));
+            let expected = btree.range((Unbounded, Included(b))).map(|(_, v)| v).sum();
+            assert_eq!(map.fold_range((Unbounded, Included(b))), Sum(expected));
+            assert_eq!(map.count_range(a..b), btree.range(a..b).count());
+        }
+        assert_eq!(map.fold_range(..), Sum(btree.values().sum()));
+    }
+
+    #[derive(Clone)]
+    struct Add(i64);
+    impl<K> Tag<K, i64, (Count, Fold<Sum>)> for Add {
+        fn apply(&self, _key: &K, value: &mut i64) {
+            *value += self.0;
+        }
+        fn apply_augment(&self, augment: &mut (Count, Fold<Sum>)) {
+            let count = Counted::<K, i64>::count(augment) as i64;
+            Folded::<K, i64>::folded_mut(augment).0 += self.0 * count;
+        }
+        fn compose(&mut self, later: &Self) {
+            self.0 += later.0;
+        }
+    }
+
+    #[test]
+    fn update_range() {
+        use rand::{self, Rng};
+        use std::collections::BTreeMap;
+
+        let mut rng = rand::thread_rng();
+        let mut map: SplayMap<_, _, Lazy<Add, (Count, Fold<Sum>)>> = SplayMap::default();
+        let mut btree = BTreeMap::new();
+        for _ in 0..3000 {
+            let k = rng.gen_range(0, 200);
+            let v = rng.gen_range(-100, 100);
+            match rng.gen_range(0, 6) {
+                0 | 1 => {
+                    assert_eq!(map.insert(k, v), btree.insert(k, v));
+                }
+                2 => {
+                    assert_eq!(map.remove(&k), btree.remove(&k));
+                }
+                3 => {
+                    assert_eq!(map.get(&k), btree.get(&k));
+                }
+                4 => {
+                    assert!(map.range(k..k + 10).eq(btree.range(k..k + 10)));
+                }
+                _ => {
+                    let end = rng.gen_range(k, 210);
+                    map.update_range(k..=end, Add(v));
+                    for x in btree.range_mut(k..=end) {
+                        *x.1 += v;
+                    }
+                }
+            }
+
+            let a = rng.gen_range(-10, 210);
+            let b = rng.gen_range(a, 210);
+            let expected = btree.range(a..b).map(|(_, v)| v).sum();
+            assert_eq!(map.fold_range(a..b), Sum(expected));
+            assert_eq!(map.count_range(a..b), btree.range(a..b).count());
+        }
+        assert_eq!(map.fold_range(..), Sum(btree.values().sum()));
+
+        let mut other = map.split_off(&100);
+        other.update_range(.., Add(1));
+        map.append(&mut other);
+        for x in btree.range_mut(100..) {
+            *x.1 += 1;
+        }
+        assert!(map.iter_mut().map(|(k, v)| (k, &*v)).eq(btree.iter()));
+    }
+
+    #[test]
+    fn splay_policies() {
+        use rand::{self, Rng};
+        use std::collections::BTreeMap;
+        use splay_tree::policy::SplayPolicy;
+
+        let policies = [
+            SplayPolicy::Full,
+            SplayPolicy::Semi,
+            SplayPolicy::Threshold(0),
+            SplayPolicy::Threshold(4),
+            SplayPolicy::Probability(0.0),
+            SplayPolicy::Probability(0.5),
+            SplayPolicy::Probability(1.0),
+        ];
+        let mut rng = rand::thread_rng();
+        for &policy in &policies {
+            let mut map: SplayMap<_, _, Lazy<Add, (Count, Fold<Sum>)>> = SplayMap::default();
+            let mut plain = SplayMap::new();
+            map.set_splay_policy(policy);
+            plain.set_splay_policy(policy);
+            let mut btree = BTreeMap::new();
+            for _ in 0..1000 {
+                let k = rng.gen_range(0, 100);
+                let v = rng.gen_range(-100, 100);
+                match rng.gen_range(0, 6) {
+                    0 | 1 => {
+                        map.insert(k, v);
+                        plain.insert(k, v);
+                        btree.insert(k, v);
+                    }
+                    2 => {
+                        assert_eq!(map.get(&k), btree.get(&k));
+                        assert_eq!(plain.get(&k), btree.get(&k));
+                        assert_eq!(plain.contains_key(&k), btree.contains_key(&k));
+                    }
+                    3 => {
+                        if let Some(x) = map.get_mut(&k) {
+                            *x = v;
+                        }
+                        if let Some(x) = plain.get_mut(&k) {
+                            *x = v;
+                        }
+                        if let Some(x) = btree.get_mut(&k) {
+                            *x = v;
+                        }
+                    }
+                    4 => {
+                        assert_eq!(map.remove(&k), btree.remove(&k));
+                        plain.remove(&k);
+                    }
+                    _ => {
+                        map.update_range(k..k + 10, Add(v));
+                        for x in btree.range_mut(k..k + 10) {
+                            *x.1 += v;
+                        }
+                        for (key, x) in plain.range_mut(k..k + 10) {
+                            *x = btree[key];
+                        }
+                    }
+                }
+                assert_eq!(map.check_invariants(), Ok(()));
+                assert_eq!(plain.check_invariants(), Ok(()));
+                let a = rng.gen_range(-10, 110);
+                let b = rng.gen_range(a, 110);
+                let expected = btree.range(a..b).map(|(_, v)| v).sum();
+                assert_eq!(map.fold_range(a..b), Sum(expected));
+                assert_eq!(map.count_range(a..b), btree.range(a..b).count());
+            }
+            assert!(map.iter_mut().map(|(k, v)| (k, &*v)).eq(btree.iter()));
+            assert!(plain.iter().eq(btree.iter()));
+            assert_eq!(map.splay_policy(), policy);
+            assert_eq!(map.split_off(&50).splay_policy(), policy);
+        }
+    }
+
+    #[test]
+    fn append_keeps_splay_policy() {
+        use splay_tree::SplaySet;
+        use splay_tree::policy::SplayPolicy;
+
+        // Into an empty receiver, a smaller disjoint one, and a smaller overlapping one.
+        for &(start, end) in &[(0, 0), (0, 3), (15, 25)] {
+            let mut map: SplayMap<_, _> = (start..end).map(|k| (k, k)).collect();
+            map.set_splay_policy(SplayPolicy::Semi);
+            let mut other: SplayMap<_, _> = (3..20).map(|k| (k, k)).collect();
+            map.append(&mut other);
+            assert_eq!(map.splay_policy(), SplayPolicy::Semi);
+            assert_eq!(other.splay_policy(), SplayPolicy::Full);
+            assert_eq!(map.check_invariants(), Ok(()));
+        }
+
+        let mut set: SplaySet<i32> = SplaySet::new();
+        set.set_splay_policy(SplayPolicy::Threshold(2));
+        set.append(&mut (0..10).collect());
+        assert_eq!(set.splay_policy(), SplayPolicy::Threshold(2));
+        set.append(&mut (10..100).collect());
+        assert_eq!(set.splay_policy(), SplayPolicy::Threshold(2));
+        assert!(set.iter().cloned().eq(0..100));
+    }
+
+    #[test]
+    #[should_panic]
+    fn invalid_splay_probability() {
+        use splay_tree::policy::SplayPolicy;
+
+        let mut map: SplayMap<i32, i32> = SplayMap::new();
+        map.set_splay_policy(SplayPolicy::Probability(1.5));
+    }
+
+    #[test]
+    fn read_keys_with_pending_updates() {
+        let mut map: SplayMap<_, _, Lazy<Add, (Count, Fold<Sum>)>> =
+            (0..100).map(|k| (k, 0)).collect();
+        map.update_range(10..20, Add(1));
+        assert!(map.keys().cloned().eq(0..100));
+        assert!(map.peek_contains_key(&15));
+        assert_eq!(map.len(), 100);
+        assert_eq!(map.get(&15), Some(&1));
+    }
+
+    #[test]
+    fn non_splaying_lookups() {
+        use rand::{self, Rng};
+        use std::collections::BTreeMap;
+        use std::sync::Arc;
+        use std::thread;
+
+        let mut rng = rand::thread_rng();
+        let mut map = SplayMap::new();
+        let mut expected = BTreeMap::new();
+        assert_eq!(map.first(), None);
+        assert_eq!(map.last(), None);
+        assert_eq!(map.peek_lower_bound_key(&0), None);
+        for _ in 0..500 {
+            let k = rng.gen_range(0, 1000);
+            map.insert(k * 2, k);
+            expected.insert(k * 2, k);
+        }
+
+        let before = format!("{:?}", map);
+        for k in 0..2001 {
+            assert_eq!(map.peek_get(&k), expected.get(&k));
+            assert_eq!(map.peek_contains_key(&k), expected.contains_key(&k));
+            assert_eq!(
+                map.peek_lower_bound_key(&k),
+                expected.range(k..).next().map(|(k, _)| k)
+            );
+            assert_eq!(
+                map.peek_upper_bound_key(&k),
+                expected.range(k + 1..).next().map(|(k, _)| k)
+            );
+        }
+        assert_eq!(map.first(), expected.iter().next());
+        assert_eq!(map.last(), expected.iter().next_back());
+        assert_eq!(format!("{:?}", map), before);
+
+        let map = Arc::new(map);
+        let handles = (0..4)
+            .map(|i| {
+                let map = map.clone();
+                thread::spawn(move || (0..100).filter(|k| map.peek_contains_key(&(k * 4 + i))).count())
+            })
+            .collect::<Vec<_>>();
+        let found = handles.into_iter().map(|h| h.join().unwrap()).sum::<usize>();
+        assert_eq!(found, expected.range(..400).count());
+    }
+
+    #[test]
+    fn find_less_and_greater() {
+        use rand::{self, Rng};
+        use std::collections::BTreeMap;
+
+        let mut rng = rand::thread_rng();
+        let mut map: SplayMap<i64, i64, Fold<Sum>> = SplayMap::default();
+        let mut expected = BTreeMap::new();
+        assert_eq!(map.find_less(&0), None);
+        assert_eq!(map.find_less_or_equal(&0), None);
+        assert_eq!(map.find_greater(&0), None);
+        for _ in 0..200 {
+            let k = rng.gen_range(0, 500);
+            map.insert(k * 2, k);
+            expected.insert(k * 2, k);
+        }
+
+        for _ in 0..1000 {
+            let k = rng.gen_range(-1, 1002);
+            assert_eq!(map.find_less(&k), expected.range(..k).next_back());
+            assert_eq!(map.find_less_or_equal(&k), expected.range(..=k).next_back());
+            assert_eq!(map.find_greater(&k), expected.range(k + 1..).next());
+
+            let d = rng.gen_range(-10, 10);
+            let found = match rng.gen_range(0, 3) {
+                0 => map.find_less_mut(&k),
+                1 => map.find_less_or_equal_mut(&k),
+                _ => map.find_greater_mut(&k),
+            };
+            if let Some((found, v)) = found {
+                *v += d;
+                *expected.get_mut(found).unwrap() += d;
+            }
+
+            let a = rng.gen_range(0, 1000);
+            let b = rng.gen_range(a, 1001);
+            assert_eq!(map.fold_range(a..b), Sum(expected.range(a..b).map(|(_, v)| v).sum()));
+        }
+        assert_eq!(map.into_iter().collect::<Vec<_>>(), expected.into_iter().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn cursor() {
+        use rand::{self, Rng};
+
+        let mut rng = rand::thread_rng();
+        let mut map: SplayMap<u32, u32, Count> = (0..100).map(|k| (k * 1000, k)).collect();
+        let mut expected = (0..100).map(|k| (k * 1000, k)).collect::<Vec<_>>();
+        for _ in 0..20 {
+            let start = rng.gen_range(0, 110) * 1000;
+            {
+                let mut cursor = map.cursor_at_mut(&start);
+                let mut position = expected.iter().position(|&(k, _)| k >= start);
+                assert_eq!(cursor.key(), position.map(|i| &expected[i].0));
+                for _ in 0..100 {
+                    match rng.gen_range(0, 6) {
+                        0 => {
+                            cursor.move_next();
+                            position = match position {
+                                None if expected.is_empty() => None,
+                                None => Some(0),
+                                Some(i) if i + 1 == expected.len() => None,
+                                Some(i) => Some(i + 1),
+                            };
+                        }
+                        1 => {
+                            cursor.move_prev();
+                            position = match position {
+                                None => expected.len().checked_sub(1),
+                                Some(0) => None,
+                                Some(i) => Some(i - 1),
+                            };
+                        }
+                        2 => {
+                            let removed = position.map(|i| expected.remove(i));
+                            assert_eq!(cursor.remove_current(), removed);
+                            position = position.filter(|&i| i < expected.len());
+                        }
+                        3 => {
+                            let v = rng.gen();
+                            if let Some(i) = position {
+                                expected[i].1 = v;
+                            }
+                            cursor.value_mut().map(|x| *x = v);
+                        }
+                        op => {
+                            // Inserts a key in the middle of the neighbouring keys, if there is room.
+                            let (lft, rgt) = match (op, position) {
+                                (4, Some(i)) => (Some(i), Some(i + 1)),
+                                (4, None) => (None, Some(0)),
+                                (_, Some(i)) => (i.checked_sub(1), Some(i)),
+                                (_, None) => (expected.len().checked_sub(1), None),
+                            };
+                            let lo = lft.map_or(0, |i| expected[i].0 + 1);
+                            let hi = rgt
+                                .filter(|&i| i < expected.len())
+                                .map_or(200_000, |i| expected[i].0);
+                            if lo >= hi {
+                                continue;
+                            }
+                            let k = rng.gen_range(lo, hi);
+                            let v = rng.gen();
+                            let at = rgt.filter(|&i| i < expected.len()).unwrap_or(expected.len());
+                            expected.insert(at, (k, v));
+                            if op == 4 {
+                                cursor.insert_after(k, v);
+                            } else {
+                                cursor.insert_before(k, v);
+                                position = position.map(|i| i + 1);
+                            }
+                        }
+                    }
+                    assert_eq!(cursor.key(), position.map(|i| &expected[i].0));
+                    assert_eq!(cursor.value(), position.map(|i| &expected[i].1));
+                }
+            }
+            assert_eq!(map.iter().map(|(k, v)| (*k, *v)).collect::<Vec<_>>(), expected);
+            for (i, &(k, _)) in expected.iter().enumerate() {
+                assert_eq!(map.rank(&k), Some(i));
+            }
+        }
+
+        let mut cursor = map.cursor_back();
+        let mut keys = Vec::new();
+        while let Some(&k) = cursor.key() {
+            keys.push(k);
+            cursor.move_prev();
+        }
+        cursor.move_prev();
+        assert_eq!(cursor.key(), expected.last().map(|e| &e.0));
+        keys.reverse();
+        assert_eq!(keys, expected.iter().map(|e| e.0).collect::<Vec<_>>());
+    }
+
+    #[test]
+    #[should_panic]
+    fn cursor_insert_out_of_order() {
+        let mut map: SplayMap<_, _> = vec![(1, ()), (3, ())].into_iter().collect();
+        map.cursor_front_mut().insert_after(4, ());
+    }
+
+    #[test]
+    fn retain_extract_if_and_drain() {
+        use rand::{self, Rng};
+        use std::collections::BTreeMap;
+
+        let mut rng = rand::thread_rng();
+        let mut map: SplayMap<u32, u32, Count> = SplayMap::default();
+        let mut expected = BTreeMap::new();
+        for _ in 0..1000 {
+            let (k, v) = (rng.gen_range(0, 2000), rng.gen());
+            map.insert(k, v);
+            expected.insert(k, v);
+        }
+
+        map.retain(|&k, v| {
+            *v = v.wrapping_add(1);
+            k % 3 != 0
+        });
+        expected = expected
+            .into_iter()
+            .filter(|&(k, _)| k % 3 != 0)
+            .map(|(k, v): (u32, u32)| (k, v.wrapping_add(1)))
+            .collect();
+        assert_eq!(map.len(), expected.len());
+        assert!(map.iter().zip(expected.iter()).all(|(a, b)| a == b));
+        for (i, k) in expected.keys().enumerate() {
+            assert_eq!(map.rank(k), Some(i));
+        }
+
+        // Dropping the iterator in the middle keeps the rest of the entries.
+        let extracted = map
+            .extract_if(|&k, _| k % 2 == 0)
+            .take(10)
+            .collect::<Vec<_>>();
+        let even = expected
+            .iter()
+            .filter(|&(k, _)| k % 2 == 0)
+            .map(|(&k, &v)| (k, v))
+            .take(10)
+            .collect::<Vec<_>>();
+        assert_eq!(extracted, even);
+        for &(k, _) in &even {
+            expected.remove(&k);
+        }
+        assert!(map.iter().zip(expected.iter()).all(|(a, b)| a == b));
+        for (i, k) in expected.keys().enumerate() {
+            assert_eq!(map.select(i).map(|(k, _)| *k), Some(*k));
+        }
+        map.insert(1, 1);
+        expected.insert(1, 1);
+
+        assert_eq!(
+            map.drain().rev().collect::<Vec<_>>(),
+            expected.into_iter().rev().collect::<Vec<_>>()
+        );
+        assert!(map.is_empty());
+        map.insert(3, 4);
+        assert_eq!(map.get(&3), Some(&4));
+    }
+
     #[test]
     fn remove() {
         let mut map = SplayMap::new();
@@ -129,6 +1085,70 @@ mod map {
         assert_eq!(count.get("c"), Some(&1));
     }
 
+    #[test]
+    fn entry_api() {
+        use splay_tree::map::Entry;
+
+        let mut groups: SplayMap<usize, Vec<&str>> = SplayMap::new();
+        for w in vec!["a", "bb", "cc", "d", "eee"] {
+            groups.entry(w.len()).or_default().push(w);
+        }
+        assert_eq!(groups.get(&2), Some(&vec!["bb", "cc"]));
+
+        let mut map = SplayMap::new();
+        map.entry(1).and_modify(|v| *v += 10).or_insert(1);
+        map.entry(1).and_modify(|v| *v += 10).or_insert(1);
+        assert_eq!(*map.entry(2).or_insert_with_key(|k| k * 100), 200);
+        assert_eq!(map.get(&1), Some(&11));
+
+        {
+            let mut e = map.entry(3).insert_entry(30);
+            assert_eq!(*e.key(), 3);
+            assert_eq!(e.insert(31), 30);
+        }
+        assert_eq!(map.entry(3).insert_entry(32).remove_entry(), (3, 32));
+        match map.entry(4) {
+            Entry::Vacant(e) => assert_eq!(e.into_key(), 4),
+            Entry::Occupied(_) => unreachable!(),
+        }
+        match map.entry(1) {
+            Entry::Occupied(e) => assert_eq!(e.remove_entry(), (1, 11)),
+            Entry::Vacant(_) => unreachable!(),
+        }
+        assert_eq!(map.into_iter().collect::<Vec<_>>(), [(2, 200)]);
+
+        let mut map = SplayMap::with_comparator(CaseInsensitive);
+        map.insert("foo".to_string(), 1);
+        map.insert("bar".to_string(), 2);
+        match map.entry("FOO".to_string()) {
+            Entry::Occupied(e) => assert_eq!(e.replace_key("FOO".to_string()), "foo"),
+            Entry::Vacant(_) => unreachable!(),
+        }
+        match map.entry("baz".to_string()) {
+            Entry::Vacant(e) => {
+                let e = e.insert_entry(3);
+                assert_eq!(e.replace_key("BAZ".to_string()), "baz");
+            }
+            Entry::Occupied(_) => unreachable!(),
+        }
+        assert_eq!(
+            map.into_iter().collect::<Vec<_>>(),
+            [("bar".to_string(), 2), ("BAZ".to_string(), 3), ("FOO".to_string(), 1)]
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn replace_key_with_different_key() {
+        use splay_tree::map::Entry;
+
+        let mut map = SplayMap::new();
+        map.insert(1, 1);
+        if let Entry::Occupied(e) = map.entry(1) {
+            e.replace_key(2);
+        }
+    }
+
     #[test]
     fn extend() {
         let mut map = SplayMap::new();
@@ -171,6 +1191,194 @@ mod map {
         assert!(map.is_empty());
     }
 
+    #[test]
+    #[cfg_attr(feature = "check-invariants", ignore)]
+    fn index_width() {
+        let mut map: SplayMap<u32, u32, (), Natural, u16> = SplayMap::default();
+        for i in 0..65535 {
+            map.insert(i, i);
+        }
+        assert_eq!(map.len(), 65535);
+        assert_eq!(map.insert(100, 0), Some(100));
+        for i in (0..65535).step_by(2) {
+            assert_eq!(map.remove(&i), Some(if i == 100 { 0 } else { i }));
+        }
+        assert_eq!(map.iter().count(), 32767);
+
+        let mut map: SplayMap<_, _, (), Natural, u64> = SplayMap::default();
+        map.insert("foo", 1);
+        map.insert("bar", 2);
+        assert_eq!(map.remove("foo"), Some(1));
+        assert_eq!(map.into_iter().collect::<Vec<_>>(), [("bar", 2)]);
+    }
+
+    #[test]
+    fn capacity() {
+        let mut map = SplayMap::with_capacity(10);
+        assert!(map.capacity() >= 10);
+        for i in 0..100 {
+            map.insert(i, i);
+        }
+        map.retain(|k, _| *k < 5);
+        map.shrink_to_fit();
+        assert!(map.capacity() < 100);
+        assert_eq!(map.try_reserve(20), Ok(()));
+        assert!(map.capacity() >= 25);
+
+        let mut map: SplayMap<u32, u32, (), Natural, usize> = SplayMap::default();
+        match map.try_reserve(usize::max_value()) {
+            Err(TryReserveError::AllocError(_)) => {}
+            other => panic!("{:?}", other),
+        }
+
+        let mut map: SplayMap<u32, u32, (), Natural, u16> = SplayMap::default();
+        assert_eq!(
+            map.try_reserve(65536),
+            Err(TryReserveError::CapacityOverflow)
+        );
+        for i in 0..65535 {
+            map.insert(i, i);
+        }
+        assert_eq!(map.try_reserve(0), Ok(()));
+        assert_eq!(map.try_reserve(1), Err(TryReserveError::CapacityOverflow));
+        assert_eq!(
+            map.try_insert(65535, 0),
+            Err(TryReserveError::CapacityOverflow)
+        );
+        assert_eq!(map.try_insert(65534, 0), Ok(Some(65534)));
+        assert_eq!(map.len(), 65535);
+        assert_eq!(map.get(&65534), Some(&0));
+    }
+
+    #[test]
+    fn from_sorted_iter_and_extend() {
+        use std::collections::BTreeMap;
+
+        let mut map: SplayMap<_, _, Count> = SplayMap::from_sorted_iter((0..1000).map(|n| (n, n)));
+        assert_eq!(map.len(), 1000);
+        assert_eq!(map.select(500), Some((&500, &500)));
+        assert_eq!(map.count_range(100..200), 100);
+
+        // ascending runs, descending runs and duplicates mixed together
+        let input = (2000..2500)
+            .chain((1000..1500).rev())
+            .chain(1500..1600)
+            .chain(vec![1600, 1600, 1599, 3000, 3001])
+            .map(|n| (n, n * 2))
+            .collect::<Vec<_>>();
+        map.extend(input.iter().cloned());
+        let mut btree: BTreeMap<_, _> = (0..1000).map(|n| (n, n)).collect();
+        btree.extend(input.iter().cloned());
+        assert_eq!(map.len(), btree.len());
+        assert_eq!(
+            map.iter().map(|(k, v)| (*k, *v)).collect::<Vec<_>>(),
+            btree.into_iter().collect::<Vec<_>>()
+        );
+        assert_eq!(map.select(1000), Some((&1000, &2000)));
+        assert_eq!(map.count_range(1400..2100), 301);
+        assert_eq!(map.remove(&2499), Some(4998));
+
+        let map: SplayMap<_, _> = input.into_iter().collect();
+        assert_eq!(map.len(), 1103);
+        assert_eq!(map.keys().next(), Some(&1000));
+    }
+
+    #[cfg(debug_assertions)]
+    #[test]
+    #[should_panic]
+    fn from_sorted_iter_unsorted() {
+        SplayMap::<_, _>::from_sorted_iter(vec![(1, 1), (3, 3), (2, 2)]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn reserve_exceeded() {
+        let mut map: SplayMap<u32, u32, (), Natural, u16> = SplayMap::default();
+        map.insert(0, 0);
+        map.reserve(65535);
+    }
+
+    #[test]
+    #[should_panic]
+    #[cfg_attr(feature = "check-invariants", ignore)]
+    fn index_width_exceeded() {
+        let mut map: SplayMap<u32, (), (), Natural, u16> = SplayMap::default();
+        for i in 0..65536 {
+            map.insert(i, ());
+        }
+    }
+
+    #[derive(Debug, Clone, Default)]
+    struct CaseInsensitive;
+    impl Compare<str> for CaseInsensitive {
+        fn compare(&self, a: &str, b: &str) -> Ordering {
+            a.to_lowercase().cmp(&b.to_lowercase())
+        }
+    }
+    impl Compare<String> for CaseInsensitive {
+        fn compare(&self, a: &String, b: &String) -> Ordering {
+            Compare::<str>::compare(self, a, b)
+        }
+    }
+
+    #[test]
+    fn comparator() {
+        let mut map = SplayMap::with_comparator(CaseInsensitive);
+        assert_eq!(map.insert("foo".to_string(), 1), None);
+        assert_eq!(map.insert("Bar".to_string(), 2), None);
+        assert_eq!(map.insert("FOO".to_string(), 3), Some(1));
+        assert_eq!(map.insert("baz".to_string(), 4), None);
+        assert_eq!(map.len(), 3);
+
+        assert_eq!(map.get("Foo"), Some(&3));
+        assert_eq!(map.find_lower_bound_key("BAZ").map(|k| k.as_str()), Some("baz"));
+        assert_eq!(map.find_upper_bound_key("BAZ").map(|k| k.as_str()), Some("foo"));
+        assert_eq!(
+            map.range::<str, _>((Bound::Included("BAR"), Bound::Excluded("FOO")))
+                .map(|(k, _)| k.as_str())
+                .collect::<Vec<_>>(),
+            ["Bar", "baz"]
+        );
+
+        *map.entry("BAR".to_string()).or_insert(0) += 10;
+        assert_eq!(map.get("bar"), Some(&12));
+
+        let mut other = map.split_off("Baz");
+        assert_eq!(map.keys().collect::<Vec<_>>(), ["Bar"]);
+        assert_eq!(other.keys().collect::<Vec<_>>(), ["baz", "foo"]);
+        other.insert("QUX".to_string(), 5);
+        map.append(&mut other);
+        assert_eq!(map.keys().collect::<Vec<_>>(), ["Bar", "baz", "foo", "QUX"]);
+
+        map.clear();
+        map.insert("b".to_string(), 0);
+        map.insert("A".to_string(), 0);
+        assert_eq!(map.keys().collect::<Vec<_>>(), ["A", "b"]);
+    }
+
+    #[test]
+    fn closure_comparator() {
+        let mut map = SplayMap::with_comparator(|a: &f64, b: &f64| a.partial_cmp(b).unwrap());
+        map.insert(0.5, "a");
+        map.insert(-1.5, "b");
+        map.insert(2.0, "c");
+        assert_eq!(map.remove(&0.5), Some("a"));
+        assert_eq!(map.into_iter().collect::<Vec<_>>(), [(-1.5, "b"), (2.0, "c")]);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn comparator_serde() {
+        let mut map = SplayMap::with_comparator(CaseInsensitive);
+        map.insert("b".to_string(), 1);
+        map.insert("A".to_string(), 2);
+
+        let mut ser_map: SplayMap<String, i32, (), CaseInsensitive> =
+            from_str(&to_string(&map).unwrap()).unwrap();
+        assert_eq!(ser_map, map);
+        assert_eq!(ser_map.get("a"), Some(&2));
+    }
+
     #[cfg(feature = "serde")]
     #[test]
     fn map_serde() {
@@ -183,9 +1391,39 @@ mod map {
         let ser_map: SplayMap<_, _> = from_str(&to_string(&map).unwrap()).unwrap();
         assert_eq!(ser_map, map);
     }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn map_serde_format() {
+        use std::collections::BTreeMap;
+
+        let btree: BTreeMap<_, _> = (0..100).map(|n| (n.to_string(), n)).collect();
+        let mut map: SplayMap<_, _, Count> = btree.clone().into_iter().collect();
+        map.get("50");
+        let json = to_string(&map).unwrap();
+        assert_eq!(json, to_string(&btree).unwrap());
+        let mut map: SplayMap<String, i32, Count> = from_str(&json).unwrap();
+        assert_eq!(map.select(10), Some((&"18".to_string(), &18)));
+        let json = to_string(&map).unwrap();
+        assert_eq!(from_str::<BTreeMap<String, i32>>(&json).unwrap(), btree);
+
+        assert!(from_str::<SplayMap<i32, i32>>(r#"{"1": 1, "3": 3, "2": 2}"#).is_err());
+        assert!(from_str::<SplayMap<i32, i32>>(r#"{"1": 1, "1": 2}"#).is_err());
+        let json = to_string(&(0..65536).map(|n| (n, 0)).collect::<BTreeMap<_, _>>()).unwrap();
+        assert!(from_str::<SplayMap<i32, i32, (), Natural, u16>>(&json).is_err());
+        let map: SplayMap<i32, i32> = from_str("{}").unwrap();
+        assert!(map.is_empty());
+
+        let json = to_string(&(0..100).map(|k| (k, 1)).collect::<BTreeMap<_, _>>()).unwrap();
+        let mut map: SplayMap<i32, i64, Lazy<Add, (Count, Fold<Sum>)>> =
+            from_str(&json).unwrap();
+        map.update_range(10..20, Add(1));
+        assert_eq!(map.fold_range(..), Sum(110));
+    }
 }
 
 mod set {
+    use std::cmp::Ordering;
     use splay_tree::SplaySet;
     use super::hash;
     #[cfg(feature = "serde")]
@@ -203,6 +1441,21 @@ mod set {
         assert!(set.is_empty());
     }
 
+    #[test]
+    fn from_sorted_iter_and_extend() {
+        let mut set: SplaySet<_> = SplaySet::from_sorted_iter(0..10);
+        set.extend(&[10, 11, 5, 12, 20, 19]);
+        set.extend(vec![30, 31]);
+        assert_eq!(
+            set.iter().cloned().collect::<Vec<_>>(),
+            [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 19, 20, 30, 31]
+        );
+        assert!(set.contains(&19));
+
+        let set: SplaySet<_> = (0..100).chain(50..150).collect();
+        assert_eq!(set.len(), 150);
+    }
+
     #[test]
     fn insert_and_replace_contains() {
         let mut set = SplaySet::new();
@@ -249,6 +1502,39 @@ mod set {
         assert_eq!(set.into_iter().collect::<Vec<_>>(), ["bar", "baz", "foo"]);
     }
 
+    #[test]
+    fn into_iter_drops_leftovers_once() {
+        use std::rc::Rc;
+
+        let items = (0..8).map(Rc::new).collect::<Vec<_>>();
+        let set: SplaySet<_> = items.iter().cloned().collect();
+        let mut iter = set.into_iter();
+        assert_eq!(iter.next().as_ref().map(|x| **x), Some(0));
+        assert_eq!(iter.next_back().as_ref().map(|x| **x), Some(7));
+        drop(iter);
+        assert!(items.iter().all(|x| Rc::strong_count(x) == 1));
+
+        let set: SplaySet<_> = items.iter().cloned().collect();
+        assert_eq!(set.into_iter().map(|x| *x).collect::<Vec<_>>(), [0, 1, 2, 3, 4, 5, 6, 7]);
+        assert!(items.iter().all(|x| Rc::strong_count(x) == 1));
+    }
+
+    #[test]
+    fn double_ended_iterator() {
+        let set: SplaySet<_> = vec!["foo", "bar", "baz"].into_iter().collect();
+        assert_eq!(
+            set.iter().rev().cloned().collect::<Vec<_>>(),
+            ["foo", "baz", "bar"]
+        );
+
+        let mut iter = set.into_iter();
+        assert_eq!(iter.next_back(), Some("foo"));
+        assert_eq!(iter.next(), Some("bar"));
+        assert_eq!(iter.next_back(), Some("baz"));
+        assert_eq!(iter.next(), None);
+        assert_eq!(iter.next_back(), None);
+    }
+
     #[test]
     fn find_lower_or_upper_bound() {
         // small set
@@ -272,6 +1558,28 @@ mod set {
         assert_eq!(set.find_upper_bound(&999), None);
     }
 
+    #[test]
+    fn range() {
+        use std::ops::Bound::{Excluded, Included};
+
+        let mut set: SplaySet<_> = vec!["foo", "bar", "baz", "qux"].into_iter().collect();
+        assert_eq!(set.range("bar".."foo").collect::<Vec<_>>(), [&"bar", &"baz"]);
+        assert_eq!(
+            set.range::<&str, _>((Excluded("bar"), Included("qux")))
+                .rev()
+                .collect::<Vec<_>>(),
+            [&"qux", &"foo", &"baz"]
+        );
+        assert_eq!(set.range("a".."b").count(), 0);
+        assert_eq!(set.range("zzz"..).count(), 0);
+
+        let mut set: SplaySet<_> = (0..1000).collect();
+        let mut iter = set.range(100..200);
+        assert_eq!(iter.next(), Some(&100));
+        assert_eq!(iter.next_back(), Some(&199));
+        assert_eq!(iter.count(), 98);
+    }
+
     #[test]
     fn remove_and_take() {
         let mut set = SplaySet::new();
@@ -368,6 +1676,212 @@ mod set {
         assert!(!d.is_superset(&c));
     }
 
+    #[test]
+    fn owned_set_operations() {
+        use rand::{self, Rng};
+        use std::collections::BTreeSet;
+        use splay_tree::augment::Count;
+
+        let mut rng = rand::thread_rng();
+        for _ in 0..100 {
+            let xs = (0..rng.gen_range(0, 50))
+                .map(|_| rng.gen_range(0, 60))
+                .collect::<Vec<_>>();
+            let ys = (0..rng.gen_range(0, 50))
+                .map(|_| rng.gen_range(0, 60))
+                .collect::<Vec<_>>();
+            let a: SplaySet<i32, Count> = xs.iter().cloned().collect();
+            let b: SplaySet<i32, Count> = ys.iter().cloned().collect();
+            let x: BTreeSet<_> = xs.into_iter().collect();
+            let y: BTreeSet<_> = ys.into_iter().collect();
+
+            let mut c = a.clone();
+            c.union_with(b.clone());
+            assert!(c.iter().eq(x.union(&y)));
+            let mid = c.len() / 2;
+            assert_eq!(c.select(mid), x.union(&y).nth(mid));
+            assert!((a.clone() | b.clone()).iter().eq(x.union(&y)));
+
+            let mut c = a.clone();
+            c.intersect_with(&b);
+            assert!(c.iter().eq(x.intersection(&y)));
+            assert_eq!(
+                c.count_range(10..40),
+                x.intersection(&y).filter(|&&n| 10 <= n && n < 40).count()
+            );
+            assert!((a.clone() & b.clone()).iter().eq(x.intersection(&y)));
+
+            let mut c = a.clone();
+            c -= &b;
+            assert!(c.iter().eq(x.difference(&y)));
+            assert!((a.clone() - b.clone()).iter().eq(x.difference(&y)));
+
+            let mut c = a.clone();
+            c ^= b.clone();
+            assert!(c.iter().eq(x.symmetric_difference(&y)));
+            assert!((a.clone() ^ b.clone()).iter().eq(x.symmetric_difference(&y)));
+            c.insert(100);
+            assert!(c.contains(&100));
+        }
+
+        let mut a = SplaySet::with_comparator(reverse);
+        a.extend(vec![1, 2, 3]);
+        let mut b = SplaySet::with_comparator(reverse);
+        b.extend(vec![3, 4, 5]);
+        a |= b;
+        assert_eq!(a.iter().cloned().collect::<Vec<_>>(), [5, 4, 3, 2, 1]);
+    }
+
+    fn reverse(a: &i32, b: &i32) -> Ordering {
+        b.cmp(a)
+    }
+
+    #[test]
+    fn comparator() {
+        let mut a = SplaySet::with_comparator(reverse);
+        a.extend(vec![1, 2, 3]);
+        let mut b = SplaySet::with_comparator(reverse);
+        b.extend(vec![3, 4, 5]);
+
+        assert_eq!(a.iter().cloned().collect::<Vec<_>>(), [3, 2, 1]);
+        assert_eq!(a.find_lower_bound(&4), Some(&3));
+        assert_eq!(a.find_upper_bound(&3), Some(&2));
+
+        assert_eq!(a.difference(&b).cloned().collect::<Vec<_>>(), [2, 1]);
+        assert_eq!((&a - &b).into_iter().collect::<Vec<_>>(), [2, 1]);
+        assert_eq!(
+            a.symmetric_difference(&b).cloned().collect::<Vec<_>>(),
+            [5, 4, 2, 1]
+        );
+        assert_eq!(a.intersection(&b).cloned().collect::<Vec<_>>(), [3]);
+        assert_eq!((&a | &b).into_iter().collect::<Vec<_>>(), [5, 4, 3, 2, 1]);
+        assert!(!a.is_disjoint(&b));
+
+        let c = a.split_off(&2);
+        assert_eq!(a.iter().cloned().collect::<Vec<_>>(), [3]);
+        assert_eq!(c.iter().cloned().collect::<Vec<_>>(), [2, 1]);
+    }
+
+    #[test]
+    fn retain_extract_if_and_drain() {
+        let mut set: SplaySet<_> = (0..100).collect();
+        set.retain(|x| x % 2 == 0);
+        assert_eq!(set.len(), 50);
+        assert!(set.contains(&98));
+        assert!(!set.contains(&99));
+
+        let mut extracted = set.extract_if(|x| x % 3 == 0);
+        assert_eq!(extracted.next(), Some(0));
+        assert_eq!(extracted.next(), Some(6));
+        drop(extracted);
+        assert_eq!(set.len(), 48);
+        assert_eq!(set.iter().take(3).cloned().collect::<Vec<_>>(), [2, 4, 8]);
+
+        assert_eq!(set.drain().count(), 48);
+        assert!(set.is_empty());
+    }
+
+    #[test]
+    fn order_statistics() {
+        use rand::{self, Rng};
+        use splay_tree::augment::Count;
+
+        let mut input = (0..500).map(|n| n * 2).collect::<Vec<_>>();
+        rand::thread_rng().shuffle(&mut input);
+
+        let mut set: SplaySet<_, Count> = input.iter().cloned().collect();
+        for i in 0..500 {
+            assert_eq!(set[i], i * 2);
+        }
+        for &n in &input {
+            assert_eq!(set.rank(&n), Some(n / 2));
+            assert_eq!(set.rank(&(n + 1)), None);
+            assert_eq!(set.select(n / 2), Some(&n));
+            assert_eq!(set.count_range(n..), 500 - n / 2);
+            assert_eq!(set.count_range(..=n + 1), n / 2 + 1);
+        }
+        assert_eq!(set.select(500), None);
+
+        for &n in input.iter().filter(|&&n| n % 4 == 0) {
+            assert!(set.remove(&n));
+        }
+        assert_eq!(set.len(), 250);
+        for i in 0..250 {
+            assert_eq!(set[i], i * 4 + 2);
+            assert_eq!(set.rank(&(i * 4 + 2)), Some(i));
+        }
+        assert_eq!(set.count_range(100..200), 25);
+    }
+
+    #[test]
+    fn index_in_sorted_order() {
+        use splay_tree::augment::Count;
+
+        let mut counted: SplaySet<_, Count> = vec![5, 1, 4, 2, 3].into_iter().collect();
+        let mut paired: SplaySet<_, (Count, ())> = counted.iter().cloned().collect();
+        assert_eq!((0..5).map(|i| counted[i]).collect::<Vec<_>>(), [1, 2, 3, 4, 5]);
+        assert_eq!((0..5).map(|i| paired[i]).collect::<Vec<_>>(), [1, 2, 3, 4, 5]);
+
+        for n in &[1, 4] {
+            counted.remove(n);
+            paired.remove(n);
+        }
+        counted.insert(0);
+        paired.insert(0);
+        assert_eq!((0..4).map(|i| counted[i]).collect::<Vec<_>>(), [0, 2, 3, 5]);
+        assert_eq!((0..4).map(|i| paired[i]).collect::<Vec<_>>(), [0, 2, 3, 5]);
+    }
+
+    #[test]
+    fn index_by_custom_count() {
+        use splay_tree::augment::{Augment, Counted};
+
+        #[derive(Clone)]
+        struct Size(usize);
+        impl<K, V> Augment<K, V> for Size {
+            fn new(_key: &K, _value: &V) -> Self {
+                Size(1)
+            }
+            fn update(&mut self, _key: &K, _value: &V, lft: Option<&Self>, rgt: Option<&Self>) {
+                self.0 = 1 + lft.map_or(0, |a| a.0) + rgt.map_or(0, |a| a.0);
+            }
+        }
+        impl<K, V> Counted<K, V> for Size {
+            fn count(&self) -> usize {
+                self.0
+            }
+        }
+
+        let mut set: SplaySet<_, Size> = vec![3, 0, 2, 1].into_iter().collect();
+        set.remove(&2);
+        assert_eq!((0..3).map(|i| set[i]).collect::<Vec<_>>(), [0, 1, 3]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn index_out_of_bounds() {
+        use splay_tree::augment::Count;
+
+        let set: SplaySet<_, Count> = (0..10).collect();
+        set[10];
+    }
+
+    #[test]
+    fn split_off_and_append() {
+        let mut a: SplaySet<_> = (0..100).collect();
+        let mut b = a.split_off(&30);
+        assert_eq!(a.len(), 30);
+        assert_eq!(b.len(), 70);
+        assert_eq!(a.iter().cloned().collect::<Vec<_>>(), (0..30).collect::<Vec<_>>());
+        assert_eq!(b.iter().cloned().collect::<Vec<_>>(), (30..100).collect::<Vec<_>>());
+        assert!(b.contains(&99));
+        assert!(!a.contains(&30));
+
+        b.append(&mut a);
+        assert!(a.is_empty());
+        assert_eq!(b.iter().cloned().collect::<Vec<_>>(), (0..100).collect::<Vec<_>>());
+    }
+
     #[test]
     fn vec_like() {
         let mut set = SplaySet::new();
@@ -402,6 +1916,20 @@ mod set {
         let set: SplaySet<_> = input.into_iter().collect();
         let ser_set: SplaySet<_> = from_str(&to_string(&set).unwrap()).unwrap();
         assert_eq!(ser_set, set);
+        let expected = (0..1000).collect::<Vec<_>>();
+        assert_eq!(to_string(&set).unwrap(), to_string(&expected).unwrap());
+
+        #[derive(Default)]
+        struct Reverse;
+        impl ::splay_tree::compare::Compare<i32> for Reverse {
+            fn compare(&self, a: &i32, b: &i32) -> Ordering {
+                b.cmp(a)
+            }
+        }
+        let set: SplaySet<i32, (), Reverse> = from_str("[3, 2, 1]").unwrap();
+        assert_eq!(set.iter().cloned().collect::<Vec<_>>(), [3, 2, 1]);
+        assert!(from_str::<SplaySet<i32>>("[3, 2, 1]").is_err());
+        assert!(from_str::<SplaySet<i32>>("[1, 2, 2]").is_err());
     }
 }
 
@@ -460,6 +1988,32 @@ mod heap {
         assert_eq!(vec![3, 2, 1], heap.into_iter().collect::<Vec<_>>());
     }
 
+    #[test]
+    fn into_iter_drops_leftovers_once() {
+        use std::rc::Rc;
+
+        // Equal items are kept apart by their insertion order.
+        let items = (0..8).map(|i| Rc::new(i / 2)).collect::<Vec<_>>();
+        let heap: SplayHeap<_> = items.iter().cloned().collect();
+        let mut iter = heap.into_iter();
+        assert_eq!(iter.next().map(|x| *x), Some(3));
+        assert_eq!(iter.next_back().map(|x| *x), Some(0));
+        drop(iter);
+        assert!(items.iter().all(|x| Rc::strong_count(x) == 1));
+
+        let mut heap: SplayHeap<_> = items.iter().cloned().collect();
+        heap.drain().next();
+        assert!(heap.is_empty());
+        assert!(items.iter().all(|x| Rc::strong_count(x) == 1));
+    }
+
+    #[test]
+    fn double_ended_iterator() {
+        let heap = vec![2, 1, 3].into_iter().collect::<SplayHeap<_>>();
+        assert_eq!(vec![1, 2, 3], heap.iter().rev().cloned().collect::<Vec<_>>());
+        assert_eq!(vec![1, 2, 3], heap.into_iter().rev().collect::<Vec<_>>());
+    }
+
     #[test]
     fn extend() {
         let mut heap = SplayHeap::new();
@@ -471,6 +2025,135 @@ mod heap {
         );
     }
 
+    #[test]
+    fn comparator() {
+        let mut heap = SplayHeap::with_comparator(|a: &i32, b: &i32| b.cmp(a));
+        heap.extend(vec![3, 1, 2]);
+        assert_eq!(heap.peek(), Some(&1));
+        assert_eq!(heap.iter().cloned().collect::<Vec<_>>(), [1, 2, 3]);
+        assert_eq!(heap.pop(), Some(1));
+        assert_eq!(heap.pop(), Some(2));
+
+        // Items which are equal by the comparator are popped in insertion order.
+        let mut heap = SplayHeap::with_comparator(|a: &(u8, char), b: &(u8, char)| a.0.cmp(&b.0));
+        heap.extend(vec![(1, 'a'), (2, 'b'), (1, 'c'), (2, 'd')]);
+        assert_eq!(
+            heap.into_iter().collect::<Vec<_>>(),
+            [(2, 'b'), (2, 'd'), (1, 'a'), (1, 'c')]
+        );
+    }
+
+    #[test]
+    fn min_heap() {
+        use rand::{self, Rng};
+        use splay_tree::compare::Reverse;
+
+        let mut input = (0..1000).map(|i| i / 3).collect::<Vec<_>>();
+        rand::thread_rng().shuffle(&mut input);
+
+        let mut heap = SplayHeap::new_min();
+        heap.extend(input.iter().cloned());
+        assert_eq!(heap.peek(), Some(&0));
+        assert_eq!(heap.len(), 1000);
+        let mut sorted = input.clone();
+        sorted.sort();
+        assert_eq!(heap.iter().cloned().collect::<Vec<_>>(), sorted);
+        for i in 0..1000 {
+            assert_eq!(heap.pop(), Some(i / 3));
+        }
+        assert_eq!(heap.pop(), None);
+
+        let heap: SplayHeap<_, Reverse> = input.into_iter().collect();
+        assert!(heap.into_iter().eq(sorted));
+    }
+
+    #[test]
+    fn key_extractor() {
+        use rand::{self, Rng};
+        use splay_tree::compare::{ByKey, Reverse};
+
+        // The jobs with equal priorities are popped in the order they were pushed.
+        let mut rng = rand::thread_rng();
+        let jobs = (0..1000).map(|id| (rng.gen_range(0, 10), id)).collect::<Vec<_>>();
+        let mut max_first = SplayHeap::with_key(|job: &(u8, u32)| job.0);
+        let mut min_first = SplayHeap::with_comparator(Reverse(ByKey(|job: &(u8, u32)| job.0)));
+        for &job in &jobs {
+            max_first.push(job);
+            min_first.push(job);
+        }
+        let mut expected = jobs.clone();
+        expected.sort_by_key(|job| job.0);
+        assert!(min_first.into_iter().eq(expected.iter().cloned()));
+        expected.sort_by_key(|job| std::cmp::Reverse(job.0));
+        assert!(max_first.into_iter().eq(expected.iter().cloned()));
+    }
+
+    #[test]
+    fn min_and_max() {
+        use rand::{self, Rng};
+
+        // `model` is sorted in the order `pop` would return the items.
+        let mut rng = rand::thread_rng();
+        let mut heap = SplayHeap::with_key(|x: &(u8, u32)| x.0);
+        let mut model: Vec<(u8, u32)> = Vec::new();
+        for id in 0..2000 {
+            match rng.gen_range(0, 4) {
+                0 => {
+                    let max = if model.is_empty() { None } else { Some(model.remove(0)) };
+                    assert_eq!(heap.pop_max(), max);
+                }
+                1 => {
+                    assert_eq!(heap.pop_min(), model.pop());
+                }
+                _ => {
+                    let x = (rng.gen_range(0, 20), id);
+                    heap.push(x);
+                    let i = model.iter().take_while(|y| y.0 >= x.0).count();
+                    model.insert(i, x);
+                }
+            }
+            assert_eq!(heap.peek_max(), model.first());
+            assert_eq!(heap.peek_min(), model.last());
+            assert_eq!(heap.len(), model.len());
+        }
+        assert!(heap.into_iter().eq(model));
+    }
+
+    #[test]
+    fn push_bounded() {
+        use rand::{self, Rng};
+
+        let mut rng = rand::thread_rng();
+        let input = (0..1000).map(|_| rng.gen_range(0, 100)).collect::<Vec<_>>();
+        let mut heap = SplayHeap::new();
+        let mut evicted = Vec::new();
+        for &x in &input {
+            evicted.extend(heap.push_bounded(x, 10));
+            assert!(heap.len() <= 10);
+        }
+        let mut sorted = input.clone();
+        sorted.sort_by(|a, b| b.cmp(a));
+        assert_eq!(heap.iter().cloned().collect::<Vec<_>>(), &sorted[..10]);
+        evicted.sort_by(|a, b| b.cmp(a));
+        assert_eq!(evicted, &sorted[10..]);
+
+        let mut heap = SplayHeap::new();
+        assert_eq!(heap.push_bounded(1, 0), Some(1));
+        assert!(heap.is_empty());
+    }
+
+    #[test]
+    fn retain_extract_if_and_drain() {
+        let mut heap: SplayHeap<_> = vec![1, 5, 2, 5, 3, 8].into_iter().collect();
+        heap.retain(|&x| x != 2);
+        assert_eq!(heap.len(), 5);
+        assert_eq!(heap.extract_if(|&x| x == 5).count(), 2);
+        assert_eq!(heap.peek(), Some(&8));
+        heap.push(4);
+        assert_eq!(heap.drain().collect::<Vec<_>>(), [8, 4, 3, 1]);
+        assert!(heap.is_empty());
+    }
+
     #[test]
     fn large_heap() {
         use rand::{self, Rng};
@@ -484,6 +2167,79 @@ mod heap {
         }
     }
 
+    #[test]
+    fn panicking_comparator() {
+        use rand::{self, Rng};
+        use std::panic::{self, AssertUnwindSafe};
+        use super::Fuse;
+
+        let mut rng = rand::thread_rng();
+        for n in 0..50 {
+            let fuse = Fuse::default();
+            let mut items = (0..50).collect::<Vec<_>>();
+            rng.shuffle(&mut items);
+            let mut heap = SplayHeap::with_comparator(fuse.clone());
+            heap.extend(items);
+
+            fuse.arm(n);
+            let result = panic::catch_unwind(AssertUnwindSafe(|| {
+                heap.push(25);
+                assert_eq!(heap.pop(), Some(49));
+                assert_eq!(heap.pop(), Some(48));
+            }));
+            assert_eq!(result.is_err(), fuse.disarm());
+
+            assert_eq!(heap.check_invariants(), Ok(()));
+            assert_eq!(heap.iter().count(), heap.len());
+            assert!(heap.len() >= 49 && heap.len() <= 51);
+        }
+    }
+
+    #[test]
+    fn check_invariants() {
+        use rand::{self, Rng};
+
+        let mut rng = rand::thread_rng();
+        let mut heap = SplayHeap::new();
+        for _ in 0..2000 {
+            if rng.gen_range(0, 3) == 0 {
+                heap.pop();
+            } else {
+                heap.push(rng.gen_range(0, 50));
+            }
+            assert_eq!(heap.check_invariants(), Ok(()));
+        }
+        heap.retain(|&x| x % 2 == 0);
+        assert_eq!(heap.check_invariants(), Ok(()));
+    }
+
+    #[test]
+    fn splay_policies() {
+        use rand::{self, Rng};
+        use std::collections::BinaryHeap;
+        use splay_tree::policy::SplayPolicy;
+
+        let mut rng = rand::thread_rng();
+        let policies = [SplayPolicy::Semi, SplayPolicy::Threshold(3), SplayPolicy::Probability(0.3)];
+        for &policy in &policies {
+            let mut heap = SplayHeap::new();
+            heap.set_splay_policy(policy);
+            let mut binary = BinaryHeap::new();
+            for _ in 0..1000 {
+                if rng.gen_range(0, 3) == 0 {
+                    assert_eq!(heap.pop(), binary.pop());
+                } else {
+                    let x = rng.gen_range(0, 50);
+                    heap.push(x);
+                    binary.push(x);
+                }
+                assert_eq!(heap.peek(), binary.peek());
+                assert_eq!(heap.peek_min(), binary.iter().min());
+                assert_eq!(heap.check_invariants(), Ok(()));
+            }
+        }
+    }
+
     #[cfg(feature = "serde")]
     #[test]
     fn heap_serde() {
@@ -495,6 +2251,246 @@ mod heap {
 
         let heap: SplayHeap<_> = input.into_iter().collect();
         let ser_heap: SplayHeap<u64> = from_str(&to_string(&heap).unwrap()).unwrap();
+        let expected = (0..1000).rev().collect::<Vec<_>>();
+        assert_eq!(to_string(&heap).unwrap(), to_string(&expected).unwrap());
         assert_eq!(Vec::from_iter(ser_heap), Vec::from_iter(heap));
+
+        let heap: SplayHeap<u64> = from_str("[1, 3, 2, 3]").unwrap();
+        assert_eq!(Vec::from_iter(heap), [3, 3, 2, 1]);
+    }
+}
+
+mod vec {
+    use splay_tree::SplayVec;
+    use splay_tree::augment::{Lazy, Tag};
+    #[cfg(feature = "serde")]
+    use serde_json::{from_str, to_string};
+
+    #[test]
+    fn insert_and_remove() {
+        let mut vec = SplayVec::new();
+        vec.insert(0, "b");
+        vec.insert(0, "a");
+        vec.insert(2, "d");
+        vec.insert(2, "c");
+        assert_eq!(vec.len(), 4);
+        assert_eq!(vec.iter().cloned().collect::<Vec<_>>(), ["a", "b", "c", "d"]);
+        assert_eq!(vec.get(2), Some(&"c"));
+        assert_eq!(vec.get(4), None);
+        assert_eq!(vec[3], "d");
+
+        assert_eq!(vec.remove(1), "b");
+        assert_eq!(vec.pop(), Some("d"));
+        assert_eq!(vec.iter().cloned().collect::<Vec<_>>(), ["a", "c"]);
+        assert_eq!(vec.iter().rev().cloned().collect::<Vec<_>>(), ["c", "a"]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn insert_out_of_bounds() {
+        let mut vec: SplayVec<_> = (0..3).collect();
+        vec.insert(4, 0);
+    }
+
+    #[test]
+    #[should_panic]
+    fn remove_out_of_bounds() {
+        let mut vec: SplayVec<_> = (0..3).collect();
+        vec.remove(3);
+    }
+
+    #[test]
+    #[cfg_attr(feature = "check-invariants", ignore)]
+    fn index_width() {
+        let mut vec: SplayVec<_, (), u16> = SplayVec::default();
+        for i in 0..65535 {
+            vec.push(i);
+        }
+        assert_eq!(vec.len(), 65535);
+        assert_eq!(vec.remove(0), 0);
+        vec.insert(100, 0);
+        assert_eq!(vec.get(100), Some(&0));
+        assert_eq!(vec.iter().rev().next(), Some(&65534));
+
+        let mut vec: SplayVec<_, (), u64> = SplayVec::default();
+        vec.push("a");
+        vec.insert(0, "b");
+        assert_eq!(vec.into_iter().collect::<Vec<_>>(), ["b", "a"]);
+    }
+
+    #[test]
+    fn random_operations() {
+        use rand::{self, Rng};
+
+        let mut rng = rand::thread_rng();
+        let mut vec = SplayVec::new();
+        let mut expected = Vec::new();
+        for i in 0..2000 {
+            let len = expected.len();
+            match rng.gen_range(0, 6) {
+                0 | 1 => {
+                    let at = rng.gen_range(0, len + 1);
+                    vec.insert(at, i);
+                    expected.insert(at, i);
+                }
+                2 if len > 0 => {
+                    let at = rng.gen_range(0, len);
+                    assert_eq!(vec.remove(at), expected.remove(at));
+                }
+                3 if len > 0 => {
+                    let at = rng.gen_range(0, len);
+                    assert_eq!(vec.get(at), Some(&expected[at]));
+                    *vec.get_mut(at).unwrap() += 1;
+                    expected[at] += 1;
+                    assert_eq!(vec[at], expected[at]);
+                }
+                4 => {
+                    let at = rng.gen_range(0, len + 1);
+                    let mut other = vec.split_off(at);
+                    let mut expected_other = expected.split_off(at);
+                    assert_eq!(other.len(), expected_other.len());
+                    if rng.gen() {
+                        other.append(&mut vec);
+                        expected_other.append(&mut expected);
+                    }
+                    vec.append(&mut other);
+                    expected.append(&mut expected_other);
+                    assert!(other.is_empty());
+                }
+                _ => {
+                    assert_eq!(vec.pop(), expected.pop());
+                }
+            }
+            assert_eq!(vec.len(), expected.len());
+            assert_eq!(vec.check_invariants(), Ok(()));
+        }
+        assert_eq!(vec.iter().cloned().collect::<Vec<_>>(), expected);
+        assert_eq!(
+            vec.iter().rev().cloned().collect::<Vec<_>>(),
+            expected.iter().rev().cloned().collect::<Vec<_>>()
+        );
+        assert_eq!(vec.into_iter().collect::<Vec<_>>(), expected);
+    }
+
+    #[derive(Clone)]
+    struct Add(i64);
+    impl<K> Tag<K, i64> for Add {
+        fn apply(&self, _key: &K, value: &mut i64) {
+            *value += self.0;
+        }
+        fn compose(&mut self, later: &Self) {
+            self.0 += later.0;
+        }
+    }
+
+    #[test]
+    fn reverse_and_update_range() {
+        use rand::{self, Rng};
+
+        let mut rng = rand::thread_rng();
+        let mut vec: SplayVec<_, Lazy<Add>> = SplayVec::default();
+        let mut expected = Vec::new();
+        for i in 0..3000 {
+            let len = expected.len();
+            let start = rng.gen_range(0, len + 1);
+            let end = rng.gen_range(start, len + 1);
+            match rng.gen_range(0, 7) {
+                0 | 1 => {
+                    vec.insert(start, i);
+                    expected.insert(start, i);
+                }
+                2 if len > 0 => {
+                    let at = rng.gen_range(0, len);
+                    assert_eq!(vec.remove(at), expected.remove(at));
+                }
+                3 if len > 0 => {
+                    let at = rng.gen_range(0, len);
+                    assert_eq!(vec.get(at), Some(&expected[at]));
+                }
+                4 => {
+                    vec.reverse(start..end);
+                    expected[start..end].reverse();
+                }
+                5 => {
+                    let v = rng.gen_range(-100, 100);
+                    vec.update_range(start..end, Add(v));
+                    for x in &mut expected[start..end] {
+                        *x += v;
+                    }
+                }
+                _ => {
+                    let mut other = vec.split_off(start);
+                    other.reverse(..);
+                    vec.append(&mut other);
+                    expected[start..].reverse();
+                }
+            }
+            assert_eq!(vec.len(), expected.len());
+            assert_eq!(vec.check_invariants(), Ok(()));
+        }
+        assert_eq!(
+            vec.iter_mut().map(|x| *x).collect::<Vec<_>>(),
+            expected
+        );
+        vec.reverse(..);
+        assert_eq!(
+            vec.into_iter().collect::<Vec<_>>(),
+            expected.into_iter().rev().collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn read_with_pending_reversals() {
+        let mut vec: SplayVec<_> = (0..10).collect();
+        vec.reverse(2..8);
+        vec.reverse(..5);
+        let mut expected: Vec<_> = (0..10).collect();
+        expected[2..8].reverse();
+        expected[..5].reverse();
+        assert_eq!(vec.iter().cloned().collect::<Vec<_>>(), expected);
+        assert_eq!(vec.iter().rev().cloned().collect::<Vec<_>>(),
+                   expected.iter().rev().cloned().collect::<Vec<_>>());
+        for (i, x) in expected.iter().enumerate() {
+            assert_eq!(vec[i], *x);
+        }
+        let flushed: SplayVec<_> = expected.iter().cloned().collect();
+        assert_eq!(vec, flushed);
+        assert_eq!(::hash(&vec), ::hash(&flushed));
+        #[cfg(feature = "serde")]
+        assert_eq!(to_string(&vec).unwrap(), to_string(&flushed).unwrap());
+    }
+
+    #[test]
+    #[should_panic]
+    fn reverse_out_of_bounds() {
+        let mut vec: SplayVec<_> = (0..3).collect();
+        vec.reverse(1..4);
+    }
+
+    #[test]
+    fn eq() {
+        let mut a: SplayVec<_> = (1..4).collect();
+        let mut b: SplayVec<_> = vec![3].into_iter().collect();
+        b.insert(0, 2);
+        b.insert(0, 1);
+        assert_eq!(a, b);
+
+        a.remove(0);
+        assert!(a > b);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn vec_serde() {
+        let mut vec: SplayVec<_> = (0..100).collect();
+        vec.insert(50, 1000);
+        let ser_vec: SplayVec<u64> = from_str(&to_string(&vec).unwrap()).unwrap();
+        assert_eq!(ser_vec, vec);
+        let expected = vec.clone().into_iter().collect::<Vec<_>>();
+        assert_eq!(to_string(&vec).unwrap(), to_string(&expected).unwrap());
+
+        let mut vec: SplayVec<u64> = from_str("[3, 1, 2]").unwrap();
+        vec.insert(1, 0);
+        assert_eq!(vec.into_iter().collect::<Vec<_>>(), [3, 0, 1, 2]);
     }
 }